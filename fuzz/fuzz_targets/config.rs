@@ -0,0 +1,17 @@
+//! Fuzz `Config::load_from`: any file content must yield a config, never a panic
+
+#![no_main]
+
+use std::fs;
+
+use grxxt::config::Config;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let path = std::env::temp_dir().join(format!("grxxt-fuzz-{}.toml", std::process::id()));
+    if fs::write(&path, data).is_err() {
+        return;
+    }
+    let config = Config::load_from(&path);
+    let _ = config.session_command();
+});
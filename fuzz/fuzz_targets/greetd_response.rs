@@ -0,0 +1,16 @@
+//! Fuzz greetd response decoding: the fake daemon replays arbitrary bytes and
+//! every client call must fail cleanly instead of panicking or hanging
+
+#![no_main]
+
+use grxxt::fake;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(mut client) = fake::serve(data.to_vec()) else {
+        return;
+    };
+    let _ = client.create_session("fuzz");
+    let _ = client.post_auth_response(Some("secret".into()));
+    let _ = client.start_session(vec!["true".into()], Vec::new());
+});
@@ -10,6 +10,10 @@ sudo install -Dm755 target/release/grxxt /usr/local/bin/grxxt
 # Install config
 sudo install -Dm644 grxxt.toml /etc/greetd/grxxt.toml
 
+# Install runtime state directory
+sudo install -Dm644 grxxt-tmpfiles.conf /usr/lib/tmpfiles.d/grxxt.conf
+sudo systemd-tmpfiles --create /usr/lib/tmpfiles.d/grxxt.conf
+
 # Backup existing greetd config if present
 if [[ -f /etc/greetd/config.toml ]]; then
     sudo cp /etc/greetd/config.toml /etc/greetd/config.toml.bak
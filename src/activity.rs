@@ -0,0 +1,81 @@
+//! Activity on the input devices themselves
+//!
+//! The terminal only reports keys typed into it, so a moved mouse or keys
+//! pressed on another VT leave the greeter thinking nobody is there. This
+//! reads /dev/input/event* directly and reports that something happened,
+//! never what: the events are thrown away unread. Devices grxxt may not
+//! read (the greeter user needs the `input` group) are skipped.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const INPUT_DIR: &str = "/dev/input";
+
+/// Report a busy device at most this often; a moving mouse sends hundreds
+/// of events a second
+const THROTTLE: Duration = Duration::from_secs(1);
+
+/// Room for a batch of `struct input_event`s
+const BUFFER: usize = 24 * 64;
+
+/// Call `notify` from a background thread whenever an input device reports
+/// something, until it returns `false`. Returns `false` when no device
+/// could be opened.
+pub fn watch(notify: impl Fn() -> bool + Clone + Send + 'static) -> bool {
+    let Ok(entries) = fs::read_dir(INPUT_DIR) else {
+        return false;
+    };
+    let devices: Vec<File> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| is_event_device(path))
+        .filter_map(|path| File::open(path).ok())
+        .collect();
+    let watching = !devices.is_empty();
+    for device in devices {
+        let notify = notify.clone();
+        thread::spawn(move || read_device(device, &notify));
+    }
+    watching
+}
+
+/// `eventN`; the `mouseN` and `js` interfaces repeat the same devices
+fn is_event_device(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix("event"))
+        .is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Block on the device until it goes away or `notify` wants no more
+fn read_device(mut device: File, notify: &impl Fn() -> bool) {
+    let mut buffer = [0; BUFFER];
+    let mut last: Option<Instant> = None;
+    while matches!(device.read(&mut buffer), Ok(read) if read > 0) {
+        if last.is_some_and(|at| at.elapsed() < THROTTLE) {
+            continue;
+        }
+        last = Some(Instant::now());
+        if !notify() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_event_device() {
+        assert!(is_event_device(Path::new("/dev/input/event0")));
+        assert!(is_event_device(Path::new("/dev/input/event12")));
+        assert!(!is_event_device(Path::new("/dev/input/mice")));
+        assert!(!is_event_device(Path::new("/dev/input/mouse0")));
+        assert!(!is_event_device(Path::new("/dev/input/event")));
+        assert!(!is_event_device(Path::new("/dev/input/by-id")));
+    }
+}
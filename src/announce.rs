@@ -0,0 +1,156 @@
+//! Spoken announcements for screen reader users
+//!
+//! The console has no accessibility bus, so with `[accessibility] announce`
+//! grxxt speaks for itself through a speech command (speech-dispatcher's
+//! `spd-say` by default): the name of the field that got focus and messages
+//! as they appear, e.g. "Authentication failed. password". Typing doesn't
+//! speak, and changes are spoken once they settle for a moment, so a burst
+//! of them (Tab pressed twice) says only where it ended.
+
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::AccessibilityConfig;
+
+/// How long the screen must hold still before it is spoken
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// What the screen tells someone who can't see it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Screen {
+    /// Errors, hints and questions, most important first
+    pub messages: Vec<String>,
+    /// Name of the focused field
+    pub field: String,
+}
+
+impl Screen {
+    /// Focus moved or a message appeared since `before`; messages going
+    /// away (an error cleared by typing) aren't news
+    fn news_since(&self, before: &Self) -> bool {
+        self.field != before.field
+            || self
+                .messages
+                .iter()
+                .any(|message| !before.messages.contains(message))
+    }
+
+    fn text(&self) -> String {
+        let mut parts = self.messages.clone();
+        parts.push(self.field.clone());
+        parts.join(". ")
+    }
+}
+
+#[derive(Debug)]
+pub struct Announcer {
+    command: Vec<String>,
+    /// The screen as last seen
+    current: Screen,
+    /// When the last news came
+    changed_at: Instant,
+    /// News that wasn't spoken yet
+    pending: bool,
+}
+
+impl Announcer {
+    /// `None` unless announcements are on and the command can be split
+    pub fn new(config: &AccessibilityConfig, now: Instant) -> Option<Self> {
+        let command = shell_words::split(&config.command).ok()?;
+        (config.announce && !command.is_empty()).then(|| Self {
+            command,
+            current: Screen::default(),
+            changed_at: now,
+            pending: false,
+        })
+    }
+
+    /// The screen now shows `screen`. Returns `true` when that is news,
+    /// to be spoken after [`DEBOUNCE`].
+    pub fn update(&mut self, screen: Screen, now: Instant) -> bool {
+        if screen == self.current {
+            return false;
+        }
+        let news = screen.news_since(&self.current);
+        self.current = screen;
+        if news {
+            self.pending = true;
+            self.changed_at = now;
+        }
+        news
+    }
+
+    /// Text to speak now: news that held still long enough
+    pub fn due(&mut self, now: Instant) -> Option<String> {
+        if !self.pending || now.duration_since(self.changed_at) < DEBOUNCE {
+            return None;
+        }
+        self.pending = false;
+        Some(self.current.text())
+    }
+
+    /// Speak `text` in the background
+    pub fn speak(&self, text: String) {
+        let Some((program, args)) = self.command.split_first() else {
+            return;
+        };
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .arg(text)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // Waited for on its own thread so it doesn't linger as a zombie
+        thread::spawn(move || command.status());
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    fn screen(messages: &[&str], field: &str) -> Screen {
+        Screen {
+            messages: messages.iter().map(ToString::to_string).collect(),
+            field: field.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_announcements() {
+        let time = Timeline::new();
+        let config = AccessibilityConfig {
+            announce: true,
+            ..AccessibilityConfig::default()
+        };
+        let mut announcer = Announcer::new(&config, time.start).unwrap();
+
+        // Tab twice in quick succession: only where it ended is spoken
+        assert!(announcer.update(screen(&[], "username"), time.millis(0)));
+        assert!(announcer.update(screen(&[], "password"), time.millis(100)));
+        assert_eq!(announcer.due(time.millis(300)), None);
+        assert_eq!(announcer.due(time.millis(400)).as_deref(), Some("password"));
+        assert_eq!(announcer.due(time.millis(800)), None);
+
+        // A failed login, then typing clears the error: not news
+        let failed = screen(&["Authentication failed"], "password");
+        assert!(announcer.update(failed, time.millis(1000)));
+        assert_eq!(
+            announcer.due(time.millis(1300)).as_deref(),
+            Some("Authentication failed. password")
+        );
+        assert!(!announcer.update(screen(&[], "password"), time.millis(1400)));
+        assert_eq!(announcer.due(time.millis(2000)), None);
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let config = AccessibilityConfig::default();
+        assert!(Announcer::new(&config, Instant::now()).is_none());
+    }
+}
@@ -1,139 +1,1322 @@
 //! Application state management for the TUI greeter
 
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::fmt::Write as _;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::announce::{Announcer, Screen};
+use crate::audit::{self, FailureTracker};
+use crate::auth::{self, AuthError, Outcome, Prompt, Rejection};
 use crate::avatar::Avatar;
-use crate::config::Config;
+use crate::bindings::{Action as KeyAction, Keymap};
+use crate::burn_in::BurnIn;
+use crate::clock::Clock;
+use crate::compose::Compose;
+use crate::config::{
+    AvatarCaption, Backend, BootEntry, CheckConfig, Config, Direction, EnvironmentConfig,
+    FallbackConfig, FieldsConfig, PasswordMask, PowerConfig, ResourcesConfig, SessionLogConfig,
+    SuccessConfig, UnlockConfig,
+};
+use crate::console;
+use crate::debug;
+use crate::effects::Screensaver;
+use crate::environment;
+use crate::events::{Event, Events};
+use crate::fade::Fade;
+use crate::focus::{Context, Field, FocusStack};
 use crate::greetd;
-use crate::theme::Theme;
+use crate::greeting::Greeting;
+use crate::handoff::Handoff;
+use crate::idle::IdleSuspend;
+use crate::kana::{Kana, Script};
+use crate::kerberos;
+use crate::locale::Locale;
+use crate::metrics::Metrics;
+use crate::notices::Rotation;
+use crate::policy::Policy;
+use crate::power::{self, Action, Hold, HoldState, Pending};
+use crate::rfkill;
+use crate::scroll::Scroll;
+use crate::sessions::{self, Session};
+use crate::state;
+use crate::status::Status;
+use crate::sticky::StickyKeys;
+use crate::submission::Submission;
+use crate::theme::{Palette, Theme};
+use crate::typewriter::Typewriter;
+use crate::undo::Undo;
+use crate::unlock;
+use crate::validate::{self, Hint};
+
+/// Width of the mask in `PasswordMask::Fixed` mode
+const FIXED_MASK_LEN: usize = 8;
 
-/// Which input field is currently focused
+/// Mask length range in `PasswordMask::Random` mode
+const RANDOM_MASK_LEN: std::ops::RangeInclusive<usize> = 4..=16;
+
+/// How the username field is drawn
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Focus {
-    Username,
-    Password,
+pub enum UsernameField {
+    Shown,
+    /// Password-first mode: a single line naming the remembered user
+    Collapsed,
+    /// The remembered user, read-only until Ctrl+U so a stray key doesn't
+    /// turn it into an unknown user
+    Locked,
 }
 
 /// Application state
 pub struct App {
     pub username: String,
     pub password: String,
+    pub password_mask: PasswordMask,
+    /// Current mask length in random mode, re-rolled on every edit
+    mask_len: usize,
     pub error: Option<String>,
-    pub authenticating: bool,
-    pub focus: Focus,
-    pub session_cmd: String,
+    /// How long errors stay on screen; `None` until the next key
+    error_timeout: Option<Duration>,
+    /// Typing leaves errors alone
+    keep_errors: bool,
+    /// The error last drawn and when it first was
+    error_shown: Option<(String, Instant)>,
+    /// Problem or note under one of the fields
+    pub hint: Option<Hint>,
+    /// Field contents before clears and runs of Backspace, for Ctrl+Z
+    undo: Undo,
+    /// Where the login attempt stands; once granted the success
+    /// confirmation is on screen
+    pub submission: Submission,
+    /// Informational messages from the auth stack, shown with the confirmation
+    pub notices: Vec<String>,
+    /// Several notices on screen take turns
+    notice_rotation: Rotation,
+    pub success: SuccessConfig,
+    /// Rows of the `[success] logo`, shown full screen after login
+    pub splash: Option<Vec<String>>,
+    /// Login accepted, counting down to the session start
+    pub handoff: Option<Handoff>,
+    /// How long the countdown runs; zero starts sessions right away
+    handoff_countdown: Duration,
+    /// Form field and any modals above it; keys go to the top context
+    pub focus: FocusStack,
+    pub username_field: UsernameField,
+    /// Custom placeholders and labels of the form fields
+    pub fields: FieldsConfig,
+    /// Follow-up question from the auth stack; the password field answers it
+    pub prompt: Option<Prompt>,
+    /// Last systemd-homed lookup: username and whether homed manages it
+    /// (`false` until the answer is in)
+    homed: Option<(String, bool)>,
+    /// Username whose homed lookup waits to be started in the background
+    homed_lookup: Option<String>,
+    /// Last full name lookup for the avatar caption: username and GECOS name
+    full_name: Option<(String, Option<String>)>,
+    /// Selectable sessions; the default session always comes first
+    pub sessions: Vec<Session>,
+    pub session_index: usize,
+    /// Session command that died right after the last login(s), and how
+    /// many times in a row
+    crashes: Option<(String, u32)>,
+    pub session_log: SessionLogConfig,
+    pub environment: EnvironmentConfig,
+    /// Opt-in helper that gets the password after login (encrypted homes)
+    pub unlock: Option<UnlockConfig>,
+    pub backend: auth::Shared,
+    /// When the last attempt went to the backend, for the spinner
+    pub sent_at: Instant,
+    /// The attempt under way was cancelled with Esc
+    abandoned: Arc<AtomicBool>,
+    /// Number of the latest attempt sent to the backend; verdicts on older
+    /// ones are stale
+    attempt: u64,
+    /// Event stream for integrators, when configured
+    events: Option<Events>,
+    /// Textfile metrics, when configured
+    metrics: Option<Metrics>,
+    /// Recent failures, for brute-force warnings in the audit log
+    failures: FailureTracker,
+    /// Failed attempts for the current username
+    attempts: u32,
+    max_attempts: u32,
     pub theme: Theme,
+    pub clock: Clock,
+    /// Line above the form, when enabled
+    pub greeting: Option<Greeting>,
+    /// Typing out of the greeting, when enabled; skipped in low-resource mode
+    pub typewriter: Option<Typewriter>,
     pub avatar: Option<Avatar>,
+    pub status: Status,
+    /// Speaks focus changes and messages, when enabled
+    pub announcer: Option<Announcer>,
+    /// Looks for greetd's socket until it is up (greetd backend)
+    pub greetd_wait: Option<greetd::Wait>,
+    /// Health checks run in the background
+    pub checks: Vec<CheckConfig>,
+    /// Disk and memory thresholds checked at startup
+    pub resources: ResourcesConfig,
+    /// Scroll position of the failed units list
+    pub units_scroll: Scroll,
+    /// Power action in progress; input is frozen until it resolves
+    pub power: Option<Pending>,
+    /// Power actions that need a confirmation
+    confirm_power: Vec<Action>,
+    /// Power key being held to confirm its action
+    pub hold: Option<Hold>,
+    /// How power actions are run
+    power_options: PowerConfig,
+    /// Index into `[[power.boot]]` of the system the next reboot starts
+    boot_entry: Option<usize>,
+    /// Key table, with the config's remappings applied
+    pub keymap: Keymap,
+    /// What the administrator lets users do besides logging in
+    pub policy: Policy,
+    /// Sequential modifier entry, when enabled
+    pub sticky: Option<StickyKeys>,
+    /// Burn-in mitigation, when enabled
+    pub burn_in: Option<BurnIn>,
+    /// Suspend after sitting idle, when enabled
+    pub idle: Option<IdleSuspend>,
+    /// Fade-in, when enabled; skipped in low-resource mode
+    pub fade: Option<Fade>,
+    /// Idle background effect, when enabled; skipped in low-resource mode
+    pub screensaver: Option<Screensaver>,
+    /// Compose sequence in progress
+    pub compose: Compose,
+    /// Romaji to kana transliteration of the username, when switched on
+    pub kana: Option<Kana>,
+    /// Whether to save the next frame as a PNG, asked for with F12; `None`
+    /// outside demo mode
+    pub screenshot: Option<bool>,
     pub should_quit: bool,
 }
 
 impl App {
-    /// Create a new application with the given configuration
-    pub fn new(config: &Config) -> Self {
+    /// Create a new application with the given configuration and policy
+    pub fn new(config: &Config, policy: Policy) -> Self {
+        if config.backend == Backend::Demo {
+            state::disable();
+        }
+        // Low-resource mode skips image decoding and terminal protocol probing
         let avatar = config
             .avatar
             .as_deref()
+            .filter(|_| !config.low_resource)
             .and_then(crate::avatar::load);
 
-        Self {
-            username: String::new(),
+        let sessions = session_list(config, &policy);
+        let min_uptime = Duration::from_secs(config.fallback.min_uptime_secs);
+        let crashes = state::load_launch()
+            .filter(|launch| launch.crashed(min_uptime))
+            .map(|launch| (launch.command, launch.failures + 1));
+
+        let (username, username_field) = initial_username(config);
+        let focus = FocusStack::new(if username.is_empty() {
+            Field::Username
+        } else {
+            Field::Password
+        });
+        // Suspending when idle is still suspending
+        let idle = IdleSuspend::new(&config.power.idle, Instant::now())
+            .filter(|_| policy.allows_power(Action::Suspend));
+
+        let locale = Locale::detect(config.locale.as_deref());
+        let mut app = Self {
+            username,
             password: String::new(),
+            password_mask: config.password_mask,
+            mask_len: 0,
             error: None,
-            authenticating: false,
-            focus: Focus::Username,
-            session_cmd: config.session.clone(),
-            theme: Theme::from(&config.theme),
+            error_timeout: config.error_timeout(),
+            keep_errors: config.keep_errors_while_typing,
+            error_shown: None,
+            hint: None,
+            undo: Undo::default(),
+            submission: Submission::default(),
+            notices: Vec::new(),
+            notice_rotation: Rotation::new(config.notice_rotate()),
+            success: config.success.clone(),
+            splash: config.success.logo.as_deref().and_then(load_logo),
+            handoff: None,
+            handoff_countdown: Duration::from_secs(config.handoff.countdown),
+            focus,
+            username_field,
+            fields: config.fields.clone(),
+            prompt: None,
+            homed: None,
+            homed_lookup: None,
+            full_name: None,
+            sessions,
+            session_index: 0,
+            crashes,
+            session_log: config.session_log.clone(),
+            environment: config.environment.clone(),
+            unlock: config.unlock.clone(),
+            backend: auth::from_config(config),
+            sent_at: Instant::now(),
+            abandoned: Arc::default(),
+            attempt: 0,
+            events: config.events_socket.as_deref().and_then(Events::bind),
+            metrics: config.metrics_file.as_deref().map(Metrics::open),
+            failures: FailureTracker::default(),
+            attempts: 0,
+            max_attempts: config.max_attempts(),
+            theme: load_theme(config, locale),
+            clock: Clock::new(config.clock.clone(), locale),
+            greeting: Greeting::new(&config.greeting, locale),
+            typewriter: Typewriter::new(&config.greeting, Instant::now())
+                .filter(|_| !config.low_resource),
             avatar,
+            status: Status::default(),
+            announcer: Announcer::new(&config.accessibility, Instant::now()),
+            greetd_wait: (config.backend == Backend::Greetd)
+                .then(|| greetd::Wait::new(config.socket.clone())),
+            checks: config.checks.clone(),
+            resources: config.resources.clone(),
+            units_scroll: Scroll::default(),
+            power: None,
+            confirm_power: config.confirm_power.clone(),
+            hold: None,
+            power_options: config.power.clone(),
+            boot_entry: None,
+            keymap: Keymap::new(&config.keys),
+            policy,
+            sticky: config.sticky_keys.then(StickyKeys::default),
+            burn_in: BurnIn::new(&config.display, Instant::now()),
+            idle,
+            fade: Fade::new(&config.display, Instant::now()).filter(|_| !config.low_resource),
+            screensaver: Screensaver::new(&config.display, Instant::now())
+                .filter(|_| !config.low_resource),
+            compose: Compose::default(),
+            kana: None,
+            screenshot: (config.backend == Backend::Demo).then_some(false),
             should_quit: false,
+        };
+        if app.focus.field() == Field::Password {
+            app.look_up_user();
+        }
+        app.check_crash_loop(&config.fallback);
+        app
+    }
+
+    /// A session that keeps dying right after login brings the greeter
+    /// straight back; past the budget warn and select the fallback session
+    fn check_crash_loop(&mut self, fallback: &FallbackConfig) {
+        let Some((ref command, failures)) = self.crashes else {
+            return;
+        };
+        if failures < fallback.max_failures {
+            return;
+        }
+        let name = self
+            .sessions
+            .iter()
+            .find(|session| session.command == *command)
+            .map_or_else(
+                || Session::from_command(command).name,
+                |session| session.name.clone(),
+            );
+        let mut warning = format!("{name} failed to start {failures} times");
+        if let Some(fallback) = fallback.session.as_ref().filter(|_| self.policy.fallback) {
+            let index = self
+                .sessions
+                .iter()
+                .position(|session| session.command == *fallback)
+                .unwrap_or_else(|| {
+                    self.sessions.push(Session::from_command(fallback));
+                    self.sessions.len() - 1
+                });
+            self.session_index = index;
+            write!(warning, ", using {}", self.sessions[index].name).ok();
+        }
+        self.error = Some(warning);
+    }
+
+    /// The screen was drawn at `now`. Returns how long until the error on
+    /// it goes away, when it just appeared.
+    pub fn error_drawn(&mut self, now: Instant) -> Option<Duration> {
+        if self.error.as_ref() == self.error_shown.as_ref().map(|(error, _)| error) {
+            return None;
+        }
+        self.error_shown = self.error.clone().map(|error| (error, now));
+        self.error.as_ref().and(self.error_timeout)
+    }
+
+    /// The screen was drawn at `now`. Returns how long until the next
+    /// notice, when several just appeared and take turns.
+    pub fn notices_drawn(&mut self, now: Instant) -> Option<Duration> {
+        let notices = match (self.submission, &self.prompt) {
+            (Submission::Granted, _) => self.notices.clone(),
+            (_, Some(prompt)) => prompt.notices.clone(),
+            (_, None) => Vec::new(),
+        };
+        self.notice_rotation.drawn(&notices, now)
+    }
+
+    /// Show the next notice when it is due; returns how long until the one
+    /// after
+    pub fn rotate_notices(&mut self, now: Instant) -> Option<Duration> {
+        self.notice_rotation.advance(now)
+    }
+
+    /// `notices` as shown: one at a time with its position while they take
+    /// turns, otherwise all of them
+    #[must_use]
+    pub fn shown_notices<'a>(&self, notices: &'a [String]) -> Vec<Cow<'a, str>> {
+        self.notice_rotation.show(notices)
+    }
+
+    /// Take the error away once it has been on screen for the timeout
+    pub fn expire_error(&mut self, now: Instant) {
+        let expired = self.error_timeout.zip(self.error_shown.as_ref()).is_some_and(
+            |(timeout, (error, since))| {
+                self.error.as_ref() == Some(error) && now.duration_since(*since) >= timeout
+            },
+        );
+        if expired {
+            self.error = None;
         }
     }
 
     /// Handle character input for the focused field
     pub fn input_char(&mut self, c: char) {
-        self.error = None;
-        match self.focus {
-            Focus::Username => self.username.push(c),
-            Focus::Password => self.password.push(c),
+        if !self.keep_errors {
+            self.error = None;
+        }
+        match self.focus.field() {
+            Field::Username if self.username_locked() => self.hint_unlock(),
+            Field::Username => {
+                self.undo.typed();
+                self.cancel_prompt();
+                self.attempts = 0;
+                self.username.push(c);
+                state::save_draft(&self.username);
+                self.check_username();
+            }
+            Field::Password => {
+                self.undo.typed();
+                self.password.push(c);
+                self.password_edited();
+            }
         }
     }
 
     /// Handle backspace for the focused field
     pub fn backspace(&mut self) {
-        match self.focus {
-            Focus::Username => {
+        match self.focus.field() {
+            Field::Username if self.username_locked() => self.hint_unlock(),
+            Field::Username => {
+                self.undo.erase(Field::Username, &self.username);
+                self.cancel_prompt();
+                self.attempts = 0;
                 self.username.pop();
+                state::save_draft(&self.username);
+                self.check_username();
             }
-            Focus::Password => {
+            Field::Password => {
+                self.undo.erase(Field::Password, &self.password);
                 self.password.pop();
+                self.password_edited();
             }
         }
     }
 
-    /// Switch focus to the next field
-    pub const fn next_field(&mut self) {
-        self.focus = match self.focus {
-            Focus::Username => Focus::Password,
-            Focus::Password => Focus::Username,
+    /// Empty the focused field, which Ctrl+Z can take back
+    pub fn clear_field(&mut self) {
+        match self.focus.field() {
+            Field::Username if self.username_locked() => self.hint_unlock(),
+            Field::Username => {
+                self.undo.clear(Field::Username, &self.username);
+                self.set_username(String::new());
+            }
+            Field::Password => {
+                self.undo.clear(Field::Password, &self.password);
+                self.password.clear();
+                self.password_edited();
+            }
+        }
+    }
+
+    /// Bring back what a field held before the last clear or run of
+    /// Backspace, and focus it
+    pub fn undo(&mut self) {
+        let Some((field, content)) = self.undo.pop() else {
+            return;
+        };
+        self.focus.set_field(field);
+        match field {
+            Field::Username => {
+                self.username_field = UsernameField::Shown;
+                self.set_username(content);
+            }
+            Field::Password => {
+                self.password = content;
+                self.password_edited();
+            }
+        }
+    }
+
+    /// Replace the username as if it had been typed
+    fn set_username(&mut self, username: String) {
+        self.cancel_prompt();
+        self.attempts = 0;
+        self.username = username;
+        state::save_draft(&self.username);
+        self.check_username();
+    }
+
+    /// After an edit of the password: a new mask length, and "Password
+    /// required" is answered. Hints on the username stay.
+    fn password_edited(&mut self) {
+        self.reroll_mask();
+        self.hint = self.hint.take().filter(|hint| hint.field == Field::Username);
+    }
+
+    /// Show what's wrong with the username as typed, or nothing
+    fn check_username(&mut self) {
+        self.hint = validate::username(&self.username)
+            .map(|problem| Hint::error(Field::Username, problem));
+    }
+
+    /// Trim the username before it's sent, noting it under the field.
+    /// Returns whether the name can be sent.
+    fn finish_username(&mut self) -> bool {
+        let trimmed = self.username.trim();
+        if trimmed.len() != self.username.len() {
+            self.username = trimmed.to_string();
+            state::save_draft(&self.username);
+            self.hint = Some(Hint::note(Field::Username, "Whitespace trimmed"));
+        }
+        if self.username.is_empty() {
+            self.hint = Some(Hint::error(Field::Username, "Username required"));
+        } else if let Some(problem) = validate::username(&self.username) {
+            self.hint = Some(Hint::error(Field::Username, problem));
+        } else {
+            return true;
+        }
+        self.focus.set_field(Field::Username);
+        false
+    }
+
+    /// Password field contents as drawn; visible prompts echo the answer
+    #[must_use]
+    pub fn masked_password(&self) -> String {
+        if self.prompt.as_ref().is_some_and(|prompt| !prompt.secret) {
+            return self.password.clone();
+        }
+        let len = match self.password_mask {
+            _ if self.password.is_empty() => 0,
+            PasswordMask::Char => self.password.chars().count(),
+            PasswordMask::Fixed => FIXED_MASK_LEN,
+            PasswordMask::Random => self.mask_len,
+        };
+        "*".repeat(len)
+    }
+
+    /// Placeholder of the empty username field
+    #[must_use]
+    pub fn username_placeholder(&self) -> &str {
+        self.fields.username.placeholder.as_deref().unwrap_or("username")
+    }
+
+    /// The form as a screen reader user should hear it: the question of a
+    /// confirmation, errors and hints, then the focused field's name
+    #[must_use]
+    pub fn screen(&self) -> Screen {
+        let mut messages = Vec::new();
+        if let Context::Confirm(action) = self.focus.top() {
+            messages.push(action.question().to_string());
+        }
+        messages.extend(self.error.clone());
+        messages.extend(self.hint.as_ref().map(|hint| hint.text.to_string()));
+        if let Some(ref prompt) = self.prompt {
+            messages.extend(prompt.notices.iter().cloned());
+        }
+        let field = match (self.focus.field(), &self.prompt) {
+            (Field::Username, _) => self
+                .fields
+                .username
+                .label
+                .clone()
+                .unwrap_or_else(|| self.username_placeholder().to_string()),
+            (Field::Password, Some(prompt)) => prompt.message.clone(),
+            (Field::Password, None) => self
+                .fields
+                .password
+                .label
+                .clone()
+                .unwrap_or_else(|| self.password_placeholder().into_owned()),
+        };
+        Screen { messages, field }
+    }
+
+    /// Placeholder of the empty password field; follow-up prompts are
+    /// answers, and homed users learn the password also unlocks their home
+    #[must_use]
+    pub fn password_placeholder(&self) -> Cow<'_, str> {
+        if self.prompt.is_some() {
+            return Cow::Borrowed("answer");
+        }
+        let placeholder = self.fields.password.placeholder.as_deref().unwrap_or("password");
+        if self.is_homed() {
+            Cow::Owned(format!("{placeholder} (unlocks home)"))
+        } else {
+            Cow::Borrowed(placeholder)
+        }
+    }
+
+    /// Pick a new random mask length (std's per-process random hash keys
+    /// are plenty for hiding a length)
+    fn reroll_mask(&mut self) {
+        if self.password_mask == PasswordMask::Random {
+            let span = RANDOM_MASK_LEN.end() - RANDOM_MASK_LEN.start() + 1;
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_usize(self.password.len());
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "value is reduced modulo a small span first"
+            )]
+            let offset = (hasher.finish() % span as u64) as usize;
+            self.mask_len = RANDOM_MASK_LEN.start() + offset;
+        }
+    }
+
+    /// Username as drawn, with romaji that isn't kana yet
+    #[must_use]
+    pub fn username_display(&self) -> Cow<'_, str> {
+        match self.kana {
+            Some(ref kana) if !kana.pending.is_empty() => {
+                Cow::Owned(format!("{}{}", self.username, kana.pending))
+            }
+            _ => Cow::Borrowed(&self.username),
+        }
+    }
+
+    /// Type a character, through kana transliteration on the username
+    fn type_char(&mut self, c: char) {
+        match self.kana {
+            Some(ref mut kana) if self.focus.field() == Field::Username => {
+                for c in kana.feed(c).chars() {
+                    self.input_char(c);
+                }
+            }
+            _ => self.input_char(c),
+        }
+    }
+
+    /// Cycle kana transliteration: hiragana, katakana, off
+    pub fn toggle_kana(&mut self) {
+        self.flush_kana();
+        self.kana = match self.kana {
+            None => Some(Kana::new(Script::Hiragana)),
+            Some(ref kana) if kana.script == Script::Hiragana => Some(Kana::new(Script::Katakana)),
+            Some(_) => None,
         };
     }
 
+    /// Type out romaji that hasn't become kana yet (leaving the username)
+    fn flush_kana(&mut self) {
+        let pending = self.kana.as_mut().map(Kana::flush).unwrap_or_default();
+        if !pending.is_empty() {
+            self.username.push_str(&pending);
+            state::save_draft(&self.username);
+        }
+    }
+
+    /// Switch focus to the next field
+    pub fn next_field(&mut self) {
+        self.flush_kana();
+        match self.focus.field() {
+            Field::Username => self.focus.set_field(Field::Password),
+            Field::Password => self.edit_username(),
+        }
+        self.look_up_user();
+    }
+
+    /// A locked username doesn't take keys
+    fn username_locked(&self) -> bool {
+        self.username_field == UsernameField::Locked
+    }
+
+    /// Say which key unlocks the username
+    fn hint_unlock(&mut self) {
+        if let Some(key) = self.keymap.label(KeyAction::EditUsername) {
+            self.hint = Some(Hint::note(Field::Username, format!("{key} to change the user")));
+        }
+    }
+
+    /// Reveal or unlock the username field and focus it
+    pub fn edit_username(&mut self) {
+        self.username_field = UsernameField::Shown;
+        self.hint = self.hint.take().filter(|hint| hint.field != Field::Username);
+        self.focus.set_field(Field::Username);
+    }
+
     /// Switch focus to the previous field
-    pub const fn prev_field(&mut self) {
+    pub fn prev_field(&mut self) {
         // Only two fields, so same as next
-        self.focus = match self.focus {
-            Focus::Username => Focus::Password,
-            Focus::Password => Focus::Username,
+        self.next_field();
+    }
+
+    /// The entered user is managed by systemd-homed: the password also unlocks home
+    #[must_use]
+    pub fn is_homed(&self) -> bool {
+        self.homed
+            .as_ref()
+            .is_some_and(|(user, homed)| *homed && *user == self.username)
+    }
+
+    /// Per-user lookups for the entered username
+    fn look_up_user(&mut self) {
+        self.check_homed();
+        self.check_full_name();
+    }
+
+    /// Queue the lookup of whether the entered user is a homed user, once
+    /// per username and only when leaving the username field (it spawns
+    /// `userdbctl`)
+    fn check_homed(&mut self) {
+        let checked = self.homed.as_ref().is_some_and(|(user, _)| *user == self.username);
+        if self.focus.field() == Field::Password && !self.username.is_empty() && !checked {
+            self.homed = Some((self.username.clone(), false));
+            self.homed_lookup = Some(self.username.clone());
+        }
+    }
+
+    /// Username to run the queued homed lookup for
+    pub const fn take_homed_lookup(&mut self) -> Option<String> {
+        self.homed_lookup.take()
+    }
+
+    /// The homed lookup for `username` is done; an answer for a user no
+    /// longer entered is dropped
+    pub fn homed_checked(&mut self, username: String, homed: bool) {
+        if self.homed.as_ref().is_some_and(|(user, _)| *user == username) {
+            self.homed = Some((username, homed));
+        }
+    }
+
+    /// Look up the real name of the entered user when the avatar caption
+    /// shows it, once per username and when leaving the username field
+    fn check_full_name(&mut self) {
+        if self.theme.avatar.caption != AvatarCaption::FullName {
+            return;
+        }
+        let checked = self.full_name.as_ref().is_some_and(|(user, _)| *user == self.username);
+        if self.focus.field() == Field::Password && !self.username.is_empty() && !checked {
+            let name = environment::full_name(&self.username);
+            self.full_name = Some((self.username.clone(), name));
+        }
+    }
+
+    /// Text under the avatar, if the theme asks for one
+    #[must_use]
+    pub fn avatar_caption(&self) -> Option<&str> {
+        let name = match self.theme.avatar.caption {
+            AvatarCaption::None => return None,
+            AvatarCaption::Username => None,
+            AvatarCaption::FullName => self
+                .full_name
+                .as_ref()
+                .filter(|(user, _)| *user == self.username)
+                .and_then(|(_, name)| name.as_deref()),
+        };
+        name.or_else(|| (!self.username.is_empty()).then_some(self.username.as_str()))
+    }
+
+    /// Drop a pending follow-up prompt (the username changed)
+    fn cancel_prompt(&mut self) {
+        if self.prompt.take().is_some() {
+            auth::lock(&self.backend).cancel();
+            self.password.clear();
+            self.mask_len = 0;
+        }
+    }
+
+    /// Currently selected session
+    #[must_use]
+    pub fn session(&self) -> &Session {
+        &self.sessions[self.session_index]
+    }
+
+    /// Cycle to the next available session
+    pub const fn next_session(&mut self) {
+        self.session_index = (self.session_index + 1) % self.sessions.len();
+    }
+
+    /// Cycle to the previous available session
+    pub const fn prev_session(&mut self) {
+        self.session_index = (self.session_index + self.sessions.len() - 1) % self.sessions.len();
+    }
+
+    /// System the next reboot starts, when not the default
+    #[must_use]
+    pub fn boot_entry(&self) -> Option<&BootEntry> {
+        self.power_options.boot.get(self.boot_entry?)
+    }
+
+    /// Cycle through the `[[power.boot]]` entries, then back to the default
+    pub fn next_boot_entry(&mut self) {
+        if !self.policy.allows_power(Action::Reboot) {
+            return;
+        }
+        self.boot_entry = match self.boot_entry {
+            None if !self.power_options.boot.is_empty() => Some(0),
+            Some(index) if index + 1 < self.power_options.boot.len() => Some(index + 1),
+            None | Some(_) => None,
         };
     }
 
     /// Handle submit action (Enter key).
     /// Returns `true` if credentials are valid and authentication should proceed.
-    /// Caller must render before calling `authenticate()` (which blocks on IPC).
+    /// The caller then runs [`App::start_auth`]'s call in the background.
     pub fn submit(&mut self) -> bool {
-        if self.authenticating {
+        if self.submission != Submission::Editing {
             return false;
         }
+        self.flush_kana();
 
-        if self.focus == Focus::Username {
-            if self.username.is_empty() {
-                self.error = Some("Username required".to_string());
-                return false;
-            }
-            self.focus = Focus::Password;
+        if !self.finish_username() {
             return false;
         }
-
-        if self.username.is_empty() {
-            self.error = Some("Username required".to_string());
-            self.focus = Focus::Username;
+        if self.focus.field() == Field::Username {
+            self.focus.set_field(Field::Password);
+            self.look_up_user();
             return false;
         }
 
         if self.password.is_empty() {
-            self.error = Some("Password required".to_string());
+            let what = if self.prompt.is_some() { "Answer" } else { "Password" };
+            self.hint = Some(Hint::error(Field::Password, format!("{what} required")));
             return false;
         }
 
-        self.authenticating = true;
         self.error = None;
-        true
+        self.submission.send()
+    }
+
+    /// Hand the credentials, or the answer to the pending prompt, to the
+    /// backend. Returns the number of the attempt and the blocking call for
+    /// a background thread; both go to [`App::finish_auth`].
+    pub fn start_auth(
+        &mut self,
+    ) -> Option<(u64, impl FnOnce() -> Result<Outcome, AuthError> + Send + 'static)> {
+        if self.submission != Submission::Sent {
+            return None;
+        }
+        self.emit(&Event::AuthStarted {
+            username: &self.username,
+        });
+        if let Some(ref mut metrics) = self.metrics {
+            metrics.record_attempt();
+        }
+        self.sent_at = Instant::now();
+        self.undo.forget(Field::Password);
+        let answer = self.prompt.take().is_some();
+        if answer {
+            debug::log("answer sent");
+        } else {
+            debug::log("login sent");
+        }
+        let backend = Arc::clone(&self.backend);
+        let username = self.username.clone();
+        let password = self.password.clone();
+        self.abandoned = Arc::default();
+        let abandoned = Arc::clone(&self.abandoned);
+        self.attempt += 1;
+        Some((self.attempt, move || {
+            let mut backend = auth::lock(&backend);
+            let result = if answer {
+                backend.answer(&password)
+            } else {
+                backend.authenticate(&username, &password)
+            };
+            // Whatever the verdict, nobody waits for it any more
+            if abandoned.load(Ordering::Relaxed) {
+                backend.cancel();
+            }
+            result
+        }))
+    }
+
+    /// Esc while the backend works: back to the form at once. A hung PAM
+    /// module can't be interrupted, so the login is cancelled when the
+    /// backend finally answers, and its verdict is ignored.
+    pub fn cancel_auth(&mut self) {
+        if self.submission != Submission::Sent {
+            return;
+        }
+        debug::log("login cancelled");
+        self.abandoned.store(true, Ordering::Relaxed);
+        self.submission.reject();
+        self.password.clear();
+        self.mask_len = 0;
+        self.error = Some("Authentication cancelled".to_string());
+        self.focus.set_field(Field::Password);
+    }
+
+    /// Act on the backend's verdict on `attempt` from [`App::start_auth`].
+    /// A cancelled attempt can answer after the next one was sent, so only
+    /// the latest counts. Returns `true` on success (session started).
+    pub fn finish_auth(&mut self, attempt: u64, result: Result<Outcome, AuthError>) -> bool {
+        if attempt != self.attempt || self.submission != Submission::Sent {
+            debug::log("verdict of a cancelled login ignored");
+            return false;
+        }
+        match result {
+            Ok(Outcome::Authenticated(_)) => debug::log("login accepted"),
+            Ok(Outcome::Prompt(ref prompt)) => {
+                debug::log(format_args!("asked {:?}", prompt.message));
+            }
+            Err(ref e) => debug::log(format_args!("login failed: {e}")),
+        }
+        let password = std::mem::take(&mut self.password);
+        self.mask_len = 0;
+
+        match result {
+            Ok(Outcome::Authenticated(mut notices)) => {
+                self.submission.accept();
+                // A failed unlock is reported but doesn't undo the login
+                if let Some(ref hook) = self.unlock {
+                    if let Err(e) = unlock::run(hook, &self.username, &password) {
+                        notices.push(e);
+                    }
+                }
+                self.notices = notices;
+                if self.handoff_countdown.is_zero() {
+                    return self.start_session();
+                }
+                self.handoff = Some(Handoff::new(self.handoff_countdown, Instant::now()));
+                self.focus.push(Context::Handoff);
+                false
+            }
+            Ok(Outcome::Prompt(prompt)) => {
+                self.submission.reject();
+                self.prompt = Some(prompt);
+                self.focus.set_field(Field::Password);
+                false
+            }
+            Err(e) => {
+                self.submission.reject();
+                let message = e.to_string();
+                self.emit(&Event::AuthFailed {
+                    username: &self.username,
+                    reason: &message,
+                });
+                if let Some(ref mut metrics) = self.metrics {
+                    metrics.record_failure();
+                }
+                if let Some((count, span)) = self.failures.record(Instant::now()) {
+                    audit::brute_force(&self.username, count, span);
+                }
+                self.rejected(e.rejection(), message);
+                if matches!(e, AuthError::AuthFailed(_)) {
+                    self.record_failed_attempt();
+                }
+                false
+            }
+        }
+    }
+
+    /// Say why a login failed. An unknown user is pointed out at the username,
+    /// unless the policy hides it; then it reads like any failed login.
+    fn rejected(&mut self, rejection: Rejection, message: String) {
+        match (rejection, self.policy.reveal_unknown_users) {
+            (Rejection::UnknownUser, true) => {
+                self.error = None;
+                self.edit_username();
+                self.hint = Some(Hint::error(Field::Username, "Unknown user"));
+                return;
+            }
+            (Rejection::WrongPassword, true) => self.error = Some("Wrong password".to_string()),
+            (Rejection::UnknownUser | Rejection::WrongPassword, false) => {
+                self.error = Some("Authentication failed".to_string());
+            }
+            (Rejection::Other, _) => {
+                self.error = Some(kerberos::explain(&message).map_or(message, str::to_string));
+            }
+        }
+        self.focus.set_field(Field::Password);
     }
 
-    /// Perform authentication against greetd (blocking IPC).
-    /// Returns `true` on success (session started).
-    pub fn authenticate(&mut self) -> bool {
-        match greetd::authenticate(&self.username, &self.password, &self.session_cmd) {
-            Ok(()) => true,
+    /// Start the selected session for the user that just authenticated.
+    /// Returns `true` once the backend took it.
+    pub fn start_session(&mut self) -> bool {
+        if self.submission != Submission::Authenticated {
+            return false;
+        }
+        if self.handoff.take().is_some() && self.focus.top() == Context::Handoff {
+            self.focus.pop();
+        }
+        let session = &self.sessions[self.session_index];
+        let session_cmd = sessions::with_logging(&session.command, &self.session_log);
+        let mut env = environment::session_env(&self.username, &self.environment);
+        if let Some(kind) = session.kind.xdg_type() {
+            env.push(format!("XDG_SESSION_TYPE={kind}"));
+        }
+        debug::log(format_args!("starting session {session_cmd:?}"));
+        let started = auth::lock(&self.backend).start(&session_cmd, &env);
+        match started {
+            Ok(()) => {
+                state::clear_draft();
+                state::save_last_user(&self.username);
+                let command = &self.sessions[self.session_index].command;
+                let failures = match self.crashes {
+                    Some((ref crashed, failures)) if crashed == command => failures,
+                    _ => 0,
+                };
+                state::save_launch(command, failures);
+                self.emit(&Event::SessionStarted {
+                    username: &self.username,
+                    session: &self.sessions[self.session_index].name,
+                });
+                if let Some(ref mut metrics) = self.metrics {
+                    metrics.record_login();
+                }
+                self.submission.grant()
+            }
             Err(e) => {
-                self.authenticating = false;
+                debug::log(format_args!("session refused: {e}"));
+                self.submission.reject();
+                self.notices.clear();
                 self.error = Some(e.to_string());
-                self.password.clear();
-                self.focus = Focus::Password;
+                self.focus.set_field(Field::Password);
                 false
             }
         }
     }
 
+    /// Esc after logging in: stop the countdown, then give up the login
+    fn stop_handoff(&mut self) {
+        let Some(ref mut handoff) = self.handoff else {
+            return;
+        };
+        if handoff.remaining(Instant::now()).is_some() {
+            handoff.stop();
+            return;
+        }
+        self.handoff = None;
+        self.focus.pop();
+        self.notices.clear();
+        self.submission.reject();
+        auth::lock(&self.backend).cancel();
+    }
+
+    /// Count a rejected attempt; at the limit start over with a blank form
+    fn record_failed_attempt(&mut self) {
+        self.attempts += 1;
+        if self.max_attempts > 0 && self.attempts >= self.max_attempts {
+            self.attempts = 0;
+            self.username.clear();
+            state::clear_draft();
+            self.hint = None;
+            self.edit_username();
+            self.error = Some("Too many failed attempts".to_string());
+        }
+    }
+
+    /// "attempt 2 of 3" once an attempt failed and a limit is set
+    #[must_use]
+    pub fn attempt_label(&self) -> Option<String> {
+        (self.attempts > 0 && self.max_attempts > 0)
+            .then(|| format!("attempt {} of {}", self.attempts + 1, self.max_attempts))
+    }
+
+    /// Publish an event when the event socket is enabled
+    fn emit(&self, event: &Event) {
+        if let Some(ref events) = self.events {
+            events.emit(event);
+        }
+    }
+
+    /// How long the success confirmation stays up before grxxt exits
+    #[must_use]
+    pub const fn success_duration(&self) -> Duration {
+        Duration::from_millis(self.success.duration_ms)
+    }
+
+    /// Switch all radios off, or back on when they all are off. The state
+    /// is read again first: the header's may be a refresh behind.
+    pub fn toggle_airplane(&mut self) {
+        match rfkill::set_airplane(!rfkill::airplane()) {
+            Ok(()) => self.status.airplane = rfkill::airplane(),
+            Err(e) => self.error = Some(format!("Can't switch radios: {e}")),
+        }
+    }
+
+    /// Switch large text mode; on a VT this also swaps the console font
+    pub fn toggle_large_text(&mut self) {
+        self.theme.large_text = !self.theme.large_text;
+        if self.theme.large_text {
+            console::set_large_font(&self.theme.large_font);
+        } else {
+            console::restore_font();
+        }
+    }
+
+    /// Carry out a bound action. `Submit` starts a background call, so
+    /// the event loop runs it itself.
+    pub fn perform(&mut self, action: KeyAction) {
+        // Any other key ends a hold
+        if !matches!(
+            action,
+            KeyAction::Shutdown
+                | KeyAction::Reboot
+                | KeyAction::Suspend
+                | KeyAction::SuspendThenHibernate
+        ) {
+            self.hold = None;
+        }
+        match action {
+            KeyAction::Shutdown => self.request_power(Action::Shutdown),
+            KeyAction::Reboot => self.request_power(Action::Reboot),
+            KeyAction::Suspend => self.request_power(Action::Suspend),
+            KeyAction::SuspendThenHibernate => self.request_power(Action::SuspendThenHibernate),
+            KeyAction::NextSession | KeyAction::PrevSession if self.policy.session_picker => {
+                if action == KeyAction::NextSession {
+                    self.next_session();
+                } else {
+                    self.prev_session();
+                }
+                // Picking another session waits for Enter
+                if let Some(ref mut handoff) = self.handoff {
+                    handoff.stop();
+                }
+            }
+            KeyAction::NextBootEntry => self.next_boot_entry(),
+            KeyAction::ToggleLargeText => self.toggle_large_text(),
+            KeyAction::Quit if self.policy.quit => self.quit(),
+            KeyAction::NextField => self.next_field(),
+            KeyAction::PrevField => self.prev_field(),
+            KeyAction::EditUsername => self.edit_username(),
+            KeyAction::ClearField => self.clear_field(),
+            KeyAction::Undo => self.undo(),
+            KeyAction::Compose => self.compose.start(),
+            KeyAction::Kana => self.toggle_kana(),
+            KeyAction::ToggleAirplane => self.toggle_airplane(),
+            KeyAction::Screenshot if cfg!(feature = "screenshot") => {
+                if let Some(ref mut screenshot) = self.screenshot {
+                    *screenshot = true;
+                }
+            }
+            // Backspace first takes back a compose sequence or romaji in progress
+            KeyAction::Backspace => {
+                let romaji = self.kana.as_mut().and_then(|kana| kana.pending.pop());
+                if !self.compose.cancel() && romaji.is_none() {
+                    self.backspace();
+                }
+            }
+            KeyAction::Input(c) => {
+                if let Some(c) = self.compose.feed(c) {
+                    self.type_char(c);
+                }
+            }
+            KeyAction::FailedUnits => self.show_failed_units(),
+            KeyAction::ScrollUp => self.units_scroll.up(),
+            KeyAction::ScrollDown => self.units_scroll.down(),
+            KeyAction::Confirm => self.confirm(),
+            KeyAction::Dismiss => self.dismiss(),
+            // Submit is run by the event loop; the rest is off by policy
+            KeyAction::Submit
+            | KeyAction::NextSession
+            | KeyAction::PrevSession
+            | KeyAction::Quit
+            | KeyAction::Screenshot => {}
+        }
+    }
+
+    /// Power key pressed: wait for it to be held or ask first if configured,
+    /// otherwise act. Actions the policy doesn't allow do nothing.
+    pub fn request_power(&mut self, action: Action) {
+        if !self.policy.allows_power(action) {
+            return;
+        }
+        if self.power_options.hold.contains(&action) {
+            let now = Instant::now();
+            match self.hold {
+                Some(ref mut hold) if hold.action == action => hold.repeat(now),
+                _ => self.hold = Some(Hold::new(action, now)),
+            }
+            self.poll_hold();
+        } else if self.confirm_power.contains(&action) {
+            self.focus.push(Context::Confirm(action));
+        } else {
+            self.power(action);
+        }
+    }
+
+    /// Mouse wheel or touchpad scrolled: an open list scrolls, the form and
+    /// the countdown pick the session like ↑/↓
+    pub fn wheel(&mut self, down: bool) {
+        if self.submission == Submission::Sent {
+            return;
+        }
+        let action = match (self.focus.top(), down) {
+            (Context::FailedUnits, false) => KeyAction::ScrollUp,
+            (Context::FailedUnits, true) => KeyAction::ScrollDown,
+            (Context::Form(_) | Context::Handoff, false) => KeyAction::PrevSession,
+            (Context::Form(_) | Context::Handoff, true) => KeyAction::NextSession,
+            (Context::Confirm(_), _) => return,
+        };
+        self.perform(action);
+    }
+
+    /// Open the failed units list, when there are any
+    pub fn show_failed_units(&mut self) {
+        if !self.status.failed_units.is_empty() {
+            self.units_scroll = Scroll::new(self.status.failed_units.len());
+            self.focus.push(Context::FailedUnits);
+        }
+    }
+
+    /// Fresh list from systemd; the open list keeps its place if it can
+    pub fn set_failed_units(&mut self, units: Vec<String>) {
+        self.units_scroll.resize(units.len());
+        self.status.failed_units = units;
+    }
+
+    /// Accept the modal on top (Enter)
+    pub fn confirm(&mut self) {
+        if let Some(Context::Confirm(action)) = self.focus.pop() {
+            self.power(action);
+        }
+    }
+
+    /// Close the modal on top without acting (Esc)
+    pub fn dismiss(&mut self) {
+        if self.focus.top() == Context::Handoff {
+            self.stop_handoff();
+        } else {
+            self.focus.pop();
+        }
+    }
+
+    /// Start a power action in the background
+    fn power(&mut self, action: Action) {
+        if self.power.is_some() {
+            return;
+        }
+        self.error = None;
+        self.power = power::start(action, &self.power_options, self.boot_entry());
+        if self.power.is_none() {
+            self.error = Some("systemctl not available".to_string());
+        }
+    }
+
+    /// Act once the held key was down long enough; drop the hold when the
+    /// key was let go
+    pub fn poll_hold(&mut self) {
+        let Some(hold) = self.hold else {
+            return;
+        };
+        match hold.state(Instant::now()) {
+            HoldState::Holding => {}
+            HoldState::Released => self.hold = None,
+            HoldState::Confirmed => {
+                self.hold = None;
+                self.power(hold.action);
+            }
+        }
+    }
+
+    /// Time left before the idle suspend, while counting down
+    #[must_use]
+    pub fn idle_countdown(&self) -> Option<Duration> {
+        self.idle.as_ref()?.remaining(Instant::now())
+    }
+
+    /// Nobody used the greeter for `[power.idle] suspend_after`
+    pub fn idle_suspend(&mut self) {
+        self.power(Action::Suspend);
+    }
+
+    /// Check on the running power action. Returns `true` after a resume from
+    /// suspend, when the last frame and any queued keystrokes are stale.
+    pub fn poll_power(&mut self) -> bool {
+        let Some(ref mut pending) = self.power else {
+            return false;
+        };
+        match pending.poll() {
+            power::State::Running => false,
+            power::State::Failed(reason) => {
+                self.error = Some(
+                    reason.unwrap_or_else(|| format!("systemctl {} failed", pending.action.verb())),
+                );
+                self.power = None;
+                false
+            }
+            power::State::Resumed => {
+                if let Some(pending) = self.power.take() {
+                    pending.reap();
+                }
+                // Nothing typed before the machine slept should log anyone in
+                self.cancel_prompt();
+                self.password.clear();
+                self.mask_len = 0;
+                self.error = None;
+                true
+            }
+        }
+    }
+
     /// Request application quit
     pub const fn quit(&mut self) {
         self.should_quit = true;
     }
 }
+
+/// The configured theme, brought down to what the terminal can show
+fn load_theme(config: &Config, locale: Locale) -> Theme {
+    Theme::from(&config.theme)
+        .with_palette(Palette::detect())
+        .with_box_drawing(console::box_drawing())
+        .with_rtl(match config.direction {
+            Direction::Auto => locale.right_to_left(),
+            Direction::Ltr => false,
+            Direction::Rtl => true,
+        })
+}
+
+/// A username left behind by a greeter that was killed mid-typing;
+/// `remember` and password-first mode otherwise start as the last user
+fn initial_username(config: &Config) -> (String, UsernameField) {
+    let draft = state::load_draft();
+    let remembered = if draft.is_none() && (config.remember || config.password_first) {
+        state::load_last_user()
+    } else {
+        None
+    };
+    let field = match (remembered.is_some(), config.password_first) {
+        (true, true) => UsernameField::Collapsed,
+        (true, false) => UsernameField::Locked,
+        (false, _) => UsernameField::Shown,
+    };
+    (draft.or(remembered).unwrap_or_default(), field)
+}
+
+/// The configured session first, then the discovered ones when the policy
+/// allows picking
+fn session_list(config: &Config, policy: &Policy) -> Vec<Session> {
+    let default_cmd = config.session_command();
+    let mut sessions = vec![Session::from_command(&default_cmd)];
+    if policy.session_picker {
+        let discovered =
+            sessions::discover(config.launcher, config.x11_wrapper(), &config.sessions);
+        for session in discovered {
+            if session.command != default_cmd {
+                sessions.push(session);
+            }
+        }
+    }
+    sessions
+}
+
+/// Rows of a logo file; `None` when it can't be read or is empty
+fn load_logo(path: &str) -> Option<Vec<String>> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let rows: Vec<String> = text.lines().map(|row| row.trim_end().to_string()).collect();
+    (!rows.is_empty()).then_some(rows)
+}
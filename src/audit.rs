@@ -0,0 +1,115 @@
+//! Security audit log
+//!
+//! Security events go to syslog (authpriv, via `/dev/log`) where
+//! fail2ban-style tools already look. Repeated login failures on the console
+//! are condensed into one rate-limited warning instead of a line per typo.
+
+use std::collections::VecDeque;
+use std::os::unix::net::UnixDatagram;
+use std::process;
+use std::time::{Duration, Instant};
+
+use crate::console;
+
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// syslog authpriv facility
+const AUTHPRIV: u8 = 10;
+/// syslog warning severity
+const WARNING: u8 = 4;
+
+/// Failures within this window count towards an attack
+const WINDOW: Duration = Duration::from_mins(1);
+/// Failures within the window that trigger a warning
+const THRESHOLD: usize = 5;
+/// At most one warning per this interval
+const RATE_LIMIT: Duration = Duration::from_mins(1);
+
+/// Sliding-window login failure counter
+#[derive(Debug, Default)]
+pub struct FailureTracker {
+    failures: VecDeque<Instant>,
+    last_warning: Option<Instant>,
+}
+
+impl FailureTracker {
+    /// Record a failure at `now`. Returns the failure count and the time
+    /// they span when this should be reported as a brute-force attempt.
+    pub fn record(&mut self, now: Instant) -> Option<(usize, Duration)> {
+        self.failures.push_back(now);
+        while self
+            .failures
+            .front()
+            .is_some_and(|first| now.duration_since(*first) > WINDOW)
+        {
+            self.failures.pop_front();
+        }
+
+        let limited = self
+            .last_warning
+            .is_some_and(|last| now.duration_since(last) < RATE_LIMIT);
+        if self.failures.len() < THRESHOLD || limited {
+            return None;
+        }
+        self.last_warning = Some(now);
+        let span = self
+            .failures
+            .front()
+            .map_or(Duration::ZERO, |first| now.duration_since(*first));
+        Some((self.failures.len(), span))
+    }
+}
+
+/// Log a brute-force warning for `username`
+pub fn brute_force(username: &str, count: usize, span: Duration) {
+    let tty = console::tty_line().unwrap_or_else(|| "?".to_string());
+    warn(&format!(
+        "repeated login failures: user={username} tty={tty} count={count} within={}s",
+        span.as_secs()
+    ));
+}
+
+/// Send a warning to syslog. Best effort: no syslog, no audit line.
+fn warn(message: &str) {
+    let line = format!(
+        "<{}>grxxt[{}]: {message}",
+        AUTHPRIV * 8 + WARNING,
+        process::id()
+    );
+    if let Ok(socket) = UnixDatagram::unbound() {
+        socket.send_to(line.as_bytes(), SYSLOG_SOCKET).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    #[test]
+    fn test_failure_tracker() {
+        let time = Timeline::new();
+        let mut tracker = FailureTracker::default();
+
+        for secs in 0..4 {
+            assert_eq!(tracker.record(time.secs(secs)), None);
+        }
+        assert_eq!(
+            tracker.record(time.secs(4)),
+            Some((5, Duration::from_secs(4)))
+        );
+
+        // Rate limited for a minute, then reported again
+        for secs in [5, 61, 62, 63] {
+            assert_eq!(tracker.record(time.secs(secs)), None);
+        }
+        assert_eq!(tracker.record(time.secs(64)), Some((6, WINDOW)));
+
+        // Slow failures never add up
+        let mut slow = FailureTracker::default();
+        for secs in 0..10 {
+            assert_eq!(slow.record(time.secs(secs * 30)), None);
+        }
+    }
+}
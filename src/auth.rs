@@ -0,0 +1,197 @@
+//! Authentication backends
+//!
+//! The UI only talks to an [`AuthBackend`]. greetd is the default; a direct
+//! PAM backend (experimental, `pam` feature) lets grxxt run without greetd,
+//! and `--demo` pretends to log in.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::config::{Backend, Config};
+use crate::demo::Demo;
+use crate::greetd::Greetd;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum AuthError {
+    #[error("Connection failed: {0}")]
+    ConnectionFailed(String),
+    #[error("Protocol error: {0}")]
+    ProtocolError(String),
+    #[error("{0}")]
+    AuthFailed(String),
+}
+
+/// Lowercase fragments of a username nobody knows: libpam's text and name
+/// for `PAM_USER_UNKNOWN`, the PAM backend's own lookup, Kerberos
+const UNKNOWN_USER: [&str; 4] = [
+    "user not known",
+    "user_unknown",
+    "unknown user",
+    "client not found in kerberos database",
+];
+
+/// Lowercase fragments of a wrong password: libpam's text and name for
+/// `PAM_AUTH_ERR`, Kerberos
+const WRONG_PASSWORD: [&str; 3] = ["authentication failure", "auth_err", "preauthentication failed"];
+
+/// What a login was turned down for, as far as the backend's message tells.
+/// Many PAM stacks don't tell an unknown user from a wrong password on
+/// purpose, and say neither.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    UnknownUser,
+    WrongPassword,
+    Other,
+}
+
+impl AuthError {
+    #[must_use]
+    pub fn rejection(&self) -> Rejection {
+        let Self::AuthFailed(message) = self else {
+            return Rejection::Other;
+        };
+        let message = message.to_lowercase();
+        let says = |fragments: &[&str]| fragments.iter().any(|fragment| message.contains(fragment));
+        if says(&UNKNOWN_USER) {
+            Rejection::UnknownUser
+        } else if says(&WRONG_PASSWORD) {
+            Rejection::WrongPassword
+        } else {
+            Rejection::Other
+        }
+    }
+}
+
+/// Question the auth stack asks after the password (homed recovery key,
+/// security token PIN, ...)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prompt {
+    /// The question, shown as the field's label ("Recovery key:")
+    pub message: String,
+    /// Info sent just before it ("Security token not inserted.")
+    pub notices: Vec<String>,
+    /// Answer must be masked like a password
+    pub secret: bool,
+}
+
+/// How far a login got
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Credentials accepted, the session waits for [`AuthBackend::start`];
+    /// informational messages from the auth stack (e.g. "Ticket acquired")
+    Authenticated(Vec<String>),
+    /// The auth stack needs another answer, see [`AuthBackend::answer`]
+    Prompt(Prompt),
+}
+
+/// A login mechanism the greeter UI can drive. Logins run on a background
+/// thread, so backends move between threads.
+pub trait AuthBackend: Send {
+    /// Verify the credentials of `username`. Blocks until the backend answers.
+    ///
+    /// # Errors
+    ///
+    /// [`AuthError::AuthFailed`] when the credentials are turned down, the
+    /// other variants when the backend can't be reached or misbehaves.
+    fn authenticate(&mut self, username: &str, password: &str) -> Result<Outcome, AuthError>;
+
+    /// Answer the pending [`Outcome::Prompt`] and continue the login
+    ///
+    /// # Errors
+    ///
+    /// As for [`authenticate`](Self::authenticate); a protocol error when
+    /// nothing was asked.
+    fn answer(&mut self, _response: &str) -> Result<Outcome, AuthError> {
+        Err(AuthError::ProtocolError("No pending prompt".into()))
+    }
+
+    /// Prepare `session_cmd` (with `KEY=value` entries in `env`) for the user
+    /// that just authenticated
+    ///
+    /// # Errors
+    ///
+    /// When the backend refuses the session or can't be reached.
+    fn start(&mut self, session_cmd: &str, env: &[String]) -> Result<(), AuthError>;
+
+    /// Abandon a login waiting on a prompt or on its session
+    fn cancel(&mut self) {}
+
+    /// Hand the TTY over to the session, called after the terminal is restored.
+    /// Backends that delegate session startup (greetd) have nothing to do here.
+    ///
+    /// # Errors
+    ///
+    /// When the session can't be started.
+    fn handoff(&mut self) -> Result<(), AuthError> {
+        Ok(())
+    }
+}
+
+/// Backend selected in the config but not compiled into this binary
+#[cfg(not(feature = "pam"))]
+struct Unavailable(&'static str);
+
+#[cfg(not(feature = "pam"))]
+impl Unavailable {
+    fn error(&self) -> AuthError {
+        AuthError::ConnectionFailed(format!(
+            "{} backend not built (enable the `{}` feature)",
+            self.0, self.0
+        ))
+    }
+}
+
+#[cfg(not(feature = "pam"))]
+impl AuthBackend for Unavailable {
+    fn authenticate(&mut self, _: &str, _: &str) -> Result<Outcome, AuthError> {
+        Err(self.error())
+    }
+
+    fn start(&mut self, _: &str, _: &[String]) -> Result<(), AuthError> {
+        Err(self.error())
+    }
+}
+
+/// A backend shared between the event loop and the login running in the
+/// background
+pub type Shared = Arc<Mutex<Box<dyn AuthBackend>>>;
+
+/// The backend; a login that panicked leaves it usable
+pub fn lock(backend: &Shared) -> MutexGuard<'_, Box<dyn AuthBackend>> {
+    backend.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Build the backend selected in the configuration
+#[must_use]
+pub fn from_config(config: &Config) -> Shared {
+    let backend: Box<dyn AuthBackend> = match config.backend {
+        Backend::Greetd => Box::new(Greetd::new(config.socket.clone(), config.auth_timeout())),
+        #[cfg(feature = "pam")]
+        Backend::Pam => Box::new(crate::pam::Pam::new(config.pam_service.as_deref())),
+        #[cfg(not(feature = "pam"))]
+        Backend::Pam => Box::new(Unavailable("pam")),
+        Backend::Demo => Box::new(Demo::default()),
+    };
+    Arc::new(Mutex::new(backend))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejection() {
+        let failed = |message: &str| AuthError::AuthFailed(message.to_string());
+        assert_eq!(failed("pam_authenticate: USER_UNKNOWN").rejection(), Rejection::UnknownUser);
+        assert_eq!(
+            failed("User not known to the underlying authentication module").rejection(),
+            Rejection::UnknownUser
+        );
+        assert_eq!(failed("pam_authenticate: AUTH_ERR").rejection(), Rejection::WrongPassword);
+        assert_eq!(failed("Authentication failure").rejection(), Rejection::WrongPassword);
+        assert_eq!(failed("Authentication failed").rejection(), Rejection::Other);
+        assert_eq!(
+            AuthError::ConnectionFailed("Unknown user".to_string()).rejection(),
+            Rejection::Other
+        );
+    }
+}
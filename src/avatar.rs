@@ -4,27 +4,40 @@
 //! Auto-detects the best terminal graphics protocol (kitty, sixel, etc.)
 //! and falls back to halfblocks on bare TTYs.
 //! Returns `None` on any error — avatar is optional, never blocks login.
+//!
+//! Image support is the `avatar` cargo feature. Without it `load` always
+//! returns `None` and the UI shows the placeholder icon.
 
+use ratatui::{layout::Rect, Frame};
+#[cfg(feature = "avatar")]
 use ratatui_image::{
     picker::{Picker, ProtocolType},
     protocol::StatefulProtocol,
+    Resize, StatefulImage,
 };
 
 /// Maximum dimension (width or height) for the decoded image.
 /// Caps memory usage for large source files.
+#[cfg(feature = "avatar")]
 const MAX_DIM: u32 = 512;
 
 /// Loaded avatar: render protocol + aspect ratio for centering
+#[cfg(feature = "avatar")]
 pub struct Avatar {
     pub protocol: StatefulProtocol,
     /// Width / height ratio of the source image
     pub aspect_ratio: f32,
 }
 
+/// Built without image support: no avatar can ever be loaded
+#[cfg(not(feature = "avatar"))]
+pub enum Avatar {}
+
 /// Load an avatar image and return a render-ready protocol state.
 ///
 /// Queries the terminal for graphics support (kitty, sixel, iterm2).
 /// Falls back to halfblocks which works on bare TTYs.
+#[cfg(feature = "avatar")]
 #[allow(
     clippy::cast_precision_loss,
     reason = "image dimensions are small u32 values, f32 is fine"
@@ -48,3 +61,111 @@ pub fn load(path: &str) -> Option<Avatar> {
         aspect_ratio,
     })
 }
+
+/// Built without image support
+#[cfg(not(feature = "avatar"))]
+pub const fn load(_path: &str) -> Option<Avatar> {
+    None
+}
+
+/// Area inside `inner` that an image of `aspect_ratio` (width / height) fills
+/// when fit and centered. With halfblocks + font(4,8), cells are effectively
+/// square, so the ratio applies to cells directly. Always within `inner`;
+/// a degenerate ratio (zero, NaN, infinite) uses all of `inner`.
+#[cfg(feature = "avatar")]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "values are clamped to the u16 dimensions of inner first"
+)]
+fn centered_area(inner: Rect, aspect_ratio: f32) -> Rect {
+    if inner.is_empty() || !aspect_ratio.is_normal() || aspect_ratio < 0.0 {
+        return inner;
+    }
+    let (inner_w, inner_h) = (f32::from(inner.width), f32::from(inner.height));
+
+    // Fit by height (portrait, square, moderate landscape) or by width (wide)
+    let (width, height) = if inner_h * aspect_ratio <= inner_w {
+        let cols = (inner_h * aspect_ratio).round().clamp(1.0, inner_w) as u16;
+        (cols, inner.height)
+    } else {
+        let rows = (inner_w / aspect_ratio).round().clamp(1.0, inner_h) as u16;
+        (inner.width, rows)
+    };
+
+    Rect::new(
+        inner.x + (inner.width - width) / 2,
+        inner.y + (inner.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+impl Avatar {
+    /// Render the image centered inside `inner` (the avatar block's inner area)
+    #[cfg(feature = "avatar")]
+    pub fn render(&mut self, frame: &mut Frame, inner: Rect) {
+        let centered = centered_area(inner, self.aspect_ratio);
+        let image = StatefulImage::default().resize(Resize::Fit(None));
+        frame.render_stateful_widget(image, centered, &mut self.protocol);
+    }
+
+    /// Built without image support: there is no value to render
+    #[cfg(not(feature = "avatar"))]
+    #[allow(
+        clippy::uninhabited_references,
+        clippy::needless_pass_by_ref_mut,
+        reason = "Avatar is uninhabited without the feature, so this is unreachable"
+    )]
+    pub const fn render(&mut self, _frame: &mut Frame, _inner: Rect) {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "avatar"))]
+mod tests {
+    use super::*;
+
+    const RATIOS: [f32; 9] = [0.01, 0.25, 0.5, 0.75, 1.0, 1.5, 16.0 / 9.0, 10.0, 100.0];
+
+    #[test]
+    fn test_centered_area_stays_inside() {
+        for ratio in RATIOS {
+            for width in 0..=60 {
+                for height in 0..=12 {
+                    let inner = Rect::new(3, 5, width, height);
+                    let area = centered_area(inner, ratio);
+                    assert!(
+                        inner.union(area) == inner,
+                        "{area:?} outside {inner:?} at ratio {ratio}"
+                    );
+                    if !inner.is_empty() {
+                        assert!(!area.is_empty(), "empty image at ratio {ratio} in {inner:?}");
+                        // Fit: one dimension fills inner, margins differ by at most a cell
+                        assert!(area.width == width || area.height == height);
+                        let left = area.x - inner.x;
+                        let right = inner.right() - area.right();
+                        assert!(left.abs_diff(right) <= 1);
+                        let top = area.y - inner.y;
+                        let bottom = inner.bottom() - area.bottom();
+                        assert!(top.abs_diff(bottom) <= 1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_centered_area_shapes() {
+        let inner = Rect::new(0, 0, 20, 8);
+        // Portrait: full height, narrow and centered
+        assert_eq!(centered_area(inner, 0.5), Rect::new(8, 0, 4, 8));
+        // Wide panorama: full width, short and vertically centered
+        assert_eq!(centered_area(inner, 5.0), Rect::new(0, 2, 20, 4));
+        // One row high never produces an empty image
+        assert_eq!(centered_area(Rect::new(0, 0, 20, 1), 0.01), Rect::new(9, 0, 1, 1));
+        // Broken ratios fall back to the whole area
+        assert_eq!(centered_area(inner, f32::NAN), inner);
+        assert_eq!(centered_area(inner, f32::INFINITY), inner);
+    }
+}
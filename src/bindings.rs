@@ -0,0 +1,519 @@
+//! Key bindings: which key does what in which focus context
+//!
+//! Keys are looked up in a table instead of a hard-coded match so they can
+//! be remapped from the `[keys]` config section, e.g. `shutdown = "ctrl+q"`.
+//! Key names are `F1`-`F12`, `enter`, `esc`, `tab`, `backtab`, `backspace`,
+//! `up`, `down`, `space` or a single character, optionally prefixed with
+//! `ctrl+`, `alt+` or `shift+`.
+
+use std::collections::BTreeMap;
+
+use crate::input::{KeyCode, KeyEvent, Modifiers};
+
+use crate::focus::Context;
+
+/// Something the user can ask for with a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Shutdown,
+    Reboot,
+    Suspend,
+    SuspendThenHibernate,
+    /// Cycle the system the next reboot starts
+    NextBootEntry,
+    /// Open the list of failed units
+    FailedUnits,
+    NextSession,
+    PrevSession,
+    ToggleLargeText,
+    Quit,
+    NextField,
+    PrevField,
+    EditUsername,
+    /// Empty the focused field
+    ClearField,
+    /// Bring back what a field held before it was cleared or erased
+    Undo,
+    /// Start a compose sequence for an accented character
+    Compose,
+    /// Cycle romaji to kana transliteration for the username
+    Kana,
+    /// Switch all radios off or back on; unbound unless configured
+    ToggleAirplane,
+    /// Save the screen as a PNG; demo mode only
+    Screenshot,
+    Submit,
+    Backspace,
+    Input(char),
+    /// Accept the modal on top
+    Confirm,
+    /// Close the modal on top
+    Dismiss,
+    ScrollUp,
+    ScrollDown,
+}
+
+impl Action {
+    /// Name used in the `[keys]` config section, for remappable actions
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "shutdown" => Self::Shutdown,
+            "reboot" => Self::Reboot,
+            "suspend" => Self::Suspend,
+            "suspend_then_hibernate" => Self::SuspendThenHibernate,
+            "boot_entry" => Self::NextBootEntry,
+            "failed_units" => Self::FailedUnits,
+            "next_session" => Self::NextSession,
+            "prev_session" => Self::PrevSession,
+            "large_text" => Self::ToggleLargeText,
+            "quit" => Self::Quit,
+            "next_field" => Self::NextField,
+            "prev_field" => Self::PrevField,
+            "edit_username" => Self::EditUsername,
+            "clear_field" => Self::ClearField,
+            "undo" => Self::Undo,
+            "compose" => Self::Compose,
+            "kana" => Self::Kana,
+            "airplane" => Self::ToggleAirplane,
+            "screenshot" => Self::Screenshot,
+            "submit" => Self::Submit,
+            _ => return None,
+        })
+    }
+}
+
+/// A key together with the modifiers that must be held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Key {
+    code: KeyCode,
+    modifiers: Modifiers,
+}
+
+impl Key {
+    const fn new(code: KeyCode, modifiers: Modifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Parse a key name like `F1` or `ctrl+u`
+    fn parse(spec: &str) -> Option<Self> {
+        let mut modifiers = Modifiers::NONE;
+        let mut rest = spec.trim();
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            let (modifier, len) = if lower.starts_with("ctrl+") {
+                (Modifiers::CONTROL, 5)
+            } else if lower.starts_with("alt+") {
+                (Modifiers::ALT, 4)
+            } else if lower.starts_with("shift+") {
+                (Modifiers::SHIFT, 6)
+            } else {
+                break;
+            };
+            modifiers = modifiers.union(modifier);
+            rest = &rest[len..];
+        }
+
+        let lower = rest.to_ascii_lowercase();
+        let code = match lower.as_str() {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "space" => KeyCode::Char(' '),
+            _ => {
+                let mut chars = rest.chars();
+                if let (Some(c), None) = (chars.next(), chars.next()) {
+                    KeyCode::Char(c.to_ascii_lowercase())
+                } else {
+                    let n = lower.strip_prefix('f')?.parse().ok()?;
+                    (1..=12).contains(&n).then_some(KeyCode::F(n))?
+                }
+            }
+        };
+        Some(Self::new(code, modifiers))
+    }
+
+    /// Name as in the `[keys]` section, e.g. `F4` or `ctrl+n`; `None` for a
+    /// lone modifier
+    fn name(self) -> Option<String> {
+        let code = match self.code {
+            KeyCode::F(n) => format!("F{n}"),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::BackTab => "backtab".to_string(),
+            KeyCode::Backspace => "backspace".to_string(),
+            KeyCode::Up => "up".to_string(),
+            KeyCode::Down => "down".to_string(),
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Modifier(_) => return None,
+        };
+        let prefixes = [
+            (Modifiers::CONTROL, "ctrl+"),
+            (Modifiers::ALT, "alt+"),
+            (Modifiers::SHIFT, "shift+"),
+        ];
+        let mut name: String = prefixes
+            .iter()
+            .filter(|&&(modifier, _)| self.modifiers.contains(modifier))
+            .map(|&(_, prefix)| prefix)
+            .collect();
+        name.push_str(&code);
+        Some(name)
+    }
+
+    /// Whether `event` is this key. Terminals report Shift on characters
+    /// and backtab inconsistently, so it only counts for other keys.
+    fn matches(self, event: KeyEvent) -> bool {
+        let (code, modifiers) = normalize(event.code, event.modifiers);
+        let (own_code, own_modifiers) = normalize(self.code, self.modifiers);
+        code == own_code && modifiers == own_modifiers
+    }
+}
+
+const fn normalize(code: KeyCode, modifiers: Modifiers) -> (KeyCode, Modifiers) {
+    let modifiers = modifiers.difference(Modifiers::SUPER);
+    match code {
+        KeyCode::Char(c) => (
+            KeyCode::Char(c.to_ascii_lowercase()),
+            modifiers.difference(Modifiers::SHIFT),
+        ),
+        KeyCode::BackTab => (code, modifiers.difference(Modifiers::SHIFT)),
+        KeyCode::F(_)
+        | KeyCode::Enter
+        | KeyCode::Esc
+        | KeyCode::Tab
+        | KeyCode::Backspace
+        | KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Modifier(_) => (code, modifiers),
+    }
+}
+
+/// Default bindings of the login form
+const FORM_DEFAULTS: [(KeyCode, Modifiers, Action); 23] = [
+    (KeyCode::F(1), Modifiers::NONE, Action::Shutdown),
+    (KeyCode::F(2), Modifiers::NONE, Action::Reboot),
+    (KeyCode::F(3), Modifiers::NONE, Action::Suspend),
+    (KeyCode::F(3), Modifiers::SHIFT, Action::SuspendThenHibernate),
+    (KeyCode::F(4), Modifiers::NONE, Action::NextSession),
+    (KeyCode::F(4), Modifiers::SHIFT, Action::PrevSession),
+    (KeyCode::Down, Modifiers::NONE, Action::NextSession),
+    (KeyCode::Up, Modifiers::NONE, Action::PrevSession),
+    (KeyCode::F(5), Modifiers::NONE, Action::ToggleLargeText),
+    (KeyCode::F(6), Modifiers::NONE, Action::NextBootEntry),
+    (KeyCode::F(7), Modifiers::NONE, Action::FailedUnits),
+    // Development only
+    (KeyCode::Esc, Modifiers::NONE, Action::Quit),
+    (KeyCode::F(12), Modifiers::NONE, Action::Screenshot),
+    (KeyCode::Tab, Modifiers::NONE, Action::NextField),
+    (KeyCode::Tab, Modifiers::SHIFT, Action::PrevField),
+    (KeyCode::BackTab, Modifiers::NONE, Action::PrevField),
+    (KeyCode::Char('u'), Modifiers::CONTROL, Action::EditUsername),
+    (KeyCode::Char('w'), Modifiers::CONTROL, Action::ClearField),
+    (KeyCode::Char('z'), Modifiers::CONTROL, Action::Undo),
+    (KeyCode::Char('k'), Modifiers::CONTROL, Action::Compose),
+    (KeyCode::Char(' '), Modifiers::CONTROL, Action::Kana),
+    (KeyCode::Enter, Modifiers::NONE, Action::Submit),
+    (KeyCode::Backspace, Modifiers::NONE, Action::Backspace),
+];
+
+/// Bindings of a confirmation modal; not remappable
+const CONFIRM: [(KeyCode, Action); 4] = [
+    (KeyCode::Enter, Action::Confirm),
+    (KeyCode::Char('y'), Action::Confirm),
+    (KeyCode::Esc, Action::Dismiss),
+    (KeyCode::Char('n'), Action::Dismiss),
+];
+
+/// Bindings of a scrollable list; not remappable
+const LIST: [(KeyCode, Action); 7] = [
+    (KeyCode::Up, Action::ScrollUp),
+    (KeyCode::Char('k'), Action::ScrollUp),
+    (KeyCode::Down, Action::ScrollDown),
+    (KeyCode::Char('j'), Action::ScrollDown),
+    (KeyCode::Esc, Action::Dismiss),
+    (KeyCode::Enter, Action::Dismiss),
+    (KeyCode::Char('q'), Action::Dismiss),
+];
+
+/// Bindings of the countdown after logging in; the form's session keys
+/// work too
+const HANDOFF: [(KeyCode, Action); 4] = [
+    (KeyCode::Enter, Action::Submit),
+    (KeyCode::Esc, Action::Dismiss),
+    (KeyCode::Down, Action::NextSession),
+    (KeyCode::Up, Action::PrevSession),
+];
+
+/// Key table for every focus context
+#[derive(Debug)]
+pub struct Keymap {
+    form: Vec<(Key, Action)>,
+}
+
+impl Keymap {
+    /// Defaults with the `[keys]` overrides applied. An override replaces all
+    /// default keys of its action; unknown actions or keys are ignored.
+    pub fn new(overrides: &BTreeMap<String, String>) -> Self {
+        let mut form: Vec<(Key, Action)> = FORM_DEFAULTS
+            .iter()
+            .map(|&(code, modifiers, action)| (Key::new(code, modifiers), action))
+            .collect();
+        for (name, spec) in overrides {
+            if let (Some(action), Some(key)) = (Action::from_name(name), Key::parse(spec)) {
+                form.retain(|&(_, bound)| bound != action);
+                // A remapped key stops doing whatever it did before
+                form.retain(|&(bound, _)| bound != key);
+                form.push((key, action));
+            }
+        }
+        Self { form }
+    }
+
+    /// Name of the first key bound to `action` on the form, e.g. `F4`
+    pub fn label(&self, action: Action) -> Option<String> {
+        self.form
+            .iter()
+            .filter(|&&(_, bound)| bound == action)
+            .find_map(|&(key, _)| key.name())
+    }
+
+    /// What `event` means in `context`
+    pub fn action(&self, context: Context, event: KeyEvent) -> Option<Action> {
+        match context {
+            Context::Confirm(_) => CONFIRM
+                .iter()
+                .find(|&&(code, _)| Key::new(code, Modifiers::NONE).matches(event))
+                .map(|&(_, action)| action),
+            Context::FailedUnits => LIST
+                .iter()
+                .find(|&&(code, _)| Key::new(code, Modifiers::NONE).matches(event))
+                .map(|&(_, action)| action),
+            Context::Handoff => HANDOFF
+                .iter()
+                .find(|&&(code, _)| Key::new(code, Modifiers::NONE).matches(event))
+                .map(|&(_, action)| action)
+                .or_else(|| {
+                    self.form
+                        .iter()
+                        .find(|&&(key, action)| {
+                            matches!(action, Action::NextSession | Action::PrevSession)
+                                && key.matches(event)
+                        })
+                        .map(|&(_, action)| action)
+                }),
+            Context::Form(_) => self
+                .form
+                .iter()
+                .find(|(key, _)| key.matches(event))
+                .map(|&(_, action)| action)
+                // Anything else that produces a character is typed
+                .or(if let KeyCode::Char(c) = event.code {
+                    Some(Action::Input(c))
+                } else {
+                    None
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::focus::Field;
+    use crate::power;
+
+    const FORM: Context = Context::Form(Field::Username);
+
+    fn key(code: KeyCode, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_default_form_bindings() {
+        let keymap = Keymap::new(&BTreeMap::new());
+        let none = Modifiers::NONE;
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::F(1), none)),
+            Some(Action::Shutdown)
+        );
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Enter, none)),
+            Some(Action::Submit)
+        );
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Tab, none)),
+            Some(Action::NextField)
+        );
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Tab, Modifiers::SHIFT)),
+            Some(Action::PrevField)
+        );
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::BackTab, Modifiers::SHIFT)),
+            Some(Action::PrevField)
+        );
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Char('u'), Modifiers::CONTROL)),
+            Some(Action::EditUsername)
+        );
+        // Unbound characters are typed, including shifted ones
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Char('u'), none)),
+            Some(Action::Input('u'))
+        );
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Char('U'), Modifiers::SHIFT)),
+            Some(Action::Input('U'))
+        );
+        assert_eq!(keymap.action(FORM, key(KeyCode::F(9), none)), None);
+    }
+
+    #[test]
+    fn test_session_bindings() {
+        let keymap = Keymap::new(&BTreeMap::new());
+        let none = Modifiers::NONE;
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Down, none)),
+            Some(Action::NextSession)
+        );
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::F(4), Modifiers::SHIFT)),
+            Some(Action::PrevSession)
+        );
+        assert_eq!(
+            keymap.action(Context::Handoff, key(KeyCode::Up, none)),
+            Some(Action::PrevSession)
+        );
+        assert_eq!(
+            keymap.action(Context::Handoff, key(KeyCode::F(4), none)),
+            Some(Action::NextSession)
+        );
+        assert_eq!(keymap.label(Action::NextSession).as_deref(), Some("F4"));
+
+        // The countdown follows the form's session keys
+        let overrides = BTreeMap::from([("next_session".to_string(), "ctrl+n".to_string())]);
+        let keymap = Keymap::new(&overrides);
+        let ctrl_n = key(KeyCode::Char('n'), Modifiers::CONTROL);
+        assert_eq!(
+            keymap.action(Context::Handoff, ctrl_n),
+            Some(Action::NextSession)
+        );
+        assert_eq!(
+            keymap.action(Context::Handoff, key(KeyCode::F(4), none)),
+            None
+        );
+        assert_eq!(keymap.label(Action::NextSession).as_deref(), Some("ctrl+n"));
+        assert_eq!(keymap.label(Action::ToggleAirplane), None);
+    }
+
+    #[test]
+    fn test_undo_bindings() {
+        let keymap = Keymap::new(&BTreeMap::new());
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Char('w'), Modifiers::CONTROL)),
+            Some(Action::ClearField)
+        );
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Char('z'), Modifiers::CONTROL)),
+            Some(Action::Undo)
+        );
+    }
+
+    #[test]
+    fn test_confirm_bindings() {
+        let keymap = Keymap::new(&BTreeMap::new());
+        let confirm = Context::Confirm(power::Action::Reboot);
+        let none = Modifiers::NONE;
+        assert_eq!(
+            keymap.action(confirm, key(KeyCode::Char('y'), none)),
+            Some(Action::Confirm)
+        );
+        assert_eq!(
+            keymap.action(confirm, key(KeyCode::Esc, none)),
+            Some(Action::Dismiss)
+        );
+        // Form keys don't leak through the modal
+        assert_eq!(keymap.action(confirm, key(KeyCode::F(1), none)), None);
+        assert_eq!(keymap.action(confirm, key(KeyCode::Char('x'), none)), None);
+    }
+
+    #[test]
+    fn test_list_bindings() {
+        let keymap = Keymap::new(&BTreeMap::new());
+        let none = Modifiers::NONE;
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::F(7), none)),
+            Some(Action::FailedUnits)
+        );
+        let list = Context::FailedUnits;
+        assert_eq!(
+            keymap.action(list, key(KeyCode::Down, none)),
+            Some(Action::ScrollDown)
+        );
+        assert_eq!(
+            keymap.action(list, key(KeyCode::Char('k'), none)),
+            Some(Action::ScrollUp)
+        );
+        assert_eq!(
+            keymap.action(list, key(KeyCode::Esc, none)),
+            Some(Action::Dismiss)
+        );
+        assert_eq!(keymap.action(list, key(KeyCode::Char('x'), none)), None);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides = BTreeMap::from([
+            ("shutdown".to_string(), "ctrl+q".to_string()),
+            ("quit".to_string(), "F10".to_string()),
+            ("bogus".to_string(), "F11".to_string()),
+            ("reboot".to_string(), "ctrl+".to_string()),
+        ]);
+        let keymap = Keymap::new(&overrides);
+        let none = Modifiers::NONE;
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::Char('q'), Modifiers::CONTROL)),
+            Some(Action::Shutdown)
+        );
+        assert_eq!(keymap.action(FORM, key(KeyCode::F(1), none)), None);
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::F(10), none)),
+            Some(Action::Quit)
+        );
+        assert_eq!(keymap.action(FORM, key(KeyCode::Esc, none)), None);
+        assert_eq!(keymap.action(FORM, key(KeyCode::F(11), none)), None);
+        // Invalid key names keep the default
+        assert_eq!(
+            keymap.action(FORM, key(KeyCode::F(2), none)),
+            Some(Action::Reboot)
+        );
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(
+            Key::parse("F12"),
+            Some(Key::new(KeyCode::F(12), Modifiers::NONE))
+        );
+        assert_eq!(
+            Key::parse("Ctrl+Alt+X"),
+            Some(Key::new(
+                KeyCode::Char('x'),
+                Modifiers::CONTROL.union(Modifiers::ALT)
+            ))
+        );
+        assert_eq!(
+            Key::parse("space"),
+            Some(Key::new(KeyCode::Char(' '), Modifiers::NONE))
+        );
+        assert_eq!(Key::parse("F13"), None);
+        assert_eq!(Key::parse("ctrl+"), None);
+        assert_eq!(Key::parse("hyper+u"), None);
+    }
+}
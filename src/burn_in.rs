@@ -0,0 +1,149 @@
+//! Burn-in mitigation for screens that show the greeter around the clock
+//!
+//! OLED panels keep a ghost of whatever stays put for long. With `[display]
+//! burn_in = true` the whole picture moves by a cell every little while,
+//! orbiting its home position, and is dimmed once nobody has typed for a
+//! while. The next key brings it back to full brightness.
+
+use std::time::{Duration, Instant};
+
+use ratatui::layout::Rect;
+
+use crate::config::DisplayConfig;
+
+/// Positions the picture moves through, one cell around its home
+const ORBIT: [(i8, i8); 8] = [
+    (0, 0),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+];
+
+#[derive(Debug, Clone)]
+pub struct BurnIn {
+    shift_interval: Duration,
+    dim_after: Duration,
+    started: Instant,
+    last_input: Instant,
+}
+
+impl BurnIn {
+    /// `None` unless enabled
+    pub fn new(options: &DisplayConfig, now: Instant) -> Option<Self> {
+        options.burn_in.then(|| Self {
+            // A zero interval would move the picture on every frame
+            shift_interval: Duration::from_secs(options.shift_interval.max(1)),
+            dim_after: Duration::from_secs(options.dim_after),
+            started: now,
+            last_input: now,
+        })
+    }
+
+    /// Someone typed; undim
+    pub const fn input(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+
+    /// Nobody typed for `dim_after`
+    pub fn dimmed(&self, now: Instant) -> bool {
+        now.duration_since(self.last_input) >= self.dim_after
+    }
+
+    /// Where `area` is drawn now: one cell in from every edge, moved by the
+    /// current orbit position. Too small areas stay as they are.
+    pub fn shift(&self, area: Rect, now: Instant) -> Rect {
+        if area.width < 3 || area.height < 3 {
+            return area;
+        }
+        let (dx, dy) = ORBIT[self.step(now) % ORBIT.len()];
+        Rect::new(
+            area.x.saturating_add_signed(1 + i16::from(dx)),
+            area.y.saturating_add_signed(1 + i16::from(dy)),
+            area.width - 2,
+            area.height - 2,
+        )
+    }
+
+    /// Time until the picture next moves or dims
+    pub fn next_change(&self, now: Instant) -> Duration {
+        let elapsed = now.duration_since(self.started);
+        let interval = self.shift_interval.as_nanos();
+        let into_step = elapsed.as_nanos() % interval;
+        let next_shift = self
+            .shift_interval
+            .saturating_sub(Duration::from_nanos(u64::try_from(into_step).unwrap_or(0)));
+        let dim = self
+            .dim_after
+            .saturating_sub(now.duration_since(self.last_input));
+        if dim.is_zero() {
+            next_shift
+        } else {
+            next_shift.min(dim)
+        }
+    }
+
+    fn step(&self, now: Instant) -> usize {
+        let steps = now.duration_since(self.started).as_nanos() / self.shift_interval.as_nanos();
+        usize::try_from(steps).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    #[test]
+    fn test_disabled() {
+        assert!(BurnIn::new(&DisplayConfig::default(), Instant::now()).is_none());
+    }
+
+    #[test]
+    fn test_shift() {
+        let start = Instant::now();
+        let options = DisplayConfig {
+            burn_in: true,
+            shift_interval: 60,
+            ..DisplayConfig::default()
+        };
+        let burn_in = BurnIn::new(&options, start).unwrap();
+        let area = Rect::new(0, 0, 80, 24);
+        assert_eq!(burn_in.shift(area, start), Rect::new(1, 1, 78, 22));
+        let later = start + Duration::from_secs(61);
+        assert_eq!(burn_in.shift(area, later), Rect::new(2, 1, 78, 22));
+        let later = start + Duration::from_mins(6);
+        assert_eq!(burn_in.shift(area, later), Rect::new(0, 0, 78, 22));
+        // Full circle
+        let later = start + Duration::from_mins(8);
+        assert_eq!(burn_in.shift(area, later), Rect::new(1, 1, 78, 22));
+
+        let tiny = Rect::new(0, 0, 2, 2);
+        assert_eq!(burn_in.shift(tiny, later), tiny);
+    }
+
+    #[test]
+    fn test_dim_and_next_change() {
+        let time = Timeline::new();
+        let options = DisplayConfig {
+            burn_in: true,
+            shift_interval: 60,
+            dim_after: 300,
+            ..DisplayConfig::default()
+        };
+        let mut burn_in = BurnIn::new(&options, time.start).unwrap();
+        assert!(!burn_in.dimmed(time.secs(299)));
+        assert!(burn_in.dimmed(time.secs(300)));
+        assert_eq!(burn_in.next_change(time.secs(10)), Duration::from_secs(50));
+        assert_eq!(burn_in.next_change(time.secs(290)), Duration::from_secs(10));
+
+        burn_in.input(time.secs(300));
+        assert!(!burn_in.dimmed(time.secs(301)));
+        assert_eq!(burn_in.next_change(time.secs(700)), Duration::from_secs(20));
+    }
+}
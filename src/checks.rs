@@ -0,0 +1,135 @@
+//! Health checks shown as red flags in the header
+//!
+//! `[[checks]]` entries either run a command, where a non-zero exit is a
+//! problem and the first line of its output says which, or use a built-in
+//! parser such as `mdstat` for degraded software RAID. Checks run as a
+//! background task; healthy checks show nothing.
+
+use std::fs;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::config::{Builtin, CheckConfig};
+use crate::status;
+
+/// How often the checks are run again
+pub const INTERVAL: Duration = Duration::from_mins(5);
+
+const MDSTAT: &str = "/proc/mdstat";
+
+/// Problems found, e.g. `RAID: md0 [U_]`. Blocks on the check commands.
+pub fn run(checks: &[CheckConfig]) -> Vec<String> {
+    checks
+        .iter()
+        .filter_map(|check| {
+            let problem = match (check.builtin, check.command.as_deref()) {
+                (Some(Builtin::Mdstat), _) => mdstat(&fs::read_to_string(MDSTAT).ok()?),
+                (None, Some(command)) => run_command(command),
+                (None, None) => None,
+            }?;
+            Some(if problem.is_empty() {
+                check.label.clone()
+            } else {
+                format!("{}: {problem}", check.label)
+            })
+        })
+        .collect()
+}
+
+/// `Some` with the first output line when the command fails. Commands that
+/// can't be started are reported too, a check that never runs is no check.
+fn run_command(command: &str) -> Option<String> {
+    let words = shell_words::split(command).unwrap_or_default();
+    let Some((program, args)) = words.split_first() else {
+        return Some("invalid command".to_string());
+    };
+    let Ok(output) = Command::new(program)
+        .args(args)
+        .envs(status::greeter_session().map(|id| ("GRXXT_SESSION_ID", id)))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return Some(format!("{program} not found"));
+    };
+    if output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(
+        stdout
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_string(),
+    )
+}
+
+/// Arrays with a missing member, e.g. `md0 [U_]`
+fn mdstat(content: &str) -> Option<String> {
+    let mut array = "";
+    let mut degraded = Vec::new();
+    for line in content.lines() {
+        if let Some((name, _)) = line.split_once(" : ") {
+            array = name.trim();
+            continue;
+        }
+        // `976630464 blocks super 1.2 [2/1] [U_]`: one `U` per working member
+        let members = line
+            .split_whitespace()
+            .filter(|word| word.starts_with('[') && word.ends_with(']'))
+            .map(|word| &word[1..word.len() - 1])
+            .find(|inner| !inner.is_empty() && inner.chars().all(|c| c == 'U' || c == '_'));
+        if let Some(members) = members.filter(|members| members.contains('_')) {
+            degraded.push(format!("{array} [{members}]"));
+        }
+    }
+    (!degraded.is_empty()).then(|| degraded.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mdstat() {
+        let healthy = "\
+Personalities : [raid1]
+md0 : active raid1 sdb1[1] sda1[0]
+      976630464 blocks super 1.2 [2/2] [UU]
+      bitmap: 0/8 pages [0KB], 65536KB chunk
+
+unused devices: <none>
+";
+        assert_eq!(mdstat(healthy), None);
+
+        let degraded = "\
+Personalities : [raid1] [raid6] [raid5] [raid4]
+md0 : active raid1 sda1[0]
+      976630464 blocks super 1.2 [2/1] [U_]
+
+md1 : active raid5 sdc1[0] sdd1[1] sde1[2]
+      1953260544 blocks level 5, 512k chunk, algorithm 2 [3/3] [UUU]
+
+md2 : active raid5 sdf1[0] sdh1[2]
+      1953260544 blocks level 5, 512k chunk, algorithm 2 [3/2] [U_U]
+";
+        assert_eq!(mdstat(degraded).as_deref(), Some("md0 [U_], md2 [U_U]"));
+    }
+
+    #[test]
+    fn test_check_commands() {
+        assert_eq!(run_command("true"), None);
+        assert_eq!(
+            run_command("sh -c 'echo; echo bad disk; exit 1'").as_deref(),
+            Some("bad disk")
+        );
+        assert_eq!(run_command("false").as_deref(), Some(""));
+        assert_eq!(
+            run_command("/nonexistent/check").as_deref(),
+            Some("/nonexistent/check not found")
+        );
+        assert_eq!(run_command("'unclosed").as_deref(), Some("invalid command"));
+    }
+}
@@ -0,0 +1,305 @@
+//! Command-line argument parsing
+//!
+//! Kept deliberately small: greetd passes arguments from its `command =` line,
+//! which is enough to set the greeter up without a config file. clap parses
+//! them, but what it doesn't understand is skipped rather than fatal: a typo
+//! on that line must never lock users out. The one subcommand, `preview`, is
+//! for people working on a theme.
+
+use std::env;
+use std::ffi::OsString;
+
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+/// Options passed on the command line
+#[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools, reason = "independent flags")]
+pub struct Args {
+    /// greetd socket path, used when `GREETD_SOCK` is unset
+    pub socket: Option<String>,
+    /// Config file instead of `grxxt.toml` or `/etc/greetd/grxxt.toml`
+    pub config: Option<String>,
+    /// Session command, instead of the config's `session`
+    pub session: Option<String>,
+    /// Config file whose `[theme]` replaces the config's
+    pub theme: Option<String>,
+    /// Trace the login steps to a log file
+    pub debug: bool,
+    /// Run against pretend logins instead of greetd
+    pub demo: bool,
+    /// Print the version and exit
+    pub version: bool,
+    /// `--help`: usage to print before exiting
+    pub help: Option<String>,
+    /// Report problems with the config and exit
+    pub check_config: bool,
+    /// Fill in the last user, like `remember = true`
+    pub remember: bool,
+    /// `grxxt preview`: print the screens instead of running the greeter
+    pub preview: Option<Preview>,
+}
+
+/// Options of `grxxt preview [--theme <config>] [--size <cols>x<rows>]`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Preview {
+    /// Config file whose theme is previewed (default: the normal config)
+    pub theme: Option<String>,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Default for Preview {
+    fn default() -> Self {
+        Self {
+            theme: None,
+            width: 80,
+            height: 24,
+        }
+    }
+}
+
+impl Args {
+    /// Parse arguments from the process command line
+    #[must_use]
+    pub fn parse() -> Self {
+        Self::parse_from(env::args_os())
+    }
+
+    /// Parse arguments from an iterator, program name first. Unknown
+    /// arguments and bad values are ignored.
+    fn parse_from(args: impl IntoIterator<Item = impl Into<OsString>>) -> Self {
+        let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let matches = loop {
+            match command().try_get_matches_from(&args) {
+                Ok(matches) => break matches,
+                Err(e) if e.kind() == ErrorKind::DisplayHelp => {
+                    return Self {
+                        help: Some(e.to_string()),
+                        ..Self::default()
+                    };
+                }
+                Err(e) => match unknown_argument(&e, &args) {
+                    Some(unknown) => {
+                        args.remove(unknown);
+                    }
+                    // A missing or bad value: keep what can be read
+                    None => {
+                        break command()
+                            .ignore_errors(true)
+                            .try_get_matches_from(&args)
+                            .unwrap_or_default();
+                    }
+                },
+            }
+        };
+        Self {
+            socket: matches.get_one::<String>("socket").cloned(),
+            config: matches.get_one::<String>("config").cloned(),
+            session: matches.get_one::<String>("cmd").cloned(),
+            theme: matches.get_one::<String>("theme").cloned(),
+            debug: matches.get_flag("debug"),
+            demo: matches.get_flag("demo"),
+            version: matches.get_flag("version"),
+            help: None,
+            check_config: matches.get_flag("check-config"),
+            remember: matches.get_flag("remember"),
+            preview: matches
+                .subcommand_matches("preview")
+                .map(Preview::from_matches),
+        }
+    }
+}
+
+impl Preview {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let (width, height) = matches
+            .get_one::<String>("size")
+            .and_then(|size| parse_size(size))
+            .unwrap_or((80, 24));
+        Self {
+            theme: matches.get_one::<String>("theme").cloned(),
+            width,
+            height,
+        }
+    }
+}
+
+fn command() -> Command {
+    let value = |name: &'static str, value_name: &'static str, help: &'static str| {
+        Arg::new(name)
+            .long(name)
+            .value_name(value_name)
+            .allow_hyphen_values(true)
+            .help(help)
+    };
+    let flag = |name: &'static str, help: &'static str| {
+        Arg::new(name)
+            .long(name)
+            .action(ArgAction::SetTrue)
+            .help(help)
+    };
+    let preview = Command::new("preview")
+        .about("Print the screens with a theme instead of running the greeter")
+        .arg(value(
+            "theme",
+            "CONFIG",
+            "Config file whose theme is previewed",
+        ))
+        .arg(value("size", "COLSxROWS", "Screen size (default 80x24)"));
+    Command::new("grxxt")
+        .about("Brutalist greetd greeter")
+        .disable_version_flag(true)
+        .arg(value(
+            "socket",
+            "PATH",
+            "greetd socket, when GREETD_SOCK is unset",
+        ))
+        .arg(value("config", "PATH", "Config file to use"))
+        .arg(value(
+            "cmd",
+            "COMMAND",
+            "Session to start, instead of the config's",
+        ))
+        .arg(value(
+            "theme",
+            "CONFIG",
+            "Config file whose [theme] is used",
+        ))
+        .arg(flag(
+            "check-config",
+            "Report problems with the config and exit",
+        ))
+        .arg(flag("remember", "Fill in the last user"))
+        .arg(flag(
+            "debug",
+            "Trace the login steps to /run/grxxt/debug.log",
+        ))
+        .arg(flag("demo", "Run against pretend logins instead of greetd"))
+        .arg(flag("version", "Print the version").short('V'))
+        .subcommand(preview)
+}
+
+/// Position in `args` of the unknown argument or subcommand `error` is
+/// about
+fn unknown_argument(error: &clap::Error, args: &[OsString]) -> Option<usize> {
+    let context = if error.kind() == ErrorKind::UnknownArgument {
+        ContextKind::InvalidArg
+    } else if error.kind() == ErrorKind::InvalidSubcommand {
+        ContextKind::InvalidSubcommand
+    } else {
+        return None;
+    };
+    let Some(ContextValue::String(unknown)) = error.get(context) else {
+        return None;
+    };
+    // Past the program name; `--name=value` is reported as `--name`
+    args.iter()
+        .skip(1)
+        .position(|arg| {
+            let arg = arg.to_string_lossy();
+            arg == unknown.as_str() || arg.starts_with(&format!("{unknown}="))
+        })
+        .map(|position| position + 1)
+}
+
+/// `120x40`
+fn parse_size(size: &str) -> Option<(u16, u16)> {
+    let (width, height) = size.split_once('x')?;
+    let width = width.parse().ok().filter(|&width| width > 0)?;
+    let height = height.parse().ok().filter(|&height| height > 0)?;
+    Some((width, height))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::parse_from(std::iter::once(&"grxxt").chain(args))
+    }
+
+    #[test]
+    fn test_parse_socket() {
+        assert_eq!(
+            parse(&["--socket", "/run/greetd.sock"]).socket.as_deref(),
+            Some("/run/greetd.sock")
+        );
+        assert_eq!(
+            parse(&["--socket=/tmp/g.sock"]).socket.as_deref(),
+            Some("/tmp/g.sock")
+        );
+        assert_eq!(parse(&[]).socket, None);
+        assert_eq!(parse(&["--unknown"]).socket, None);
+        assert_eq!(parse(&["--socket", "/run/greetd.sock"]).preview, None);
+    }
+
+    #[test]
+    fn test_parse_check_config() {
+        let args = parse(&["--check-config", "--config", "/tmp/grxxt.toml"]);
+        assert!(args.check_config);
+        assert_eq!(args.config.as_deref(), Some("/tmp/grxxt.toml"));
+        assert!(!parse(&[]).check_config);
+    }
+
+    #[test]
+    fn test_parse_remember() {
+        assert!(parse(&["--remember"]).remember);
+        assert!(parse(&["--socket", "/run/greetd.sock", "--remember"]).remember);
+        assert!(!parse(&[]).remember);
+    }
+
+    #[test]
+    fn test_parse_greetd_command_line() {
+        let args = parse(&[
+            "--cmd",
+            "sway --unsupported-gpu",
+            "--theme=/usr/share/grxxt/dusk.toml",
+            "--debug",
+            "--demo",
+        ]);
+        assert_eq!(args.session.as_deref(), Some("sway --unsupported-gpu"));
+        assert_eq!(args.theme.as_deref(), Some("/usr/share/grxxt/dusk.toml"));
+        assert!(args.debug);
+        assert!(args.demo);
+        assert!(!args.version);
+        assert!(parse(&["--version"]).version);
+        assert!(parse(&["-V"]).version);
+    }
+
+    #[test]
+    fn test_unknown_arguments_ignored() {
+        let args = parse(&["--bogus=1", "--remember", "-x", "stray", "--debug"]);
+        assert!(args.remember);
+        assert!(args.debug);
+        assert_eq!(
+            parse(&["preview", "--bogus", "--size", "100x30"])
+                .preview
+                .unwrap()
+                .width,
+            100
+        );
+        // A missing value keeps the options before it
+        let args = parse(&["--remember", "--socket"]);
+        assert!(args.remember);
+        assert_eq!(args.socket, None);
+        assert_eq!(args.help, None);
+        assert!(parse(&["--help"]).help.unwrap().contains("--check-config"));
+    }
+
+    #[test]
+    fn test_parse_preview() {
+        assert_eq!(parse(&["preview"]).preview, Some(Preview::default()));
+        assert_eq!(
+            parse(&["preview", "--theme", "dusk.toml", "--size=120x40"]).preview,
+            Some(Preview {
+                theme: Some("dusk.toml".to_string()),
+                width: 120,
+                height: 40,
+            })
+        );
+        let bad_size = parse(&["preview", "--size", "0x40"]).preview.unwrap();
+        assert_eq!((bad_size.width, bad_size.height), (80, 24));
+    }
+}
@@ -0,0 +1,194 @@
+//! Header clock widget
+//!
+//! Formats the date line and secondary clocks from the `[clock]` options;
+//! the header only decides where they go.
+
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::config::{ClockConfig, DateFormat};
+use crate::locale::Locale;
+use crate::scheduler;
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    options: ClockConfig,
+    locale: Locale,
+    /// Secondary clocks with a known timezone
+    zones: Vec<(String, Tz)>,
+    /// The formats show seconds, so the header is redrawn every second
+    seconds: bool,
+}
+
+impl Clock {
+    /// Secondary clocks with an unknown timezone are left out
+    pub fn new(options: ClockConfig, locale: Locale) -> Self {
+        let zones = options
+            .extra
+            .iter()
+            .filter_map(|clock| Some((clock.label.clone(), clock.timezone.parse().ok()?)))
+            .collect();
+        let mut clock = Self {
+            options,
+            locale,
+            zones,
+            seconds: false,
+        };
+        // Whatever the format spells them as (%S, %T, %X, ...), seconds
+        // show as a difference one second later
+        let epoch = DateTime::<Utc>::UNIX_EPOCH;
+        let later = epoch + TimeDelta::seconds(1);
+        clock.seconds = clock.time(&epoch) != clock.time(&later)
+            || clock.date(&epoch) != clock.date(&later);
+        clock
+    }
+
+    /// Time until the header shows something new: the next second when the
+    /// formats show seconds, else the next minute
+    pub fn next_tick(&self) -> Duration {
+        if self.seconds {
+            scheduler::until_next_second()
+        } else {
+            scheduler::until_next_minute()
+        }
+    }
+
+    /// The time, e.g. `20:05`, or `08:05 PM` in a 12-hour locale
+    pub fn time<Z: TimeZone>(&self, now: &DateTime<Z>) -> String
+    where
+        Z::Offset: std::fmt::Display,
+    {
+        let format = self.options.clock_format().unwrap_or_else(|| self.locale.time_format());
+        self.locale.format(now, format)
+    }
+
+    /// Secondary clocks in the same format, e.g. `NYC 08:05`
+    pub fn extra<Z: TimeZone>(&self, now: &DateTime<Z>) -> Vec<String> {
+        self.zones
+            .iter()
+            .map(|(label, zone)| format!("{label} {}", self.time(&now.with_timezone(zone))))
+            .collect()
+    }
+
+    /// Date line, e.g. `MON 04 MAR`, `2024-03-04` or `2024-03-04  W10`
+    pub fn date<Z: TimeZone>(&self, now: &DateTime<Z>) -> String
+    where
+        Z::Offset: std::fmt::Display,
+    {
+        let mut date = match (self.options.date_format(), self.options.date) {
+            (Some(format), _) => self.locale.format(now, format),
+            (None, DateFormat::Locale) => self.locale.date(now).to_uppercase(),
+            (None, DateFormat::Iso) => now.format("%Y-%m-%d").to_string(),
+        };
+        if self.options.week_number {
+            let separator = if date.is_empty() { "" } else { "  " };
+            date.push_str(&now.format(&format!("{separator}W%V")).to_string());
+        }
+        date
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+    use crate::config::ExtraClock;
+    use chrono::Utc;
+
+    #[test]
+    fn test_date_options() {
+        // Sunday of ISO week 52 of 2023
+        let now = Utc.with_ymd_and_hms(2023, 12, 31, 9, 5, 0).unwrap();
+        let clock = |date, week_number| {
+            let options = ClockConfig {
+                date,
+                week_number,
+                ..ClockConfig::default()
+            };
+            Clock::new(options, Locale::default())
+        };
+        assert_eq!(clock(DateFormat::Locale, false).date(&now), "SUN 31 DEC");
+        assert_eq!(clock(DateFormat::Iso, false).date(&now), "2023-12-31");
+        assert_eq!(clock(DateFormat::Iso, true).date(&now), "2023-12-31  W52");
+        assert_eq!(
+            Clock::new(
+                ClockConfig {
+                    date: DateFormat::Locale,
+                    week_number: true,
+                    ..ClockConfig::default()
+                },
+                Locale::parse("de_DE"),
+            )
+            .date(&now),
+            "SO 31 DEZ  W52"
+        );
+    }
+
+    #[test]
+    fn test_custom_formats() {
+        let now = Utc.with_ymd_and_hms(2023, 12, 31, 21, 5, 9).unwrap();
+        let clock = |clock_format: &str, date_format: &str, week_number| {
+            let options = ClockConfig {
+                clock_format: Some(clock_format.to_string()),
+                date_format: Some(date_format.to_string()),
+                week_number,
+                ..ClockConfig::default()
+            };
+            Clock::new(options, Locale::parse("de_DE"))
+        };
+        let seconds = clock("%H:%M:%S", "%A, %d. %B", false);
+        assert_eq!(seconds.time(&now), "21:05:09");
+        assert_eq!(seconds.date(&now), "Sonntag, 31. Dezember");
+        assert_eq!(clock("%H:%M", "", false).date(&now), "");
+        assert_eq!(clock("%H:%M", "", true).date(&now), "W52");
+
+        // Broken formats fall back to the defaults
+        let broken = clock("%H:%Q", "%Y-%", false);
+        assert_eq!(broken.time(&now), "21:05");
+        assert_eq!(broken.date(&now), "SO 31 DEZ");
+        assert_eq!(
+            broken.options.format_warnings(),
+            ["invalid clock_format \"%H:%Q\"", "invalid date_format \"%Y-%\""]
+        );
+    }
+
+    #[test]
+    fn test_seconds_tick() {
+        let clock = |clock_format: Option<&str>, date_format: Option<&str>| {
+            let options = ClockConfig {
+                clock_format: clock_format.map(str::to_string),
+                date_format: date_format.map(str::to_string),
+                ..ClockConfig::default()
+            };
+            Clock::new(options, Locale::default())
+        };
+        assert!(!clock(None, None).seconds);
+        assert!(!clock(Some("%I:%M %p"), Some("%F")).seconds);
+        assert!(clock(Some("%H:%M:%S"), None).seconds);
+        assert!(clock(Some("%T"), None).seconds);
+        assert!(clock(None, Some("%c")).seconds);
+        assert!(clock(None, None).next_tick() <= Duration::from_mins(1));
+        assert!(clock(Some("%T"), None).next_tick() <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_extra_clocks() {
+        let now = Utc.with_ymd_and_hms(2024, 7, 1, 12, 30, 0).unwrap();
+        let extra = |label: &str, timezone: &str| ExtraClock {
+            label: label.to_string(),
+            timezone: timezone.to_string(),
+        };
+        let options = ClockConfig {
+            extra: vec![
+                extra("NYC", "America/New_York"),
+                extra("Mars", "Olympus/Mons"),
+                extra("IST", "Asia/Kolkata"),
+            ],
+            ..ClockConfig::default()
+        };
+        let clock = Clock::new(options, Locale::default());
+        assert_eq!(clock.extra(&now), ["NYC 08:30", "IST 18:00"]);
+    }
+}
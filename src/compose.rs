@@ -0,0 +1,225 @@
+//! Compose sequences for accented characters
+//!
+//! The Linux console has no input method, and its dead keys only work with
+//! a keymap that defines them, so passwords like `café` can't be typed on
+//! a US keymap. The compose key (Ctrl+K, as in Vim digraphs) followed by two
+//! characters produces one, e.g. Ctrl+K `'` `e` gives `é`. The two characters
+//! may come in either order; unknown pairs type nothing.
+
+/// Built-in sequences, modelled on X11's Compose table
+const TABLE: [(&str, char); 94] = [
+    // Acute
+    ("'a", 'á'),
+    ("'e", 'é'),
+    ("'i", 'í'),
+    ("'o", 'ó'),
+    ("'u", 'ú'),
+    ("'y", 'ý'),
+    ("'A", 'Á'),
+    ("'E", 'É'),
+    ("'I", 'Í'),
+    ("'O", 'Ó'),
+    ("'U", 'Ú'),
+    ("'Y", 'Ý'),
+    ("'c", 'ć'),
+    ("'n", 'ń'),
+    ("'s", 'ś'),
+    ("'z", 'ź'),
+    // Grave
+    ("`a", 'à'),
+    ("`e", 'è'),
+    ("`i", 'ì'),
+    ("`o", 'ò'),
+    ("`u", 'ù'),
+    ("`A", 'À'),
+    ("`E", 'È'),
+    ("`I", 'Ì'),
+    ("`O", 'Ò'),
+    ("`U", 'Ù'),
+    // Circumflex
+    ("^a", 'â'),
+    ("^e", 'ê'),
+    ("^i", 'î'),
+    ("^o", 'ô'),
+    ("^u", 'û'),
+    ("^A", 'Â'),
+    ("^E", 'Ê'),
+    ("^I", 'Î'),
+    ("^O", 'Ô'),
+    ("^U", 'Û'),
+    // Diaeresis
+    ("\"a", 'ä'),
+    ("\"e", 'ë'),
+    ("\"i", 'ï'),
+    ("\"o", 'ö'),
+    ("\"u", 'ü'),
+    ("\"y", 'ÿ'),
+    ("\"A", 'Ä'),
+    ("\"E", 'Ë'),
+    ("\"I", 'Ï'),
+    ("\"O", 'Ö'),
+    ("\"U", 'Ü'),
+    // Tilde
+    ("~a", 'ã'),
+    ("~n", 'ñ'),
+    ("~o", 'õ'),
+    ("~A", 'Ã'),
+    ("~N", 'Ñ'),
+    ("~O", 'Õ'),
+    // Cedilla
+    (",c", 'ç'),
+    (",C", 'Ç'),
+    (",s", 'ş'),
+    (",S", 'Ş'),
+    // Caron
+    ("cc", 'č'),
+    ("cs", 'š'),
+    ("cz", 'ž'),
+    ("cr", 'ř'),
+    ("ce", 'ě'),
+    ("cC", 'Č'),
+    ("cS", 'Š'),
+    ("cZ", 'Ž'),
+    ("cR", 'Ř'),
+    ("cE", 'Ě'),
+    // Ring and stroke
+    ("oa", 'å'),
+    ("oA", 'Å'),
+    ("/o", 'ø'),
+    ("/O", 'Ø'),
+    ("/l", 'ł'),
+    ("/L", 'Ł'),
+    // Ligatures
+    ("ss", 'ß'),
+    ("ae", 'æ'),
+    ("AE", 'Æ'),
+    ("oe", 'œ'),
+    ("OE", 'Œ'),
+    // Ogonek and dot above (Polish)
+    (";a", 'ą'),
+    (";e", 'ę'),
+    (";A", 'Ą'),
+    (";E", 'Ę'),
+    (".z", 'ż'),
+    (".Z", 'Ż'),
+    // Symbols
+    ("=e", '€'),
+    ("-l", '£'),
+    ("=y", '¥'),
+    ("!!", '¡'),
+    ("??", '¿'),
+    ("<<", '«'),
+    (">>", '»'),
+    ("oo", '°'),
+    ("xx", '×'),
+    ("-:", '÷'),
+];
+
+/// Where a compose sequence stands
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Compose {
+    #[default]
+    Off,
+    /// Compose key pressed, waiting for the first character
+    Started,
+    /// Waiting for the second character
+    First(char),
+}
+
+impl Compose {
+    /// Compose key pressed; pressing it again mid-sequence cancels
+    pub fn start(&mut self) {
+        *self = if *self == Self::Off {
+            Self::Started
+        } else {
+            Self::Off
+        };
+    }
+
+    /// Drop a sequence in progress; `true` if there was one
+    pub fn cancel(&mut self) -> bool {
+        std::mem::take(self) != Self::Off
+    }
+
+    /// Feed a typed character. Returns the character to insert, if any.
+    pub fn feed(&mut self, c: char) -> Option<char> {
+        match std::mem::take(self) {
+            Self::Off => Some(c),
+            Self::Started => {
+                *self = Self::First(c);
+                None
+            }
+            Self::First(first) => lookup(first, c).or_else(|| lookup(c, first)),
+        }
+    }
+
+    /// Sequence in progress for display. The characters typed so far are
+    /// not shown: they can be part of the password.
+    pub const fn label(self) -> Option<&'static str> {
+        match self {
+            Self::Off => None,
+            Self::Started | Self::First(_) => Some("COMPOSE"),
+        }
+    }
+}
+
+fn lookup(first: char, second: char) -> Option<char> {
+    TABLE.iter().find_map(|&(keys, result)| {
+        let mut chars = keys.chars();
+        (chars.next() == Some(first) && chars.next() == Some(second)).then_some(result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compose(keys: &str) -> Vec<char> {
+        let mut compose = Compose::default();
+        compose.start();
+        keys.chars().filter_map(|c| compose.feed(c)).collect()
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(compose("'e"), ['é']);
+        // Either order
+        assert_eq!(compose("e'"), ['é']);
+        assert_eq!(compose("ss"), ['ß']);
+        assert_eq!(compose("\"O"), ['Ö']);
+        // Only one character is composed, the rest is typed as is
+        assert_eq!(compose("~nx"), ['ñ', 'x']);
+        // Unknown pairs type nothing
+        assert_eq!(compose("qz"), []);
+    }
+
+    #[test]
+    fn test_start_and_cancel() {
+        let mut compose = Compose::default();
+        assert_eq!(compose.label(), None);
+        compose.start();
+        assert_eq!(compose.feed('^'), None);
+        assert_eq!(compose.label(), Some("COMPOSE"));
+        assert!(compose.cancel());
+        assert_eq!(compose.feed('a'), Some('a'));
+        assert!(!compose.cancel());
+
+        // The compose key again mid-sequence cancels
+        compose.start();
+        compose.start();
+        assert_eq!(compose, Compose::Off);
+    }
+
+    #[test]
+    fn test_table_has_no_duplicates() {
+        for (i, (keys, _)) in TABLE.iter().enumerate() {
+            let reversed: String = keys.chars().rev().collect();
+            assert!(
+                TABLE[i + 1..]
+                    .iter()
+                    .all(|(other, _)| other != keys && *other != reversed),
+                "{keys} is listed twice"
+            );
+        }
+    }
+}
@@ -2,24 +2,750 @@
 //!
 //! Reads settings from /etc/greetd/grxxt.toml
 
+use chrono::format::StrftimeItems;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::power::{Action, Inhibit};
+use crate::sessions::{self, Kind, Launcher};
 
 const CONFIG_PATH: &str = "/etc/greetd/grxxt.toml";
+const LOCAL_CONFIG_PATH: &str = "grxxt.toml";
 const DEFAULT_SESSION: &str = "/usr/local/bin/start-hyprland.sh";
+const DEFAULT_SESSION_LOG: &str = "~/.local/share/grxxt/session.log";
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_AUTH_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_X11_WRAPPER: &str = "startx /usr/bin/env";
+const DEFAULT_SUCCESS_MESSAGE: &str = "access granted";
+const DEFAULT_SUCCESS_DURATION_MS: u64 = 600;
+const DEFAULT_SPEAK_COMMAND: &str = "spd-say";
 
-#[derive(Debug, Deserialize)]
+/// Login mechanism used to authenticate and start sessions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Delegate to the greetd daemon (the normal setup)
+    #[default]
+    Greetd,
+    /// Direct PAM login without greetd (experimental, `pam` feature)
+    Pam,
+    /// Pretend logins for working on a theme; only `--demo` selects it
+    #[serde(skip)]
+    Demo,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[allow(clippy::struct_excessive_bools, reason = "independent switches of the config file")]
 pub struct Config {
-    #[serde(default = "default_session")]
-    pub session: String,
+    /// Session command; defaults to greetd's own default session
+    #[serde(default)]
+    pub session: Option<String>,
 
     #[serde(default)]
     pub avatar: Option<String>,
 
+    /// greetd socket path, used when `GREETD_SOCK` is unset
+    #[serde(default)]
+    pub socket: Option<String>,
+
+    #[serde(default)]
+    pub backend: Backend,
+
+    /// PAM service of the `pam` backend; defaults to `login`
+    #[serde(default)]
+    pub pam_service: Option<String>,
+
+    /// Seconds without an answer from greetd before a login is given up
+    /// (default 60, 0 = wait forever)
+    #[serde(default)]
+    pub auth_timeout_secs: Option<u64>,
+
+    /// Failed attempts before the form resets, like login(1)'s retry limit
+    /// (default 3, 0 = unlimited)
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+
+    /// Seconds an error message stays on screen (default 0 = until the
+    /// next key)
+    #[serde(default)]
+    pub error_timeout_secs: u64,
+
+    /// Typing leaves error messages alone; only `error_timeout_secs` takes
+    /// them away
+    #[serde(default)]
+    pub keep_errors_while_typing: bool,
+
+    /// Seconds each auth stack notice is shown when there are several, in
+    /// turns (default 0 = all at once)
+    #[serde(default)]
+    pub notice_rotate_secs: u64,
+
+    /// Prometheus textfile for login metrics (off when unset)
+    #[serde(default)]
+    pub metrics_file: Option<String>,
+
+    /// Unix socket streaming login events as JSON lines (off when unset)
+    #[serde(default)]
+    pub events_socket: Option<String>,
+
+    /// How sessions found in `/usr/share/wayland-sessions` are launched
+    #[serde(default)]
+    pub launcher: Launcher,
+
+    #[serde(default)]
+    pub session_log: SessionLogConfig,
+
+    #[serde(default)]
+    pub sessions: SessionsConfig,
+
+    #[serde(default)]
+    pub environment: EnvironmentConfig,
+
+    /// Prefix for X11 session commands that starts the X server
+    /// (default `startx /usr/bin/env`, empty = none)
+    #[serde(default)]
+    pub x11_wrapper: Option<String>,
+
+    #[serde(default)]
+    pub fallback: FallbackConfig,
+
+    #[serde(default)]
+    pub clock: ClockConfig,
+
+    #[serde(default)]
+    pub greeting: GreetingConfig,
+
+    #[serde(default)]
+    pub fields: FieldsConfig,
+
+    #[serde(default)]
+    pub display: DisplayConfig,
+
+    /// Health checks flagged in the header
+    #[serde(default)]
+    pub checks: Vec<CheckConfig>,
+
+    #[serde(default)]
+    pub resources: ResourcesConfig,
+
+    #[serde(default)]
+    pub success: SuccessConfig,
+
+    #[serde(default)]
+    pub handoff: HandoffConfig,
+
+    #[serde(default)]
+    pub accessibility: AccessibilityConfig,
+
+    /// Helper that unlocks an encrypted home after login (`unlock-hook` feature)
+    #[serde(default)]
+    pub unlock: Option<UnlockConfig>,
+
+    /// Console keymap loaded while the greeter runs, e.g. `de-latin1`, for
+    /// systems without a layout in vconsole.conf
+    #[serde(default)]
+    pub keymap: Option<String>,
+
+    /// Locale of the header date, e.g. `de_DE` (default: the system locale)
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// Layout direction; `auto` mirrors it for right-to-left languages
+    #[serde(default)]
+    pub direction: Direction,
+
+    /// Hide the password length from onlookers (public terminals)
+    #[serde(default)]
+    pub password_mask: PasswordMask,
+
+    /// Key remapping, action name to key name (see `bindings`)
+    #[serde(default)]
+    pub keys: BTreeMap<String, String>,
+
+    /// Power actions that ask for confirmation first, e.g. `["shutdown", "reboot"]`
+    #[serde(default)]
+    pub confirm_power: Vec<Action>,
+
+    #[serde(default)]
+    pub power: PowerConfig,
+
+    /// Start on the password field as the last user; Ctrl+U edits the username
+    #[serde(default)]
+    pub password_first: bool,
+
+    /// Fill in the last user and start on the password field; the name is
+    /// locked until Ctrl+U
+    #[serde(default)]
+    pub remember: bool,
+
+    /// Latch modifier presses for the next key (Ctrl, then U = Ctrl+U)
+    #[serde(default)]
+    pub sticky_keys: bool,
+
+    /// Skip the avatar pipeline, terminal probing and the alternate screen
+    /// — for very low-end boards on the console
+    #[serde(default)]
+    pub low_resource: bool,
+
     #[serde(default)]
     pub theme: ThemeConfig,
 }
 
+/// How the password field hides what is typed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordMask {
+    /// One `*` per character
+    #[default]
+    Char,
+    /// Same fixed-width mask whatever the length
+    Fixed,
+    /// Mask length changes randomly on every keystroke
+    Random,
+}
+
+/// Where the session's stdout/stderr end up
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogMode {
+    /// Leave output wherever greetd sends it
+    #[default]
+    None,
+    /// Redirect to `path`, keeping the previous log as `<path>.old`
+    File,
+    /// Send to the journal via `systemd-cat`
+    Journal,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionLogConfig {
+    #[serde(default)]
+    pub mode: LogMode,
+
+    /// Log file for `mode = "file"`; `~` is the user's home
+    #[serde(default = "default_session_log")]
+    pub path: String,
+}
+
+impl Default for SessionLogConfig {
+    fn default() -> Self {
+        Self {
+            mode: LogMode::default(),
+            path: default_session_log(),
+        }
+    }
+}
+
+/// Casing applied to error messages when they are drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageTransform {
+    /// All caps, matching the brutalist look
+    #[default]
+    Upper,
+    /// Show messages exactly as PAM/greetd reported them
+    None,
+    /// All lowercase
+    Lower,
+}
+
+/// Which side the layout starts on
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Right to left when the locale's language is written that way
+    #[default]
+    Auto,
+    Ltr,
+    Rtl,
+}
+
+/// Post-auth unlock helper, run with the password on stdin
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct UnlockConfig {
+    /// Command line; `%u` is replaced by the username
+    pub command: String,
+}
+
+/// `environment.d(5)`-style files added to the session environment
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnvironmentConfig {
+    /// System-wide `*.conf` files, applied to every session
+    #[serde(default = "default_environment_dir")]
+    pub dir: String,
+
+    /// Also read the user's `~/.config/environment.d`, when the greeter can
+    #[serde(default)]
+    pub user: bool,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_environment_dir(),
+            user: false,
+        }
+    }
+}
+
+/// What the session list shows, layered over the scanned `.desktop` files
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SessionsConfig {
+    /// Entries that have no `.desktop` file
+    #[serde(default)]
+    pub extra: Vec<ExtraSession>,
+
+    /// Desktop ids to hide; `*` matches anything (e.g. `*-xorg`)
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    /// Display names by desktop id
+    #[serde(default)]
+    pub rename: BTreeMap<String, String>,
+
+    /// Desktop ids or names listed first, in this order; the rest follow by name
+    #[serde(default)]
+    pub order: Vec<String>,
+
+    /// Session types by desktop id or name, where the directory is wrong
+    #[serde(default)]
+    pub kind: BTreeMap<String, Kind>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtraSession {
+    pub name: String,
+    pub command: String,
+    /// `wayland`, `x11` or `shell` (the default)
+    #[serde(default)]
+    pub kind: Option<Kind>,
+}
+
+/// Date line of the header clock
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateFormat {
+    /// Weekday, day and month in the configured locale (`MON 04 MAR`)
+    #[default]
+    Locale,
+    /// ISO 8601 calendar date (`2024-03-04`)
+    Iso,
+}
+
+/// Header clock options
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ClockConfig {
+    #[serde(default)]
+    pub date: DateFormat,
+
+    /// strftime string for the time, e.g. `%H:%M:%S` or `%I:%M %p`
+    #[serde(default)]
+    pub clock_format: Option<String>,
+
+    /// strftime string for the date line, replacing `date`; empty for none
+    #[serde(default)]
+    pub date_format: Option<String>,
+
+    /// Append the ISO 8601 week number (`W10`)
+    #[serde(default)]
+    pub week_number: bool,
+
+    /// Secondary clocks shown next to the time
+    #[serde(default)]
+    pub extra: Vec<ExtraClock>,
+}
+
+impl ClockConfig {
+    /// Time format, when `clock_format` is set and valid
+    #[must_use]
+    pub fn clock_format(&self) -> Option<&str> {
+        self.clock_format.as_deref().filter(|format| valid_format(format))
+    }
+
+    /// Date format, when `date_format` is set and valid
+    #[must_use]
+    pub fn date_format(&self) -> Option<&str> {
+        self.date_format.as_deref().filter(|format| valid_format(format))
+    }
+
+    /// Format strings that can't be used, e.g. `invalid clock_format "%Q"`
+    #[must_use]
+    pub fn format_warnings(&self) -> Vec<String> {
+        [("clock_format", &self.clock_format), ("date_format", &self.date_format)]
+            .into_iter()
+            .filter_map(|(key, format)| {
+                let format = format.as_deref()?;
+                (!valid_format(format)).then(|| format!("invalid {key} {format:?}"))
+            })
+            .collect()
+    }
+}
+
+/// chrono can format with `format`; it would panic mid-draw otherwise
+fn valid_format(format: &str) -> bool {
+    StrftimeItems::new(format).parse().is_ok()
+}
+
+/// A `[[clock.extra]]` entry
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtraClock {
+    pub label: String,
+    /// IANA name, e.g. `America/New_York`
+    pub timezone: String,
+}
+
+/// `[greeting]`: a line above the form by time of day
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GreetingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Phrases from 5:00, one picked per day; empty for the built-in one
+    #[serde(default)]
+    pub morning: Vec<String>,
+
+    /// Phrases from 12:00
+    #[serde(default)]
+    pub afternoon: Vec<String>,
+
+    /// Phrases from 18:00
+    #[serde(default)]
+    pub evening: Vec<String>,
+
+    /// Phrases from 22:00
+    #[serde(default)]
+    pub night: Vec<String>,
+
+    /// Type the greeting out at this many milliseconds per character, on
+    /// startup and when waking a dimmed or idle screen (0 = off)
+    #[serde(default)]
+    pub typewriter: u64,
+}
+
+/// `[power]`: how power actions are handed to systemd
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PowerConfig {
+    /// Flush filesystems with `sync` before shutting down or rebooting
+    #[serde(default)]
+    pub sync: bool,
+
+    /// Run `systemctl --no-block`, queueing the job without waiting for it
+    #[serde(default)]
+    pub no_block: bool,
+
+    /// Power actions confirmed by holding their key down, e.g. `["shutdown"]`
+    #[serde(default)]
+    pub hold: Vec<Action>,
+
+    /// Key handling logind leaves alone while the greeter is up, e.g.
+    /// `["handle-lid-switch"]` so a closed-lid server doesn't sleep
+    #[serde(default)]
+    pub inhibit: Vec<Inhibit>,
+
+    /// Other systems a reboot can go to, picked with F6
+    #[serde(default)]
+    pub boot: Vec<BootEntry>,
+
+    #[serde(default)]
+    pub idle: IdleConfig,
+}
+
+/// `[power.idle]`: suspend after sitting at the greeter unused
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdleConfig {
+    /// Minutes without input before suspending; 0 never does
+    #[serde(default)]
+    pub suspend_after: u64,
+
+    /// Seconds the countdown is shown before suspending
+    #[serde(default = "default_countdown")]
+    pub countdown: u64,
+
+    /// Stay awake on AC power (and on machines without a battery)
+    #[serde(default)]
+    pub except_on_ac: bool,
+
+    /// Stay awake while anyone is logged in remotely, e.g. over SSH
+    #[serde(default = "default_except_remote")]
+    pub except_remote: bool,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            suspend_after: 0,
+            countdown: default_countdown(),
+            except_on_ac: false,
+            except_remote: default_except_remote(),
+        }
+    }
+}
+
+const fn default_countdown() -> u64 {
+    60
+}
+
+const fn default_except_remote() -> bool {
+    true
+}
+
+/// `[[power.boot]]`: a boot entry to reboot into once, e.g. Windows
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BootEntry {
+    /// Shown in the header, e.g. `Windows`
+    pub label: String,
+
+    /// Entry id for `bootctl set-oneshot` (`windows.conf`, `auto-windows`)
+    /// or the menu entry for `grub-reboot`
+    pub entry: String,
+
+    #[serde(default)]
+    pub loader: Bootloader,
+}
+
+/// Bootloader that picks the entry of the next boot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Bootloader {
+    #[default]
+    SystemdBoot,
+    Grub,
+}
+
+/// `[[checks]]`: a health check, by command or built in
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckConfig {
+    /// Shown with the problem, e.g. `RAID`
+    pub label: String,
+
+    /// Command whose non-zero exit is a problem; its first output line
+    /// says which
+    #[serde(default)]
+    pub command: Option<String>,
+
+    /// A built-in check instead of a command
+    #[serde(default)]
+    pub builtin: Option<Builtin>,
+}
+
+/// Checks grxxt does itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Builtin {
+    /// Degraded md arrays in /proc/mdstat
+    Mdstat,
+}
+
+/// `[display]`: care for screens that show the greeter all day
+#[derive(Debug, Clone, Deserialize)]
+pub struct DisplayConfig {
+    /// Move the picture by a cell now and then and dim it when idle
+    #[serde(default)]
+    pub burn_in: bool,
+
+    /// Seconds between moves
+    #[serde(default = "default_shift_interval")]
+    pub shift_interval: u64,
+
+    /// Seconds without input before the picture is dimmed
+    #[serde(default = "default_dim_after")]
+    pub dim_after: u64,
+
+    /// Milliseconds the picture takes to fade in at startup and when
+    /// undimmed (0 = appear at once)
+    #[serde(default)]
+    pub fade_in: u64,
+
+    /// Animated background once nobody has typed for `effect_after`
+    #[serde(default)]
+    pub effect: Option<EffectKind>,
+
+    /// Seconds without input before the effect starts
+    #[serde(default = "default_effect_after")]
+    pub effect_after: u64,
+}
+
+/// Idle background animations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EffectKind {
+    /// Columns of characters running down
+    Rain,
+    /// Stars flying towards the viewer
+    Starfield,
+    /// Flickering dither specks
+    Noise,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            burn_in: false,
+            shift_interval: default_shift_interval(),
+            dim_after: default_dim_after(),
+            fade_in: 0,
+            effect: None,
+            effect_after: default_effect_after(),
+        }
+    }
+}
+
+const fn default_shift_interval() -> u64 {
+    60
+}
+
+const fn default_dim_after() -> u64 {
+    300
+}
+
+const fn default_effect_after() -> u64 {
+    300
+}
+
+/// `[resources]`: warn before logging in to a machine that is out of room
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResourcesConfig {
+    /// Warn when a filesystem has less than this percent free (0 = never)
+    #[serde(default = "default_min_disk_free")]
+    pub min_disk_free: u8,
+
+    /// Filesystems checked, by a path on them
+    #[serde(default = "default_disks")]
+    pub disks: Vec<String>,
+
+    /// Warn when less than this percent of the memory is available (0 = never)
+    #[serde(default = "default_min_memory")]
+    pub min_memory: u8,
+}
+
+impl Default for ResourcesConfig {
+    fn default() -> Self {
+        Self {
+            min_disk_free: default_min_disk_free(),
+            disks: default_disks(),
+            min_memory: default_min_memory(),
+        }
+    }
+}
+
+const fn default_min_disk_free() -> u8 {
+    5
+}
+
+fn default_disks() -> Vec<String> {
+    vec!["/".to_string(), "/home".to_string()]
+}
+
+const fn default_min_memory() -> u8 {
+    5
+}
+
+/// Text shown with one of the login form fields
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FieldText {
+    /// Shown inside the empty field instead of the default
+    #[serde(default)]
+    pub placeholder: Option<String>,
+
+    /// Shown on the row above the field
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// `[fields.username]` and `[fields.password]`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FieldsConfig {
+    #[serde(default)]
+    pub username: FieldText,
+
+    #[serde(default)]
+    pub password: FieldText,
+}
+
+/// Session offered after the chosen one keeps dying right after login
+#[derive(Debug, Clone, Deserialize)]
+pub struct FallbackConfig {
+    /// Command line of the fallback session (e.g. a plain shell)
+    #[serde(default)]
+    pub session: Option<String>,
+
+    /// Quick failures in a row before the fallback is selected
+    #[serde(default = "default_fallback_failures")]
+    pub max_failures: u32,
+
+    /// A session that ends sooner than this counts as failed to start
+    #[serde(default = "default_fallback_uptime")]
+    pub min_uptime_secs: u64,
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        Self {
+            session: None,
+            max_failures: default_fallback_failures(),
+            min_uptime_secs: default_fallback_uptime(),
+        }
+    }
+}
+
+/// Confirmation shown after the session was started, before grxxt exits
+#[derive(Debug, Clone, Deserialize)]
+pub struct SuccessConfig {
+    #[serde(default = "default_success_message")]
+    pub message: String,
+
+    /// How long the confirmation stays up, in milliseconds; 0 skips it
+    #[serde(default = "default_success_duration")]
+    pub duration_ms: u64,
+
+    /// Text file with a logo; the confirmation becomes a splash screen of
+    /// the logo and the message
+    #[serde(default)]
+    pub logo: Option<String>,
+}
+
+impl Default for SuccessConfig {
+    fn default() -> Self {
+        Self {
+            message: default_success_message(),
+            duration_ms: default_success_duration(),
+            logo: None,
+        }
+    }
+}
+
+/// `[accessibility]`: spoken announcements for blind users
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccessibilityConfig {
+    /// Speak the focused field and new messages as they change
+    #[serde(default)]
+    pub announce: bool,
+
+    /// Command that speaks the text given as its last argument
+    #[serde(default = "default_speak_command")]
+    pub command: String,
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        Self {
+            announce: false,
+            command: default_speak_command(),
+        }
+    }
+}
+
+/// `[handoff]`: time to pick another session after logging in
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HandoffConfig {
+    /// Seconds counted down before the session starts; 0 starts it at once
+    #[serde(default)]
+    pub countdown: u64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ThemeConfig {
     #[serde(default = "default_background")]
@@ -33,6 +759,167 @@ pub struct ThemeConfig {
 
     #[serde(default = "default_error")]
     pub error: String,
+
+    /// Maximum rows for error messages; longer ones are word-wrapped up to this
+    #[serde(default = "default_message_lines")]
+    pub message_lines: u16,
+
+    #[serde(default)]
+    pub message_transform: MessageTransform,
+
+    /// Start in large text mode (also toggled with F5)
+    #[serde(default)]
+    pub large_text: bool,
+
+    /// Console font loaded in large text mode on a VT
+    #[serde(default = "default_large_font")]
+    pub large_font: String,
+
+    #[serde(default)]
+    pub input_style: InputStyle,
+
+    /// How the focused field stands out besides its color
+    #[serde(default)]
+    pub focus_indicator: FocusIndicator,
+
+    /// What marks a list that doesn't fit its box
+    #[serde(default)]
+    pub scrollbar: ScrollbarStyle,
+
+    /// Characters boxes are drawn with, unless `[theme.borders]` or
+    /// `[theme.avatar]` pick others
+    #[serde(default)]
+    pub border: BorderSet,
+
+    #[serde(default)]
+    pub borders: WidgetBorders,
+
+    #[serde(default)]
+    pub avatar: AvatarStyle,
+}
+
+/// Characters a box is drawn with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BorderSet {
+    #[default]
+    #[serde(alias = "light")]
+    Plain,
+    Rounded,
+    Double,
+    #[serde(alias = "heavy")]
+    Thick,
+    /// `+`, `-` and `|`, for fonts without box-drawing characters
+    Ascii,
+}
+
+/// `[theme.borders]`: border sets of single widgets
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct WidgetBorders {
+    /// Username and password fields in the `box` and `underline` styles
+    #[serde(default)]
+    pub input: Option<BorderSet>,
+
+    /// Lists opened over the form (failed units)
+    #[serde(default)]
+    pub popup: Option<BorderSet>,
+}
+
+/// How the username and password fields are drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputStyle {
+    /// Bordered box
+    #[default]
+    Box,
+    /// A line under the text, no box
+    Underline,
+    /// Solid bar in the foreground color (accent when focused)
+    Filled,
+    /// Just the text
+    Minimal,
+}
+
+/// What marks the focused field, for those who can't tell the accent color
+/// apart
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusIndicator {
+    /// Only the accent color
+    #[default]
+    Color,
+    /// An arrow beside the field
+    Arrow,
+    /// The field's label in reverse video
+    Inverted,
+    /// A heavy border around the field
+    Heavy,
+}
+
+/// Mark on the right border of a list that doesn't fit its box
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrollbarStyle {
+    /// A thumb sized and placed like the visible part
+    #[default]
+    Bar,
+    /// ▲ and ▼ where more lines are hidden
+    Arrows,
+    /// Nothing
+    None,
+}
+
+/// Frame around the avatar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AvatarBorder {
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+    Ascii,
+    None,
+}
+
+impl AvatarBorder {
+    /// Characters of the frame; `None` for no frame
+    #[must_use]
+    pub const fn set(self) -> Option<BorderSet> {
+        match self {
+            Self::Plain => Some(BorderSet::Plain),
+            Self::Rounded => Some(BorderSet::Rounded),
+            Self::Double => Some(BorderSet::Double),
+            Self::Thick => Some(BorderSet::Thick),
+            Self::Ascii => Some(BorderSet::Ascii),
+            Self::None => None,
+        }
+    }
+}
+
+/// Text under the avatar
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AvatarCaption {
+    #[default]
+    None,
+    Username,
+    /// The GECOS name from /etc/passwd, else the username
+    FullName,
+}
+
+/// `[theme.avatar]`: how the avatar block is framed
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct AvatarStyle {
+    /// Frame around the image; the theme's `border` when unset
+    #[serde(default)]
+    pub border: Option<AvatarBorder>,
+
+    #[serde(default)]
+    pub caption: AvatarCaption,
+
+    /// Blank cells between the frame and the image
+    #[serde(default)]
+    pub padding: u16,
 }
 
 impl Default for ThemeConfig {
@@ -42,12 +929,46 @@ impl Default for ThemeConfig {
             foreground: default_foreground(),
             accent: default_accent(),
             error: default_error(),
+            message_lines: default_message_lines(),
+            message_transform: MessageTransform::default(),
+            large_text: false,
+            large_font: default_large_font(),
+            input_style: InputStyle::default(),
+            focus_indicator: FocusIndicator::default(),
+            scrollbar: ScrollbarStyle::default(),
+            border: BorderSet::default(),
+            borders: WidgetBorders::default(),
+            avatar: AvatarStyle::default(),
         }
     }
 }
 
-fn default_session() -> String {
-    DEFAULT_SESSION.to_string()
+fn default_environment_dir() -> String {
+    "/etc/greetd/grxxt-env.d".to_string()
+}
+
+fn default_session_log() -> String {
+    DEFAULT_SESSION_LOG.to_string()
+}
+
+const fn default_fallback_failures() -> u32 {
+    3
+}
+
+const fn default_fallback_uptime() -> u64 {
+    30
+}
+
+fn default_success_message() -> String {
+    DEFAULT_SUCCESS_MESSAGE.to_string()
+}
+
+fn default_speak_command() -> String {
+    DEFAULT_SPEAK_COMMAND.to_string()
+}
+
+const fn default_success_duration() -> u64 {
+    DEFAULT_SUCCESS_DURATION_MS
 }
 
 fn default_background() -> String {
@@ -66,26 +987,107 @@ fn default_error() -> String {
     "#d14b64".to_string()
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            session: default_session(),
-            avatar: None,
-            theme: ThemeConfig::default(),
-        }
-    }
+fn default_large_font() -> String {
+    "ter-v32b".to_string()
+}
+
+const fn default_message_lines() -> u16 {
+    3
 }
 
 impl Config {
+    /// Session command to offer first: the configured one, then greetd's
+    /// default session from its own config, then the built-in default
+    pub fn session_command(&self) -> String {
+        self.session
+            .clone()
+            .or_else(sessions::greetd_default)
+            .unwrap_or_else(|| DEFAULT_SESSION.to_string())
+    }
+
+    /// Command prefix for sessions from `/usr/share/xsessions`
+    #[must_use]
+    pub fn x11_wrapper(&self) -> &str {
+        self.x11_wrapper.as_deref().unwrap_or(DEFAULT_X11_WRAPPER)
+    }
+
+    /// Failed attempts allowed per user before the form resets; 0 = unlimited
+    #[must_use]
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS)
+    }
+
+    /// How long greetd may take to answer; `None` waits forever
+    #[must_use]
+    pub fn auth_timeout(&self) -> Option<Duration> {
+        match self.auth_timeout_secs.unwrap_or(DEFAULT_AUTH_TIMEOUT_SECS) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// How long error messages stay on screen; `None` until the next key
+    #[must_use]
+    pub const fn error_timeout(&self) -> Option<Duration> {
+        match self.error_timeout_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// How long each of several notices is shown; `None` shows them all
+    #[must_use]
+    pub const fn notice_rotate(&self) -> Option<Duration> {
+        match self.notice_rotate_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
     /// Load configuration, checking local `grxxt.toml` then the system path
+    #[must_use]
     pub fn load() -> Self {
-        // Local config first (development), then system path (production)
-        fs::read_to_string("grxxt.toml")
-            .or_else(|_| fs::read_to_string(CONFIG_PATH))
-            .map_or_else(
-                |_| Self::default(),
-                |content| toml::from_str(&content).unwrap_or_default(),
-            )
+        Self::load_from(Self::path())
+    }
+
+    /// Where the configuration is read from: local config first
+    /// (development), then the system path (production)
+    #[must_use]
+    pub fn path() -> &'static Path {
+        let local = Path::new(LOCAL_CONFIG_PATH);
+        if local.exists() {
+            local
+        } else {
+            Path::new(CONFIG_PATH)
+        }
+    }
+
+    /// Load configuration from `path`; a missing file yields the defaults
+    #[must_use]
+    pub fn load_from(path: &Path) -> Self {
+        fs::read_to_string(path).map_or_else(|_| Self::default(), |content| Self::parse(&content))
+    }
+
+    /// Load configuration from `path`, reporting what [`Config::load_from`]
+    /// quietly replaces with the defaults. A missing file is still fine.
+    ///
+    /// # Errors
+    ///
+    /// What is wrong with the file: unreadable, not TOML, or a key with the
+    /// wrong type.
+    pub fn check(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| e.to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Parse configuration text; invalid TOML yields the defaults so a broken
+    /// config never locks users out
+    #[must_use]
+    pub fn parse(content: &str) -> Self {
+        toml::from_str(content).unwrap_or_default()
     }
 }
 
@@ -97,24 +1099,67 @@ mod tests {
     #[test]
     fn test_default_config() {
         let config = Config::default();
-        assert_eq!(config.session, DEFAULT_SESSION);
+        assert_eq!(config.session, None);
         assert_eq!(config.theme.background, "#0b0a13");
+        assert_eq!(config.backend, Backend::Greetd);
+        assert_eq!(config.max_attempts(), 3);
+        assert_eq!(config.auth_timeout(), Some(Duration::from_mins(1)));
+    }
+
+    #[test]
+    fn test_parse_invalid_config() {
+        let config = Config::parse("session = [not toml");
+        assert_eq!(config.session, None);
+        assert_eq!(config.theme.accent, "#f1c35f");
     }
 
     #[test]
     fn test_parse_config() {
         let toml = r##"
 session = "/bin/bash"
+password_mask = "fixed"
+max_attempts = 0
+auth_timeout_secs = 0
 
 [theme]
 background = "#000000"
 foreground = "#ffffff"
+border = "heavy"
+
+[theme.borders]
+popup = "ascii"
+
+[theme.avatar]
+border = "double"
+caption = "full_name"
+
+[[power.boot]]
+label = "Windows"
+entry = "auto-windows"
+
+[[power.boot]]
+label = "Rescue"
+entry = "Advanced options>Recovery mode"
+loader = "grub"
 "##;
         let config: Config = toml::from_str(toml).unwrap();
-        assert_eq!(config.session, "/bin/bash");
+        assert_eq!(config.session.as_deref(), Some("/bin/bash"));
+        assert_eq!(config.password_mask, PasswordMask::Fixed);
+        assert_eq!(config.max_attempts(), 0);
+        assert_eq!(config.auth_timeout(), None);
         assert_eq!(config.theme.background, "#000000");
         assert_eq!(config.theme.foreground, "#ffffff");
+        assert_eq!(config.theme.border, BorderSet::Thick);
+        assert_eq!(config.theme.borders.popup, Some(BorderSet::Ascii));
+        assert_eq!(config.theme.borders.input, None);
+        assert_eq!(config.theme.avatar.border, Some(AvatarBorder::Double));
+        assert_eq!(config.theme.avatar.caption, AvatarCaption::FullName);
+        assert_eq!(config.theme.avatar.padding, 0);
+        assert_eq!(config.power.boot.len(), 2);
+        assert_eq!(config.power.boot[0].loader, Bootloader::SystemdBoot);
+        assert_eq!(config.power.boot[1].loader, Bootloader::Grub);
         // Defaults for unspecified
         assert_eq!(config.theme.accent, "#f1c35f");
+        assert_eq!(config.session_log.mode, LogMode::None);
     }
 }
@@ -0,0 +1,147 @@
+//! Linux virtual console control
+//!
+//! Font and keymap changes only apply when grxxt runs on a VT
+//! (`TERM=linux`); in terminal emulators they are no-ops. Uses the kbd
+//! tools, like the rest of the system does.
+
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Console font in use before large text was switched on
+const SAVED_FONT: &str = "/run/grxxt/font.psf";
+
+/// Console keymap in use before the configured one was loaded
+const SAVED_KEYMAP: &str = "/run/grxxt/keymap.map";
+
+/// Keymap loaded by [`set_keymap`]
+static KEYMAP: OnceLock<String> = OnceLock::new();
+
+/// Running on a Linux virtual console
+fn is_vt() -> bool {
+    env::var("TERM").is_ok_and(|term| term == "linux")
+}
+
+/// `─`, the line every border is made of
+const BOX_HORIZONTAL: u16 = 0x2500;
+
+/// `struct unipair` of `<linux/kd.h>`: a character and the font glyph
+/// drawing it
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct UniPair {
+    unicode: u16,
+    #[allow(dead_code, reason = "filled in by the kernel, only the characters matter")]
+    fontpos: u16,
+}
+
+/// `struct unimapdesc` of `<linux/kd.h>`
+#[repr(C)]
+struct UnimapDesc {
+    entry_ct: u16,
+    entries: *mut UniPair,
+}
+
+nix::ioctl_readwrite_bad!(get_unimap, 0x4B66, UnimapDesc);
+
+/// Whether the console font can draw box-drawing lines. Terminal emulators
+/// are trusted to; on a VT the font's Unicode map is asked.
+#[must_use]
+pub fn box_drawing() -> bool {
+    !is_vt() || font_has(BOX_HORIZONTAL).unwrap_or(true)
+}
+
+/// Whether the VT's font maps `unicode` to a glyph; `None` when the map
+/// can't be read
+fn font_has(unicode: u16) -> Option<bool> {
+    let mut entries = vec![UniPair::default(); usize::from(u16::MAX)];
+    let mut map = UnimapDesc {
+        entry_ct: u16::MAX,
+        entries: entries.as_mut_ptr(),
+    };
+    // SAFETY: `map` points at `entry_ct` writable entries (GIO_UNIMAP)
+    unsafe { get_unimap(io::stdin().as_raw_fd(), &raw mut map) }.ok()?;
+    let count = usize::from(map.entry_ct).min(entries.len());
+    Some(entries[..count].iter().any(|pair| pair.unicode == unicode))
+}
+
+/// Terminal line of stdin relative to `/dev` (e.g. `tty1`)
+#[must_use]
+pub fn tty_line() -> Option<String> {
+    let path = fs::read_link("/proc/self/fd/0").ok()?;
+    Some(path.strip_prefix("/dev").ok()?.to_string_lossy().into_owned())
+}
+
+/// Switch the console to `font` (e.g. `ter-v32b`), saving the current font
+/// for [`restore_font`]. Off a VT the terminal's own font stays.
+pub fn set_large_font(font: &str) {
+    if is_vt() {
+        setfont(&["-O", SAVED_FONT, font]);
+    }
+}
+
+/// Go back to the font saved by [`set_large_font`]
+pub fn restore_font() {
+    if is_vt() {
+        setfont(&[SAVED_FONT]);
+    }
+}
+
+/// Load the console keymap `keymap` (e.g. `de-latin1`), saving the current
+/// one for [`restore_keymap`]. The kernel keymap is shared by all VTs, so it
+/// must be restored before the session starts.
+pub fn set_keymap(keymap: &str) -> bool {
+    let loaded = is_vt() && dumpkeys(SAVED_KEYMAP) && loadkeys(keymap);
+    if loaded {
+        KEYMAP.get_or_init(|| keymap.to_string());
+    }
+    loaded
+}
+
+/// Load the keymap from [`set_keymap`] again. udev rules for a new
+/// keyboard may have put the system keymap back.
+#[cfg(feature = "hotplug")]
+pub fn reload_keymap() {
+    if let Some(keymap) = KEYMAP.get() {
+        loadkeys(keymap);
+    }
+}
+
+/// Go back to the keymap saved by [`set_keymap`]
+pub fn restore_keymap() {
+    if is_vt() {
+        loadkeys(SAVED_KEYMAP);
+    }
+}
+
+fn dumpkeys(path: &str) -> bool {
+    let Ok(file) = File::create(path) else {
+        return false;
+    };
+    Command::new("dumpkeys")
+        .stdout(file)
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn loadkeys(keymap: &str) -> bool {
+    Command::new("loadkeys")
+        .args(["--quiet", keymap])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn setfont(args: &[&str]) -> bool {
+    Command::new("setfont")
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
@@ -0,0 +1,43 @@
+//! `--debug`: a trace of what the greeter did
+//!
+//! The terminal belongs to the UI, so the trace goes to a file next to the
+//! greeter's other state, one timestamped line per step: the config read,
+//! logins sent and the backend's verdicts, sessions started. Passwords,
+//! usernames and answers to prompts never go in it.
+
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::os::unix::fs::OpenOptionsExt;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use chrono::Local;
+
+pub const LOG_FILE: &str = "/run/grxxt/debug.log";
+
+static LOG: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Start the trace, readable by root only; `false` when the file can't be
+/// opened
+pub fn enable() -> bool {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(LOG_FILE);
+    let Ok(file) = file else {
+        return false;
+    };
+    LOG.set(Mutex::new(file)).is_ok()
+}
+
+/// Add a line to the trace, when it is on
+pub fn log(message: impl Display) {
+    if let Some(log) = LOG.get() {
+        let line = format!("{} {message}\n", Local::now().format("%F %T%.3f"));
+        log.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .write_all(line.as_bytes())
+            .ok();
+    }
+}
@@ -0,0 +1,83 @@
+//! `grxxt --demo`: the greeter against a pretend login
+//!
+//! For working on a theme without greetd or a second machine: any password
+//! is accepted after a second, like a real auth stack taking its time. A few
+//! magic passwords show the other screens: `fail` is rejected and `prompt`
+//! asks a follow-up question, which any answer gets past.
+
+use std::thread;
+use std::time::Duration;
+
+use crate::auth::{AuthBackend, AuthError, Outcome, Prompt};
+
+/// How long the pretend auth stack thinks
+const DELAY: Duration = Duration::from_secs(1);
+
+/// Password that is rejected
+const FAIL: &str = "fail";
+
+/// Password that is answered with a question
+const PROMPT: &str = "prompt";
+
+#[derive(Debug)]
+pub struct Demo {
+    delay: Duration,
+}
+
+impl Default for Demo {
+    fn default() -> Self {
+        Self { delay: DELAY }
+    }
+}
+
+impl AuthBackend for Demo {
+    fn authenticate(&mut self, _username: &str, password: &str) -> Result<Outcome, AuthError> {
+        thread::sleep(self.delay);
+        match password {
+            FAIL => Err(AuthError::AuthFailed("Authentication failed".into())),
+            PROMPT => Ok(Outcome::Prompt(Prompt {
+                message: "Verification code:".into(),
+                notices: vec!["A code was sent to your phone.".into()],
+                secret: false,
+            })),
+            _ => Ok(Outcome::Authenticated(Vec::new())),
+        }
+    }
+
+    fn answer(&mut self, _response: &str) -> Result<Outcome, AuthError> {
+        thread::sleep(self.delay);
+        Ok(Outcome::Authenticated(vec!["Welcome to the demo.".into()]))
+    }
+
+    fn start(&mut self, _session_cmd: &str, _env: &[String]) -> Result<(), AuthError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_passwords() {
+        let mut demo = Demo {
+            delay: Duration::ZERO,
+        };
+        assert_eq!(
+            demo.authenticate("alice", "hunter2"),
+            Ok(Outcome::Authenticated(Vec::new()))
+        );
+        assert!(matches!(
+            demo.authenticate("alice", FAIL),
+            Err(AuthError::AuthFailed(_))
+        ));
+        assert!(matches!(
+            demo.authenticate("alice", PROMPT),
+            Ok(Outcome::Prompt(_))
+        ));
+        assert!(matches!(
+            demo.answer("123456"),
+            Ok(Outcome::Authenticated(_))
+        ));
+    }
+}
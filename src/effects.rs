@@ -0,0 +1,432 @@
+//! Animated backgrounds for an idle greeter
+//!
+//! With `[display] effect` set, the empty cells around the clock and the
+//! form fill with matrix rain, a starfield or dither noise once nobody has
+//! typed for `effect_after` seconds; the next key clears them. Effects run
+//! at a few frames a second, are switched off for good when their frames
+//! keep taking longer than the budget, and pause while on battery.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+
+use crate::config::{DisplayConfig, EffectKind};
+use crate::theme::Theme;
+
+/// Time between frames
+const FRAME: Duration = Duration::from_millis(125);
+
+/// Longest stepping or drawing a frame may take
+const BUDGET: Duration = Duration::from_millis(4);
+
+/// Frames over budget in a row before the effect is switched off
+const MAX_SLOW_FRAMES: u8 = 3;
+
+/// How often the power supply is checked again
+const POWER_CHECK: Duration = Duration::from_mins(1);
+
+/// Characters of the rain
+const RAIN_GLYPHS: &[char] = &[
+    '0', '1', '2', '3', '4', '5', '7', '8', '9', 'Z', ':', '.', '=', '+', '-', '<', '>', '|',
+];
+
+/// Distance at which stars appear
+const FAR: u16 = 32;
+
+/// An animation drawn into the free cells of the screen
+pub trait Effect {
+    /// Advance a frame on a screen of `area`'s size
+    fn step(&mut self, area: Rect, rng: &mut Rng);
+    /// Draw the current frame
+    fn draw(&self, canvas: &mut Canvas);
+}
+
+impl EffectKind {
+    fn build(self) -> Box<dyn Effect> {
+        match self {
+            Self::Rain => Box::new(Rain::default()),
+            Self::Starfield => Box::new(Starfield::default()),
+            Self::Noise => Box::new(Noise::default()),
+        }
+    }
+}
+
+/// The frame buffer as effects see it: cells already drawn on are kept
+pub struct Canvas<'a> {
+    buffer: &'a mut Buffer,
+    /// The form fields, left blank even where empty
+    keep_out: Rect,
+    bright: Style,
+    dim: Style,
+}
+
+impl Canvas<'_> {
+    /// Put `glyph` at `(x, y)` unless something is drawn there already or
+    /// it is inside the form
+    pub fn put(&mut self, x: u16, y: u16, glyph: char, bright: bool) {
+        if self.keep_out.contains((x, y).into()) {
+            return;
+        }
+        let Some(cell) = self.buffer.cell_mut((x, y)) else {
+            return;
+        };
+        if cell.symbol() == " " {
+            cell.set_char(glyph)
+                .set_style(if bright { self.bright } else { self.dim });
+        }
+    }
+}
+
+/// xorshift; effects need variety, not quality
+pub struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u8(0);
+        Self(hasher.finish() | 1)
+    }
+
+    const fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number below `n`
+    pub fn below(&mut self, n: u16) -> u16 {
+        u16::try_from(self.next_u64() % u64::from(n.max(1))).unwrap_or(0)
+    }
+}
+
+/// The effect, and when it runs
+pub struct Screensaver {
+    effect: Box<dyn Effect>,
+    rng: Rng,
+    after: Duration,
+    /// When the power supply is checked, and the effect started if on AC
+    check_at: Instant,
+    running: bool,
+    /// Size of the screen last drawn
+    area: Rect,
+    slow_frames: u8,
+    /// Too slow for this machine; stays off
+    disabled: bool,
+}
+
+impl Screensaver {
+    /// `None` unless an effect is set
+    pub fn new(options: &DisplayConfig, now: Instant) -> Option<Self> {
+        let effect = options.effect?.build();
+        let after = Duration::from_secs(options.effect_after);
+        Some(Self {
+            effect,
+            rng: Rng::new(),
+            after,
+            check_at: now + after,
+            running: false,
+            area: Rect::default(),
+            slow_frames: 0,
+            disabled: false,
+        })
+    }
+
+    /// Someone typed; clear the effect and start over
+    pub fn input(&mut self, now: Instant) {
+        self.running = false;
+        self.check_at = now + self.after;
+    }
+
+    /// The effect is on screen
+    pub const fn running(&self) -> bool {
+        self.running
+    }
+
+    /// Whether to check the power supply now
+    pub fn check_due(&self, now: Instant) -> bool {
+        !self.disabled && now >= self.check_at
+    }
+
+    /// Run the effect on AC power, pause it on battery
+    pub fn checked(&mut self, on_ac: bool, now: Instant) {
+        self.running = on_ac;
+        self.check_at = now + POWER_CHECK;
+    }
+
+    /// Advance a frame, when running
+    pub fn step(&mut self) {
+        if self.running {
+            let start = Instant::now();
+            self.effect.step(self.area, &mut self.rng);
+            self.account(start.elapsed());
+        }
+    }
+
+    /// Draw into the free cells of `buffer` outside `keep_out`, when running
+    pub fn draw(&mut self, buffer: &mut Buffer, keep_out: Rect, theme: &Theme) {
+        if !self.running {
+            return;
+        }
+        let start = Instant::now();
+        self.area = buffer.area;
+        let mut canvas = Canvas {
+            buffer,
+            keep_out,
+            bright: Style::default().fg(theme.accent),
+            dim: Style::default()
+                .fg(theme.foreground)
+                .add_modifier(Modifier::DIM),
+        };
+        self.effect.draw(&mut canvas);
+        self.account(start.elapsed());
+    }
+
+    /// Switch off after a few slow frames in a row
+    fn account(&mut self, spent: Duration) {
+        if spent <= BUDGET {
+            self.slow_frames = 0;
+            return;
+        }
+        self.slow_frames += 1;
+        if self.slow_frames >= MAX_SLOW_FRAMES {
+            self.disabled = true;
+            self.running = false;
+        }
+    }
+
+    /// Time until the effect timer should fire again; `None` once disabled
+    pub fn next_poll(&self, now: Instant) -> Option<Duration> {
+        let check = self.check_at.saturating_duration_since(now);
+        match (self.disabled, self.running) {
+            (true, _) => None,
+            (false, true) => Some(check.min(FRAME)),
+            (false, false) => Some(check),
+        }
+    }
+}
+
+/// Columns of characters running down the screen
+#[derive(Default)]
+struct Rain {
+    /// Row of the leading character and length of each column's drop
+    drops: Vec<(i32, u16)>,
+    height: u16,
+}
+
+impl Rain {
+    fn respawn(&self, rng: &mut Rng) -> (i32, u16) {
+        (
+            -i32::from(rng.below(self.height)),
+            4 + rng.below(self.height / 2),
+        )
+    }
+}
+
+impl Effect for Rain {
+    fn step(&mut self, area: Rect, rng: &mut Rng) {
+        if self.drops.len() != usize::from(area.width) || self.height != area.height {
+            self.height = area.height;
+            self.drops = (0..area.width).map(|_| self.respawn(rng)).collect();
+        }
+        for index in 0..self.drops.len() {
+            let (head, len) = self.drops[index];
+            self.drops[index] = if head - i32::from(len) > i32::from(self.height) {
+                self.respawn(rng)
+            } else {
+                (head + 1, len)
+            };
+        }
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        for (x, &(head, len)) in (0u16..).zip(&self.drops) {
+            for y in (head - i32::from(len) + 1).max(0)..=head {
+                let Ok(y) = u16::try_from(y) else {
+                    continue;
+                };
+                // Each cell keeps its character; the drops reveal them
+                let glyph = RAIN_GLYPHS[usize::from(x ^ y.wrapping_mul(31)) % RAIN_GLYPHS.len()];
+                canvas.put(x, y, glyph, i32::from(y) == head);
+            }
+        }
+    }
+}
+
+/// Stars flying towards the viewer
+#[derive(Default)]
+struct Starfield {
+    /// Offset from the center at the far distance, and distance
+    stars: Vec<(i32, i32, i32)>,
+    area: Rect,
+}
+
+impl Starfield {
+    fn spawn(area: Rect, rng: &mut Rng) -> (i32, i32, i32) {
+        let mut half = |size: u16| i32::from(rng.below(size)) - i32::from(size / 2);
+        (half(area.width), half(area.height), i32::from(FAR))
+    }
+
+    /// Screen cell of a star, if on screen
+    fn project(&self, (x, y, z): (i32, i32, i32)) -> Option<(u16, u16)> {
+        let far = i32::from(FAR);
+        let column = i32::from(self.area.width / 2) + x * far / z.max(1);
+        let row = i32::from(self.area.height / 2) + y * far / z.max(1);
+        let column = u16::try_from(column)
+            .ok()
+            .filter(|&c| c < self.area.width)?;
+        let row = u16::try_from(row).ok().filter(|&r| r < self.area.height)?;
+        Some((column, row))
+    }
+}
+
+impl Effect for Starfield {
+    fn step(&mut self, area: Rect, rng: &mut Rng) {
+        if self.area != area {
+            self.area = area;
+            // Spread over all distances so the field doesn't arrive as a wave
+            self.stars = (0..area.area() / 40)
+                .map(|_| {
+                    let (x, y, _) = Self::spawn(area, rng);
+                    (x, y, 1 + i32::from(rng.below(FAR)))
+                })
+                .collect();
+        }
+        for index in 0..self.stars.len() {
+            let (x, y, z) = self.stars[index];
+            let star = (x, y, z - 1);
+            self.stars[index] = if z <= 1 || self.project(star).is_none() {
+                Self::spawn(area, rng)
+            } else {
+                star
+            };
+        }
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        for &star in &self.stars {
+            if let Some((x, y)) = self.project(star) {
+                let near = star.2 < i32::from(FAR / 3);
+                canvas.put(x, y, if near { '*' } else { '.' }, near);
+            }
+        }
+    }
+}
+
+/// Specks scattered anew on every frame
+#[derive(Default)]
+struct Noise {
+    specks: Vec<(u16, u16, char)>,
+}
+
+impl Effect for Noise {
+    fn step(&mut self, area: Rect, rng: &mut Rng) {
+        let count = area.area() / 50;
+        self.specks = (0..count)
+            .map(|_| {
+                let glyph = ['░', '▒', '·'][usize::from(rng.below(3))];
+                (rng.below(area.width), rng.below(area.height), glyph)
+            })
+            .collect();
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        for &(x, y, glyph) in &self.specks {
+            canvas.put(x, y, glyph, false);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    #[test]
+    fn test_schedule() {
+        let time = Timeline::new();
+        let mut options = DisplayConfig::default();
+        assert!(Screensaver::new(&options, time.start).is_none());
+
+        options.effect = Some(EffectKind::Rain);
+        options.effect_after = 300;
+        let mut saver = Screensaver::new(&options, time.start).unwrap();
+        assert!(!saver.check_due(time.secs(299)));
+        assert_eq!(saver.next_poll(time.start), Some(Duration::from_mins(5)));
+        assert!(saver.check_due(time.secs(300)));
+        // On battery: check again later
+        saver.checked(false, time.secs(300));
+        assert!(!saver.running);
+        assert_eq!(saver.next_poll(time.secs(300)), Some(POWER_CHECK));
+        saver.checked(true, time.secs(360));
+        assert_eq!(saver.next_poll(time.secs(360)), Some(FRAME));
+
+        saver.input(time.secs(400));
+        assert!(!saver.running);
+        assert_eq!(
+            saver.next_poll(time.secs(400)),
+            Some(Duration::from_mins(5))
+        );
+    }
+
+    #[test]
+    fn test_budget() {
+        let start = Instant::now();
+        let options = DisplayConfig {
+            effect: Some(EffectKind::Noise),
+            ..DisplayConfig::default()
+        };
+        let mut saver = Screensaver::new(&options, start).unwrap();
+        saver.checked(true, start);
+        saver.account(BUDGET * 2);
+        saver.account(BUDGET * 2);
+        saver.account(BUDGET);
+        assert!(saver.running);
+        for _ in 0..MAX_SLOW_FRAMES {
+            saver.account(BUDGET * 2);
+        }
+        assert!(!saver.running);
+        assert_eq!(saver.next_poll(start), None);
+        assert!(!saver.check_due(start + Duration::from_hours(1)));
+    }
+
+    #[test]
+    fn test_effects_draw_around_text() {
+        let area = Rect::new(0, 0, 80, 24);
+        let mut rng = Rng::new();
+        let theme = Theme::default();
+        for kind in [EffectKind::Rain, EffectKind::Starfield, EffectKind::Noise] {
+            let mut effect = kind.build();
+            let mut buffer = Buffer::empty(area);
+            buffer.set_string(0, 0, "x".repeat(80), Style::default());
+            for _ in 0..40 {
+                effect.step(area, &mut rng);
+            }
+            let field = Rect::new(20, 10, 40, 3);
+            let mut canvas = Canvas {
+                buffer: &mut buffer,
+                keep_out: field,
+                bright: Style::default().fg(theme.accent),
+                dim: Style::default(),
+            };
+            effect.draw(&mut canvas);
+            assert!((0..80).all(|x| buffer[(x, 0)].symbol() == "x"), "{kind:?}");
+            assert!(
+                field.positions().all(|cell| buffer[cell].symbol() == " "),
+                "{kind:?}"
+            );
+            let drawn = buffer
+                .content
+                .iter()
+                .filter(|cell| cell.symbol() != " ")
+                .count();
+            assert!(drawn > 80, "{kind:?} drew nothing");
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Session environment
+//!
+//! Builds the `KEY=value` variables passed to the session on start, so a
+//! compositor launched from the greeter gets the system locale and keyboard
+//! layout instead of POSIX/US defaults. `environment.d`-style files (the
+//! `[environment]` directory, and the user's own when readable) add to that.
+
+use std::fs;
+use std::path::Path;
+
+use nix::unistd::User;
+
+use crate::config::EnvironmentConfig;
+
+pub const LOCALE_CONF: &str = "/etc/locale.conf";
+const VCONSOLE_CONF: &str = "/etc/vconsole.conf";
+
+/// systemd's default `PATH`, for files that extend `$PATH`
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/bin";
+
+/// vconsole.conf keys mapped to the variables xkbcommon reads
+const XKB_VARS: [(&str, &str); 4] = [
+    ("XKBLAYOUT", "XKB_DEFAULT_LAYOUT"),
+    ("XKBMODEL", "XKB_DEFAULT_MODEL"),
+    ("XKBVARIANT", "XKB_DEFAULT_VARIANT"),
+    ("XKBOPTIONS", "XKB_DEFAULT_OPTIONS"),
+];
+
+/// Environment for `username`'s new session, as `KEY=value` strings
+pub fn session_env(username: &str, config: &EnvironmentConfig) -> Vec<String> {
+    let mut env = Vec::new();
+
+    if let Ok(content) = fs::read_to_string(LOCALE_CONF) {
+        env.extend(locale_vars(&parse_env_file(&content)));
+    }
+    if let Ok(content) = fs::read_to_string(VCONSOLE_CONF) {
+        env.extend(keyboard_vars(&parse_env_file(&content)));
+    }
+
+    let home = User::from_name(username)
+        .ok()
+        .flatten()
+        .map(|user| user.dir.to_string_lossy().into_owned());
+    let mut files = conf_files(Path::new(&config.dir));
+    if config.user {
+        // Usually unreadable for the greeter user; then there is nothing to add
+        if let Some(ref home) = home {
+            files.extend(conf_files(&Path::new(home).join(".config/environment.d")));
+        }
+    }
+    let mut vars = vec![("PATH".to_string(), DEFAULT_PATH.to_string())];
+    if let Some(home) = home {
+        vars.push(("HOME".to_string(), home));
+    }
+    vars.push(("USER".to_string(), username.to_string()));
+    let base = vars.clone();
+    for content in files {
+        environment_d(&content, &mut vars);
+    }
+    for (key, value) in vars.into_iter().filter(|var| !base.contains(var)) {
+        env.retain(|entry| entry.split_once('=').map(|(k, _)| k) != Some(key.as_str()));
+        env.push(format!("{key}={value}"));
+    }
+
+    env
+}
+
+/// Contents of the `*.conf` files in `dir`, ordered by file name
+fn conf_files(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "conf"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .collect()
+}
+
+/// Real name of `username` from the GECOS field of their user record
+pub fn full_name(username: &str) -> Option<String> {
+    let user = User::from_name(username).ok().flatten()?;
+    gecos_name(&user.gecos.to_string_lossy())
+}
+
+/// First subfield of a GECOS field, when set
+fn gecos_name(gecos: &str) -> Option<String> {
+    let name = gecos.split(',').next().unwrap_or_default().trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Apply an `environment.d(5)` file to `vars`: assignments in order, each
+/// able to expand `$VAR`, `${VAR}`, `${VAR:-default}` and `${VAR:+alternate}`
+/// from earlier ones. A reassigned variable moves to the end.
+fn environment_d(content: &str, vars: &mut Vec<(String, String)>) {
+    for (key, value) in parse_env_file(content) {
+        let valid = !key.is_empty()
+            && !key.starts_with(|c: char| c.is_ascii_digit())
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            continue;
+        }
+        let value = expand(&value, vars);
+        vars.retain(|(existing, _)| *existing != key);
+        vars.push((key, value));
+    }
+}
+
+fn expand(value: &str, vars: &[(String, String)]) -> String {
+    let lookup = |name: &str| {
+        vars.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.is_empty())
+    };
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(at) = rest.find('$') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+        if let Some(braced) = rest.strip_prefix('{') {
+            let Some(end) = braced.find('}') else {
+                out.push_str("${");
+                rest = braced;
+                continue;
+            };
+            let expr = &braced[..end];
+            rest = &braced[end + 1..];
+            let expanded = if let Some((name, default)) = expr.split_once(":-") {
+                lookup(name).unwrap_or(default)
+            } else if let Some((name, alternate)) = expr.split_once(":+") {
+                lookup(name).map_or("", |_| alternate)
+            } else {
+                lookup(expr).unwrap_or_default()
+            };
+            out.push_str(expanded);
+        } else {
+            let end = rest.find(|c: char| !is_name(c)).unwrap_or(rest.len());
+            if end == 0 {
+                out.push('$');
+            } else {
+                out.push_str(lookup(&rest[..end]).unwrap_or_default());
+            }
+            rest = &rest[end..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Parse a shell-style `KEY=value` file, skipping comments and stripping quotes
+pub fn parse_env_file(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// `LANG`, `LANGUAGE` and `LC_*` entries from locale.conf
+fn locale_vars(vars: &[(String, String)]) -> Vec<String> {
+    vars.iter()
+        .filter(|(key, _)| key == "LANG" || key == "LANGUAGE" || key.starts_with("LC_"))
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect()
+}
+
+/// `XKB_DEFAULT_*` entries from vconsole.conf. Without `XKBLAYOUT`, the
+/// layout is derived from the console `KEYMAP` (e.g. `de-latin1` → `de`).
+fn keyboard_vars(vars: &[(String, String)]) -> Vec<String> {
+    let lookup = |name: &str| {
+        vars.iter()
+            .find(|(key, value)| key == name && !value.is_empty())
+            .map(|(_, value)| value.as_str())
+    };
+
+    let mut env: Vec<String> = XKB_VARS
+        .iter()
+        .filter_map(|(source, target)| lookup(source).map(|value| format!("{target}={value}")))
+        .collect();
+
+    if lookup("XKBLAYOUT").is_none() {
+        if let Some(keymap) = lookup("KEYMAP") {
+            let layout: String = keymap
+                .chars()
+                .take_while(char::is_ascii_alphabetic)
+                .collect();
+            if !layout.is_empty() {
+                env.push(format!("XKB_DEFAULT_LAYOUT={layout}"));
+            }
+        }
+    }
+
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_vars() {
+        let vars =
+            parse_env_file("# comment\nLANG=\"de_DE.UTF-8\"\nLC_TIME=en_GB.UTF-8\nFOO=bar\n");
+        assert_eq!(
+            locale_vars(&vars),
+            vec!["LANG=de_DE.UTF-8", "LC_TIME=en_GB.UTF-8"]
+        );
+    }
+
+    #[test]
+    #[allow(
+        clippy::literal_string_with_formatting_args,
+        reason = "shell-style expansions, not format arguments"
+    )]
+    fn test_environment_d() {
+        let mut vars = vec![
+            ("PATH".to_string(), DEFAULT_PATH.to_string()),
+            ("HOME".to_string(), "/home/me".to_string()),
+        ];
+        environment_d(
+            "PATH=$HOME/.local/bin:${PATH}\n\
+             EDITOR=${VISUAL:-nvim}\n\
+             PAGER=${EDITOR:+less}\n\
+             GOPATH=\"$HOME/go\"\n\
+             PRICE=5$\n\
+             9BAD=x\n",
+            &mut vars,
+        );
+        let get = |name: &str| {
+            vars.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(
+            get("PATH"),
+            Some("/home/me/.local/bin:/usr/local/sbin:/usr/local/bin:/usr/bin")
+        );
+        assert_eq!(get("EDITOR"), Some("nvim"));
+        assert_eq!(get("PAGER"), Some("less"));
+        assert_eq!(get("GOPATH"), Some("/home/me/go"));
+        assert_eq!(get("PRICE"), Some("5$"));
+        assert_eq!(get("9BAD"), None);
+        // Reassigned PATH moved behind HOME
+        assert_eq!(vars[0].0, "HOME");
+    }
+
+    #[test]
+    fn test_gecos_name() {
+        assert_eq!(gecos_name("Mei Ling,Room 4,,").as_deref(), Some("Mei Ling"));
+        assert_eq!(gecos_name(""), None);
+    }
+
+    #[test]
+    fn test_keyboard_vars() {
+        let vars = parse_env_file("KEYMAP=de-latin1\nXKBLAYOUT=de\nXKBVARIANT=nodeadkeys\n");
+        assert_eq!(
+            keyboard_vars(&vars),
+            vec!["XKB_DEFAULT_LAYOUT=de", "XKB_DEFAULT_VARIANT=nodeadkeys"]
+        );
+
+        let console_only = parse_env_file("KEYMAP=fr-latin9\n");
+        assert_eq!(keyboard_vars(&console_only), vec!["XKB_DEFAULT_LAYOUT=fr"]);
+    }
+}
@@ -0,0 +1,109 @@
+//! Greeter event stream for integrators
+//!
+//! With `events_socket` set, grxxt listens on a Unix socket and writes one
+//! JSON object per line to every connected client when a login starts,
+//! fails, or hands over to a session — enough for status bars, kiosk
+//! supervisors and monitoring agents to react without polling.
+
+use std::fs;
+use std::io::Write;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// A slow reader must never stall the greeter
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Something integrators may want to react to
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    AuthStarted { username: &'a str },
+    AuthFailed { username: &'a str, reason: &'a str },
+    SessionStarted { username: &'a str, session: &'a str },
+}
+
+/// Listening event socket and its connected clients
+pub struct Events {
+    path: PathBuf,
+    clients: Arc<Mutex<Vec<UnixStream>>>,
+}
+
+impl Events {
+    /// Listen on `path`, replacing a stale socket. `None` if that fails —
+    /// events are an extra, never a reason not to show the login.
+    pub fn bind(path: &str) -> Option<Self> {
+        fs::remove_file(path).ok();
+        let listener = UnixListener::bind(path).ok()?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+
+        let accepted = Arc::clone(&clients);
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok();
+                if let Ok(mut clients) = accepted.lock() {
+                    clients.push(stream);
+                }
+            }
+        });
+
+        Some(Self {
+            path: PathBuf::from(path),
+            clients,
+        })
+    }
+
+    /// Send `event` to every client, dropping those that went away
+    pub fn emit(&self, event: &Event) {
+        let Ok(mut line) = serde_json::to_vec(event) else {
+            return;
+        };
+        line.push(b'\n');
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.retain_mut(|client| client.write_all(&line).is_ok());
+        }
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn test_emit_reaches_clients() {
+        let path = std::env::temp_dir().join(format!("grxxt-events-{}.sock", std::process::id()));
+        let events = Events::bind(path.to_str().unwrap()).unwrap();
+        let client = UnixStream::connect(&path).unwrap();
+
+        // Wait for the accept thread to register the client
+        while events.clients.lock().unwrap().is_empty() {
+            thread::sleep(Duration::from_millis(5));
+        }
+        events.emit(&Event::AuthFailed {
+            username: "me",
+            reason: "Authentication failed",
+        });
+
+        let mut line = String::new();
+        BufReader::new(client).read_line(&mut line).unwrap();
+        assert_eq!(
+            line,
+            "{\"event\":\"auth_failed\",\"username\":\"me\",\"reason\":\"Authentication failed\"}\n"
+        );
+
+        drop(events);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,82 @@
+//! Fade-in when the greeter appears
+//!
+//! Switching VTs to a full-screen greeter is abrupt. With `[display]
+//! fade_in` set, the picture starts at the background color and brightens
+//! over that many milliseconds, at startup and again when a key wakes a
+//! screen dimmed against burn-in. Terminals without RGB colors get a dimmed
+//! first half instead.
+
+use std::time::{Duration, Instant};
+
+use crate::config::DisplayConfig;
+
+/// Time between redraws while fading
+pub const FRAME: Duration = Duration::from_millis(30);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Fade {
+    duration: Duration,
+    /// Start of the running fade; `None` once done
+    started: Option<Instant>,
+}
+
+impl Fade {
+    /// `None` unless enabled; the first fade starts at `now`
+    pub fn new(options: &DisplayConfig, now: Instant) -> Option<Self> {
+        (options.fade_in > 0).then(|| Self {
+            duration: Duration::from_millis(options.fade_in),
+            started: Some(now),
+        })
+    }
+
+    /// Fade in again from the background
+    pub const fn restart(&mut self, now: Instant) {
+        self.started = Some(now);
+    }
+
+    /// How far the picture is faded in, in thousandths, while fading
+    pub fn level(&mut self, now: Instant) -> Option<u32> {
+        let elapsed = now.duration_since(self.started?);
+        if elapsed >= self.duration {
+            self.started = None;
+            return None;
+        }
+        u32::try_from(elapsed.as_nanos() * 1000 / self.duration.as_nanos()).ok()
+    }
+
+    /// Time until the next frame, while fading
+    pub fn next_frame(&self, now: Instant) -> Option<Duration> {
+        let elapsed = now.duration_since(self.started?);
+        Some(self.duration.saturating_sub(elapsed).min(FRAME))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    #[test]
+    fn test_fade() {
+        let time = Timeline::new();
+        let mut options = DisplayConfig::default();
+        assert!(Fade::new(&options, time.start).is_none());
+
+        options.fade_in = 300;
+        let mut fade = Fade::new(&options, time.start).unwrap();
+        assert_eq!(fade.level(time.start), Some(0));
+        assert_eq!(fade.level(time.millis(150)), Some(500));
+        assert_eq!(fade.next_frame(time.millis(150)), Some(FRAME));
+        assert_eq!(
+            fade.next_frame(time.millis(290)),
+            Some(Duration::from_millis(10))
+        );
+        assert_eq!(fade.level(time.millis(300)), None);
+        assert_eq!(fade.next_frame(time.millis(300)), None);
+
+        fade.restart(time.millis(1000));
+        assert_eq!(fade.level(time.millis(1075)), Some(250));
+    }
+}
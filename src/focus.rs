@@ -0,0 +1,99 @@
+//! Focus contexts and modal input routing
+//!
+//! The login form sits at the bottom of a stack. Modals (confirmations, and
+//! later menus and pickers) are pushed on top and receive all input until
+//! they are popped, so the form never sees keys meant for a dialog.
+
+use crate::power::Action;
+
+/// Input field of the login form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Username,
+    Password,
+}
+
+/// Something that can hold the input focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// The login form, with its focused field
+    Form(Field),
+    /// Yes/no question before a power action runs
+    Confirm(Action),
+    /// List of the failed systemd units
+    FailedUnits,
+    /// Countdown to the session start after logging in
+    Handoff,
+}
+
+/// Stack of focus contexts; never empty, the form is always at the bottom
+#[derive(Debug)]
+pub struct FocusStack {
+    form: Field,
+    modals: Vec<Context>,
+}
+
+impl FocusStack {
+    pub const fn new(field: Field) -> Self {
+        Self {
+            form: field,
+            modals: Vec::new(),
+        }
+    }
+
+    /// Context that receives input
+    pub fn top(&self) -> Context {
+        self.modals
+            .last()
+            .copied()
+            .unwrap_or(Context::Form(self.form))
+    }
+
+    /// Focused form field, even while a modal covers the form
+    pub const fn field(&self) -> Field {
+        self.form
+    }
+
+    pub const fn set_field(&mut self, field: Field) {
+        self.form = field;
+    }
+
+    /// `field` is focused and nothing covers the form
+    pub fn is_focused(&self, field: Field) -> bool {
+        self.top() == Context::Form(field)
+    }
+
+    /// Open a modal on top of everything
+    pub fn push(&mut self, context: Context) {
+        self.modals.push(context);
+    }
+
+    /// Close the topmost modal; the form itself can't be popped
+    pub fn pop(&mut self) -> Option<Context> {
+        self.modals.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modal_traps_focus() {
+        let mut focus = FocusStack::new(Field::Username);
+        assert!(focus.is_focused(Field::Username));
+
+        focus.push(Context::Confirm(Action::Reboot));
+        assert_eq!(focus.top(), Context::Confirm(Action::Reboot));
+        assert!(!focus.is_focused(Field::Username));
+
+        // The form keeps its field underneath the modal
+        focus.set_field(Field::Password);
+        assert_eq!(focus.field(), Field::Password);
+
+        assert_eq!(focus.pop(), Some(Context::Confirm(Action::Reboot)));
+        assert!(focus.is_focused(Field::Password));
+        assert_eq!(focus.pop(), None);
+        assert_eq!(focus.top(), Context::Form(Field::Password));
+    }
+}
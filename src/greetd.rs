@@ -3,31 +3,77 @@
 use greetd_ipc::codec::SyncCodec;
 use greetd_ipc::{AuthMessageType, ErrorType, Request, Response};
 use std::env;
+use std::io;
 use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::auth::{AuthBackend, AuthError, Outcome, Prompt};
+
+/// Waits between attempts to connect while greetd's socket isn't up yet
+const CONNECT_BACKOFF: [Duration; 5] = [
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+    Duration::from_millis(400),
+    Duration::from_millis(800),
+    Duration::from_millis(1600),
+];
+
+/// Longest wait between looks at the socket for the startup banner
+const WAIT_MAX: Duration = Duration::from_secs(5);
 
 pub struct GreetdClient {
     stream: UnixStream,
+    /// Longest wait for one read or write; `None` waits forever
+    timeout: Option<Duration>,
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum AuthError {
-    #[error("Connection failed: {0}")]
-    ConnectionFailed(String),
-    #[error("Protocol error: {0}")]
-    ProtocolError(String),
-    #[error("{0}")]
-    AuthFailed(String),
+impl From<UnixStream> for GreetdClient {
+    fn from(stream: UnixStream) -> Self {
+        Self {
+            stream,
+            timeout: None,
+        }
+    }
 }
 
 impl GreetdClient {
-    pub fn connect() -> Result<Self, AuthError> {
-        let socket_path = env::var("GREETD_SOCK")
-            .map_err(|_| AuthError::ConnectionFailed("GREETD_SOCK not set".into()))?;
+    /// Connect to greetd via `GREETD_SOCK`, falling back to `fallback_socket`.
+    /// A socket that isn't up yet (greetd still starting) is tried again a
+    /// few times, backing off. A daemon that doesn't answer within
+    /// `timeout` fails the call.
+    pub fn connect(
+        fallback_socket: Option<&str>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, AuthError> {
+        let socket_path = socket_path(fallback_socket).ok_or_else(|| {
+            AuthError::ConnectionFailed("Neither GREETD_SOCK nor socket is set".into())
+        })?;
 
-        let stream = UnixStream::connect(&socket_path)
-            .map_err(|e| AuthError::ConnectionFailed(e.to_string()))?;
+        let mut waits = CONNECT_BACKOFF.into_iter();
+        let stream = loop {
+            match UnixStream::connect(&socket_path) {
+                Ok(stream) => break stream,
+                Err(e) => match waits.next() {
+                    Some(wait) if starting(&e) => thread::sleep(wait),
+                    _ => {
+                        return Err(AuthError::ConnectionFailed(format!("{socket_path}: {e}")));
+                    }
+                },
+            }
+        };
+
+        Self::from(stream).with_timeout(timeout)
+    }
 
-        Ok(Self { stream })
+    /// Give up on greetd after `timeout` without progress
+    fn with_timeout(mut self, timeout: Option<Duration>) -> Result<Self, AuthError> {
+        self.stream
+            .set_read_timeout(timeout)
+            .and_then(|()| self.stream.set_write_timeout(timeout))
+            .map_err(|e| AuthError::ConnectionFailed(e.to_string()))?;
+        self.timeout = timeout;
+        Ok(self)
     }
 
     fn send(&mut self, request: &Request) -> Result<(), AuthError> {
@@ -37,52 +83,39 @@ impl GreetdClient {
     }
 
     fn receive(&mut self) -> Result<Response, AuthError> {
-        Response::read_from(&mut self.stream).map_err(|e| AuthError::ProtocolError(e.to_string()))
+        let started = Instant::now();
+        Response::read_from(&mut self.stream).map_err(|e| match self.timeout {
+            // The codec keeps only the message of I/O errors; a read that
+            // failed after the whole timeout timed out
+            Some(timeout) if started.elapsed() >= timeout => AuthError::ConnectionFailed(format!(
+                "greetd did not answer within {} seconds",
+                timeout.as_secs()
+            )),
+            _ => AuthError::ProtocolError(e.to_string()),
+        })
     }
 
-    pub fn create_session(&mut self, username: &str) -> Result<(), AuthError> {
+    /// Start a login for `username`; PAM's first question comes back
+    pub fn create_session(&mut self, username: &str) -> Result<AuthState, AuthError> {
         self.send(&Request::CreateSession {
             username: username.to_string(),
         })?;
-
-        match self.receive()? {
-            Response::Success | Response::AuthMessage { .. } => Ok(()),
-            Response::Error {
-                error_type,
-                description,
-            } => Err(AuthError::AuthFailed(format_error(
-                &error_type,
-                &description,
-            ))),
-        }
+        auth_state(self.receive()?)
     }
 
     pub fn post_auth_response(&mut self, response: Option<String>) -> Result<AuthState, AuthError> {
         self.send(&Request::PostAuthMessageResponse { response })?;
+        auth_state(self.receive()?)
+    }
 
-        match self.receive()? {
-            Response::Success => Ok(AuthState::Done),
-            Response::AuthMessage {
-                auth_message_type,
-                auth_message,
-            } => match auth_message_type {
-                AuthMessageType::Visible => Ok(AuthState::NeedInput(auth_message)),
-                AuthMessageType::Secret => Ok(AuthState::NeedSecret(auth_message)),
-                AuthMessageType::Info => Ok(AuthState::Info(auth_message)),
-                AuthMessageType::Error => Ok(AuthState::Error(auth_message)),
-            },
-            Response::Error {
-                error_type,
-                description,
-            } => Err(AuthError::AuthFailed(format_error(
-                &error_type,
-                &description,
-            ))),
-        }
+    /// Abort the current login; greetd resets so a new one can start
+    pub fn cancel_session(&mut self) -> Result<(), AuthError> {
+        self.send(&Request::CancelSession)?;
+        self.receive().map(drop)
     }
 
-    pub fn start_session(&mut self, cmd: Vec<String>) -> Result<(), AuthError> {
-        self.send(&Request::StartSession { cmd, env: vec![] })?;
+    pub fn start_session(&mut self, cmd: Vec<String>, env: Vec<String>) -> Result<(), AuthError> {
+        self.send(&Request::StartSession { cmd, env })?;
 
         match self.receive()? {
             Response::Success => Ok(()),
@@ -101,6 +134,76 @@ impl GreetdClient {
 
 }
 
+/// `GREETD_SOCK`, else the configured socket
+fn socket_path(fallback_socket: Option<&str>) -> Option<String> {
+    env::var("GREETD_SOCK")
+        .ok()
+        .or_else(|| fallback_socket.map(str::to_string))
+}
+
+/// The socket isn't there or nobody listens on it yet
+fn starting(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+    )
+}
+
+/// Looks at greetd's socket after startup until it takes connections, for
+/// the "waiting for greetd" banner; the waits between looks double
+#[derive(Debug)]
+pub struct Wait {
+    socket: Option<String>,
+    delay: Duration,
+}
+
+impl Wait {
+    pub const fn new(socket: Option<String>) -> Self {
+        Self {
+            socket,
+            delay: CONNECT_BACKOFF[0],
+        }
+    }
+
+    /// `None` once greetd is up (or there is no socket to wait for), else
+    /// how long until the next look
+    pub fn poll(&mut self) -> Option<Duration> {
+        let path = socket_path(self.socket.as_deref())?;
+        match UnixStream::connect(path) {
+            Err(e) if starting(&e) => {
+                let delay = self.delay;
+                self.delay = (delay * 2).min(WAIT_MAX);
+                Some(delay)
+            }
+            // Up, or broken in a way waiting won't fix; logging in tells
+            _ => None,
+        }
+    }
+}
+
+/// Where a login stands after greetd's answer
+fn auth_state(response: Response) -> Result<AuthState, AuthError> {
+    match response {
+        Response::Success => Ok(AuthState::Done),
+        Response::AuthMessage {
+            auth_message_type,
+            auth_message,
+        } => match auth_message_type {
+            AuthMessageType::Visible => Ok(AuthState::NeedInput(auth_message)),
+            AuthMessageType::Secret => Ok(AuthState::NeedSecret(auth_message)),
+            AuthMessageType::Info => Ok(AuthState::Info(auth_message)),
+            AuthMessageType::Error => Ok(AuthState::Error(auth_message)),
+        },
+        Response::Error {
+            error_type,
+            description,
+        } => Err(AuthError::AuthFailed(format_error(
+            &error_type,
+            &description,
+        ))),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum AuthState {
     NeedInput(String),
@@ -124,27 +227,412 @@ fn format_error(error_type: &ErrorType, description: &str) -> String {
 }
 
 /// Perform full authentication flow
-pub fn authenticate(username: &str, password: &str, session_cmd: &str) -> Result<(), AuthError> {
-    let mut client = GreetdClient::connect()?;
+pub fn authenticate(
+    client: &mut GreetdClient,
+    username: &str,
+    password: &str,
+) -> Result<Outcome, AuthError> {
+    let mut infos = Vec::new();
+    let mut state = client.create_session(username)?;
+    // Banners before the first question are acknowledged. A secret question
+    // gets the password; a visible one (an OTP, a username) is asked
+    loop {
+        state = match state {
+            AuthState::Info(msg) => {
+                infos.push(msg);
+                client.post_auth_response(None)?
+            }
+            AuthState::NeedInput(message) => return Ok(prompt(infos, message, false)),
+            AuthState::NeedSecret(_) => {
+                let state = client.post_auth_response(Some(password.to_string()))?;
+                return advance(client, state, infos);
+            }
+            // Nothing to ask (e.g. `pam_permit`)
+            AuthState::Done => return Ok(Outcome::Authenticated(infos)),
+            AuthState::Error(msg) => return Err(AuthError::AuthFailed(msg)),
+        };
+    }
+}
+
+/// Continue a login from `state` until it needs the user again or is done.
+/// Info messages (`pam_krb5`/sssd ticket status) are acknowledged and collected.
+fn advance(
+    client: &mut GreetdClient,
+    mut state: AuthState,
+    mut infos: Vec<String>,
+) -> Result<Outcome, AuthError> {
+    loop {
+        state = match state {
+            AuthState::Info(msg) => {
+                infos.push(msg);
+                client.post_auth_response(None)?
+            }
+            AuthState::Done => return Ok(Outcome::Authenticated(infos)),
+            AuthState::Error(msg) => return Err(AuthError::AuthFailed(msg)),
+            AuthState::NeedInput(message) => return Ok(prompt(infos, message, false)),
+            AuthState::NeedSecret(message) => return Ok(prompt(infos, message, true)),
+        };
+    }
+}
+
+/// A follow-up question with any info PAM sent just before it
+/// ("Security token not inserted." + "Recovery key:")
+const fn prompt(notices: Vec<String>, message: String, secret: bool) -> Outcome {
+    Outcome::Prompt(Prompt {
+        message,
+        notices,
+        secret,
+    })
+}
+
+/// Default backend: authenticate through the greetd daemon.
+/// One connection serves all attempts; greetd's session is cancelled after
+/// each failure so the next attempt starts clean.
+pub struct Greetd {
+    socket: Option<String>,
+    timeout: Option<Duration>,
+    client: Option<GreetdClient>,
+    /// greetd holds a session for us that must be cancelled before a new one
+    session_open: bool,
+    /// A prompt waits for its answer
+    pending: bool,
+}
+
+impl Greetd {
+    /// `socket` is used when `GREETD_SOCK` is unset; `timeout` bounds each
+    /// wait for greetd
+    pub const fn new(socket: Option<String>, timeout: Option<Duration>) -> Self {
+        Self {
+            socket,
+            timeout,
+            client: None,
+            session_open: false,
+            pending: false,
+        }
+    }
+
+    /// Track greetd's session state after a login step; `open` is whether
+    /// greetd still holds the session when the step succeeded
+    fn settle<T>(&mut self, outcome: Result<T, AuthError>, open: bool) -> Result<T, AuthError> {
+        match outcome {
+            Ok(_) => self.session_open = open,
+            // The session may survive a rejected password; drop it
+            Err(AuthError::AuthFailed(_)) => {
+                self.session_open = true;
+                self.cancel();
+            }
+            // Connection state unknown: reconnect next time (greetd drops
+            // the session of a closed connection)
+            Err(AuthError::ConnectionFailed(_) | AuthError::ProtocolError(_)) => {
+                self.client = None;
+                self.session_open = false;
+            }
+        }
+        outcome
+    }
+}
+
+impl AuthBackend for Greetd {
+    fn authenticate(&mut self, username: &str, password: &str) -> Result<Outcome, AuthError> {
+        self.cancel();
+        let client = match self.client {
+            Some(ref mut client) => client,
+            None => self
+                .client
+                .insert(GreetdClient::connect(self.socket.as_deref(), self.timeout)?),
+        };
+        let outcome = authenticate(client, username, password);
+        self.pending = matches!(outcome, Ok(Outcome::Prompt(_)));
+        self.settle(outcome, true)
+    }
+
+    fn answer(&mut self, response: &str) -> Result<Outcome, AuthError> {
+        if !std::mem::take(&mut self.pending) {
+            return Err(AuthError::ProtocolError("No pending prompt".into()));
+        }
+        let client = self
+            .client
+            .as_mut()
+            .ok_or_else(|| AuthError::ProtocolError("Not connected".into()))?;
+        let outcome = client
+            .post_auth_response(Some(response.to_string()))
+            .and_then(|state| advance(client, state, Vec::new()));
+        self.pending = matches!(outcome, Ok(Outcome::Prompt(_)));
+        self.settle(outcome, true)
+    }
+
+    fn start(&mut self, session_cmd: &str, env: &[String]) -> Result<(), AuthError> {
+        let client = self
+            .client
+            .as_mut()
+            .ok_or_else(|| AuthError::ProtocolError("Not connected".into()))?;
+        let cmd: Vec<String> =
+            shell_words::split(session_cmd).unwrap_or_else(|_| vec![session_cmd.to_string()]);
+        let result = client.start_session(cmd, env.to_vec());
+        self.settle(result, false)
+    }
+
+    fn cancel(&mut self) {
+        self.pending = false;
+        if !std::mem::take(&mut self.session_open) {
+            return;
+        }
+        if let Some(ref mut client) = self.client {
+            if client.cancel_session().is_err() {
+                self.client = None;
+            }
+        }
+    }
+}
 
-    // Create session for user
-    client.create_session(username)?;
+/// Scripted greetd stand-in for tests and fuzzing: replays raw response
+/// bytes over a socket pair and discards whatever the client sends.
+#[cfg(any(test, fuzzing))]
+pub mod fake {
+    use std::io::{self, Write};
+    use std::net::Shutdown;
+    use std::os::unix::net::UnixStream;
+    use std::thread;
 
-    // Send password
-    let state = client.post_auth_response(Some(password.to_string()))?;
+    use greetd_ipc::codec::SyncCodec;
+    use greetd_ipc::Response;
 
-    match state {
-        AuthState::Done => {
-            let cmd: Vec<String> = shell_words::split(session_cmd)
-                .unwrap_or_else(|_| vec![session_cmd.to_string()]);
-            client.start_session(cmd)?;
-            Ok(())
+    use super::GreetdClient;
+
+    /// Connect a client to a fake daemon that answers with `responses` (raw wire bytes)
+    pub fn serve(responses: Vec<u8>) -> io::Result<GreetdClient> {
+        let (client, mut server) = UnixStream::pair()?;
+        thread::spawn(move || {
+            server.write_all(&responses).ok();
+            // EOF after the script so a client waiting for more sees an error
+            server.shutdown(Shutdown::Write).ok();
+            io::copy(&mut server, &mut io::sink()).ok();
+        });
+        Ok(GreetdClient::from(client))
+    }
+
+    /// Encode responses in greetd's wire format
+    pub fn encode(responses: &[Response]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for response in responses {
+            response.write_to(&mut bytes).ok();
         }
-        AuthState::Error(msg) => Err(AuthError::AuthFailed(msg)),
-        AuthState::NeedInput(msg)
-        | AuthState::NeedSecret(msg)
-        | AuthState::Info(msg) => {
-            Err(AuthError::ProtocolError(format!("Unexpected auth state: {msg}")))
+        bytes
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    fn login_against(responses: &[Response]) -> Result<Outcome, AuthError> {
+        let mut client = fake::serve(fake::encode(responses)).unwrap();
+        authenticate(&mut client, "user", "hunter2")
+    }
+
+    #[test]
+    fn test_login_success() {
+        let result = login_against(&[
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Secret,
+                auth_message: "Password:".into(),
+            },
+            Response::Success,
+        ]);
+        assert_eq!(result.unwrap(), Outcome::Authenticated(Vec::new()));
+    }
+
+    #[test]
+    fn test_session_starts_separately() {
+        let script = fake::encode(&[
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Secret,
+                auth_message: "Password:".into(),
+            },
+            Response::Success,
+            Response::Success,
+        ]);
+        let mut greetd = Greetd::new(None, None);
+        greetd.client = Some(fake::serve(script).unwrap());
+
+        assert_eq!(
+            greetd.authenticate("user", "hunter2").unwrap(),
+            Outcome::Authenticated(Vec::new())
+        );
+        // greetd holds the session until it is started or cancelled
+        assert!(greetd.session_open);
+        greetd.start("sway --debug", &[]).unwrap();
+        assert!(!greetd.session_open);
+    }
+
+    #[test]
+    fn test_login_collects_info_messages() {
+        let result = login_against(&[
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Secret,
+                auth_message: "Password:".into(),
+            },
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Info,
+                auth_message: "Ticket acquired".into(),
+            },
+            Response::Success,
+        ]);
+        assert_eq!(
+            result.unwrap(),
+            Outcome::Authenticated(vec!["Ticket acquired".into()])
+        );
+    }
+
+    #[test]
+    fn test_login_follow_up_prompt() {
+        let result = login_against(&[
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Secret,
+                auth_message: "Password:".into(),
+            },
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Info,
+                auth_message: "Password incorrect or not sufficient.".into(),
+            },
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Secret,
+                auth_message: "Recovery key:".into(),
+            },
+        ]);
+        assert_eq!(
+            result.unwrap(),
+            Outcome::Prompt(Prompt {
+                message: "Recovery key:".into(),
+                notices: vec!["Password incorrect or not sufficient.".into()],
+                secret: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_login_first_message() {
+        // A banner comes before the password prompt
+        let result = login_against(&[
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Info,
+                auth_message: "Authorized use only".into(),
+            },
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Secret,
+                auth_message: "Password:".into(),
+            },
+            Response::Success,
+        ]);
+        assert_eq!(
+            result.unwrap(),
+            Outcome::Authenticated(vec!["Authorized use only".into()])
+        );
+        // Nothing asked at all
+        let result = login_against(&[Response::Success]);
+        assert_eq!(result.unwrap(), Outcome::Authenticated(Vec::new()));
+    }
+
+    #[test]
+    fn test_login_visible_first_question() {
+        // A one-time code is asked for, not answered with the password
+        let result = login_against(&[Response::AuthMessage {
+            auth_message_type: AuthMessageType::Visible,
+            auth_message: "Verification code:".into(),
+        }]);
+        assert_eq!(
+            result.unwrap(),
+            Outcome::Prompt(Prompt {
+                message: "Verification code:".into(),
+                notices: Vec::new(),
+                secret: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_login_wrong_password() {
+        let result = login_against(&[
+            Response::AuthMessage {
+                auth_message_type: AuthMessageType::Secret,
+                auth_message: "Password:".into(),
+            },
+            Response::Error {
+                error_type: ErrorType::AuthError,
+                description: String::new(),
+            },
+        ]);
+        assert!(
+            matches!(result, Err(AuthError::AuthFailed(msg)) if msg == "Authentication failed")
+        );
+    }
+
+    #[test]
+    fn test_retry_reuses_connection() {
+        let password = || Response::AuthMessage {
+            auth_message_type: AuthMessageType::Secret,
+            auth_message: "Password:".into(),
+        };
+        let script = fake::encode(&[
+            password(),
+            Response::Error {
+                error_type: ErrorType::AuthError,
+                description: String::new(),
+            },
+            // CancelSession after the failure
+            Response::Success,
+            password(),
+            Response::Success,
+        ]);
+        let mut greetd = Greetd::new(None, None);
+        greetd.client = Some(fake::serve(script).unwrap());
+
+        assert!(greetd.authenticate("user", "wrong").is_err());
+        assert!(!greetd.session_open);
+        assert_eq!(
+            greetd.authenticate("user", "hunter2").unwrap(),
+            Outcome::Authenticated(Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_login_truncated_response() {
+        let mut bytes = fake::encode(&[Response::Success]);
+        bytes.truncate(bytes.len() - 1);
+        let mut client = fake::serve(bytes).unwrap();
+        assert!(matches!(
+            client.create_session("user"),
+            Err(AuthError::ProtocolError(_))
+        ));
+    }
+
+    #[test]
+    fn test_wedged_daemon_times_out() {
+        // A daemon that reads requests and never answers
+        let (client, _daemon) = UnixStream::pair().unwrap();
+        let mut client = GreetdClient::from(client)
+            .with_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        assert!(matches!(
+            client.create_session("user"),
+            Err(AuthError::ConnectionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_wait_for_socket() {
+        if env::var_os("GREETD_SOCK").is_some() {
+            return;
         }
+        let path = env::temp_dir().join(format!("grxxt-test-{}.sock", std::process::id()));
+        let mut wait = Wait::new(Some(path.to_string_lossy().into_owned()));
+        let delays: Vec<Duration> = (0..8).map_while(|_| wait.poll()).collect();
+        assert_eq!(delays[..2], CONNECT_BACKOFF[..2]);
+        assert_eq!(delays.last(), Some(&WAIT_MAX));
+
+        let _greetd = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        assert_eq!(wait.poll(), None);
+        std::fs::remove_file(&path).ok();
     }
 }
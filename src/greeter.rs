@@ -0,0 +1,612 @@
+//! The greeter without a screen
+//!
+//! [`GreeterCore`] owns the form's state and everything that moves it along:
+//! keys, timers and the background jobs of the scheduler. A frontend
+//! draws [`GreeterCore::app`] with [`ui::render`](crate::ui::render), calls
+//! [`GreeterCore::drawn`], then [`GreeterCore::step`] to wait for the next
+//! thing to happen, and does what the returned [`Step`] asks. Nothing here
+//! touches a terminal, so the whole login flow runs in tests.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::announce;
+use crate::app::App;
+use crate::auth::{AuthError, Outcome};
+use crate::bindings;
+use crate::checks;
+#[cfg(feature = "hotplug")]
+use crate::console;
+use crate::effects::Screensaver;
+use crate::greetd;
+use crate::homed;
+use crate::idle;
+use crate::input::{EventSource, InputEvent, KeyCode, KeyEvent};
+use crate::keyboard;
+use crate::power;
+use crate::resources;
+use crate::rfkill;
+use crate::scheduler::{Scheduler, Task, Timer, Wake};
+use crate::status;
+use crate::submission::{self, Submission};
+
+/// What the frontend has to do after a [`GreeterCore::step`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Draw the screen again
+    Redraw,
+    /// Clear and draw everything again: the machine is back from suspend
+    Repaint,
+    /// The session was started: confirm the login, then hand the screen over
+    LoggedIn,
+    /// The greeter was quit
+    Quit,
+}
+
+pub struct GreeterCore {
+    pub app: App,
+    scheduler: Scheduler,
+}
+
+impl GreeterCore {
+    /// Start the timers and background jobs of `app`, with keys from `source`
+    pub fn new(mut app: App, source: impl EventSource + Send + 'static) -> Self {
+        let mut scheduler = Scheduler::new(source);
+        start_timers(&mut app, &mut scheduler);
+        look_up_homed(&mut app, &scheduler);
+        #[cfg(feature = "input-activity")]
+        if app.burn_in.is_some() || app.idle.is_some() {
+            scheduler.watch_activity();
+        }
+        Self { app, scheduler }
+    }
+
+    /// The screen was drawn: queue what changed for the announcement and
+    /// time the error on it
+    pub fn drawn(&mut self) {
+        announce(&mut self.app, &mut self.scheduler);
+        let now = Instant::now();
+        if let Some(timeout) = self.app.error_drawn(now) {
+            self.scheduler.after(timeout, Timer::Error);
+        }
+        if let Some(interval) = self.app.notices_drawn(now) {
+            self.scheduler.after(interval, Timer::Notice);
+        }
+    }
+
+    /// Wait for the next key, timer or finished job and act on it
+    ///
+    /// # Errors
+    ///
+    /// When the input source failed.
+    pub fn step(&mut self) -> io::Result<Step> {
+        let Self { app, scheduler } = self;
+        match scheduler.next()? {
+            // Input is frozen while a power action runs
+            Wake::Input(InputEvent::Key(key)) if app.power.is_none() => {
+                note_activity(app, scheduler);
+                if handle_key(app, scheduler, key) {
+                    return Ok(Step::LoggedIn);
+                }
+                if app.power.is_some() {
+                    scheduler.after(power::POLL, Timer::Power);
+                }
+                if app.hold.is_some() {
+                    scheduler.after(power::HOLD_TICK, Timer::Hold);
+                }
+                if app.handoff.is_some() {
+                    scheduler.after(Duration::ZERO, Timer::Handoff);
+                }
+            }
+            Wake::Input(InputEvent::ScrollUp) if app.power.is_none() => {
+                note_activity(app, scheduler);
+                app.wheel(false);
+            }
+            Wake::Input(InputEvent::ScrollDown) if app.power.is_none() => {
+                note_activity(app, scheduler);
+                app.wheel(true);
+            }
+            Wake::Input(_) => {}
+            #[cfg(feature = "input-activity")]
+            Wake::Activity => note_activity(app, scheduler),
+            #[cfg(feature = "hotplug")]
+            Wake::Hotplug => input_hotplugged(app),
+            Wake::Timer(Timer::BurnIn) => {
+                if let Some(ref burn_in) = app.burn_in {
+                    scheduler.after(burn_in.next_change(Instant::now()), Timer::BurnIn);
+                }
+            }
+            Wake::Timer(Timer::Idle) => poll_idle(app, scheduler),
+            Wake::Timer(Timer::Fade) => schedule_fade(app, scheduler),
+            Wake::Timer(Timer::Typewriter) => schedule_typewriter(app, scheduler),
+            Wake::Timer(Timer::Effect) => poll_effect(app, scheduler),
+            Wake::Timer(Timer::Handoff) => {
+                if poll_handoff(app, scheduler) {
+                    return Ok(Step::LoggedIn);
+                }
+            }
+            Wake::Timer(Timer::Checks) => run_checks(app, scheduler),
+            Wake::Timer(Timer::Clock) => scheduler.after(app.clock.next_tick(), Timer::Clock),
+            Wake::Timer(Timer::Status) => refresh_status(scheduler),
+            Wake::Timer(Timer::Keyboard) => poll_keyboard(app, scheduler),
+            Wake::Timer(Timer::Greetd) => poll_greetd(app, scheduler),
+            Wake::Timer(Timer::Announce) => speak(app),
+            Wake::Timer(Timer::Error) => app.expire_error(Instant::now()),
+            Wake::Timer(Timer::Notice) => rotate_notices(app, scheduler),
+            Wake::Timer(Timer::Spinner) => schedule_spinner(app, scheduler),
+            Wake::Timer(Timer::Hold) => poll_hold(app, scheduler),
+            Wake::Timer(Timer::Power) => {
+                let resumed = app.poll_power();
+                if resumed {
+                    // Drop keys queued while the machine went down and
+                    // catch the clock up
+                    scheduler.discard_input();
+                    scheduler.after(Duration::ZERO, Timer::Clock);
+                    note_activity(app, scheduler);
+                }
+                if app.power.is_some() {
+                    scheduler.after(power::POLL, Timer::Power);
+                }
+                if resumed {
+                    return Ok(Step::Repaint);
+                }
+            }
+            Wake::Task(Task::ClockSync(synced)) => {
+                app.status.clock_unsynced = synced == Some(false);
+            }
+            Wake::Task(Task::RemoteSessions(count)) => {
+                app.status.remote_sessions = count.unwrap_or(0);
+            }
+            Wake::Task(Task::Health(problems)) => app.status.health = problems,
+            Wake::Task(Task::Resources(warnings)) => app.status.resources = warnings,
+            Wake::Task(Task::SessionClass(class)) => app.status.session_class = class,
+            Wake::Task(Task::Airplane(airplane)) => app.status.airplane = airplane,
+            Wake::Task(Task::Homed(username, homed)) => app.homed_checked(username, homed),
+            Wake::Task(Task::FailedUnits(units)) => app.set_failed_units(units.unwrap_or_default()),
+            Wake::Task(Task::Auth(attempt, result)) => {
+                if auth_done(app, scheduler, attempt, result) {
+                    return Ok(Step::LoggedIn);
+                }
+            }
+            Wake::Task(Task::IdleExcluded(excluded)) => {
+                if let Some(ref mut idle) = app.idle {
+                    let now = Instant::now();
+                    idle.checked(excluded, now);
+                    scheduler.after(idle.next_poll(now), Timer::Idle);
+                }
+            }
+        }
+
+        look_up_homed(app, scheduler);
+        Ok(if app.should_quit { Step::Quit } else { Step::Redraw })
+    }
+}
+
+/// First status queries and checks, and the timers that repeat them
+fn start_timers(app: &mut App, scheduler: &mut Scheduler) {
+    scheduler.after(Duration::ZERO, Timer::Status);
+    scheduler.after(Duration::ZERO, Timer::Keyboard);
+    if app.greetd_wait.is_some() {
+        scheduler.after(Duration::ZERO, Timer::Greetd);
+    }
+    if !app.checks.is_empty() {
+        scheduler.after(Duration::ZERO, Timer::Checks);
+    }
+    let resources = app.resources.clone();
+    scheduler.spawn(move || Task::Resources(resources::check(&resources)));
+    scheduler.spawn(|| Task::SessionClass(status::wrong_session_class()));
+    scheduler.after(app.clock.next_tick(), Timer::Clock);
+    #[cfg(feature = "hotplug")]
+    scheduler.watch_hotplug();
+    note_activity(app, scheduler);
+}
+
+/// Ask `userdbctl` about a newly entered user in the background
+fn look_up_homed(app: &mut App, scheduler: &Scheduler) {
+    if let Some(username) = app.take_homed_lookup() {
+        scheduler.spawn(move || {
+            let homed = homed::is_homed_user(&username);
+            Task::Homed(username, homed)
+        });
+    }
+}
+
+/// Re-query the header status indicators in the background
+fn refresh_status(scheduler: &mut Scheduler) {
+    scheduler.spawn(|| Task::ClockSync(status::ntp_synchronized()));
+    scheduler.spawn(|| Task::RemoteSessions(status::remote_sessions()));
+    scheduler.spawn(|| Task::FailedUnits(status::failed_units()));
+    scheduler.spawn(|| Task::Airplane(rfkill::airplane()));
+    scheduler.after(status::REFRESH, Timer::Status);
+}
+
+/// Show the next notice and schedule the one after
+fn rotate_notices(app: &mut App, scheduler: &mut Scheduler) {
+    if let Some(interval) = app.rotate_notices(Instant::now()) {
+        scheduler.after(interval, Timer::Notice);
+    }
+}
+
+/// Run the health checks in the background, and again after the interval
+fn run_checks(app: &App, scheduler: &mut Scheduler) {
+    let checks = app.checks.clone();
+    scheduler.spawn(move || Task::Health(checks::run(&checks)));
+    scheduler.after(checks::INTERVAL, Timer::Checks);
+}
+
+/// Turn the spinner while a login attempt is out
+fn schedule_spinner(app: &App, scheduler: &mut Scheduler) {
+    if app.submission == Submission::Sent {
+        scheduler.after(submission::SPINNER_TICK, Timer::Spinner);
+    }
+}
+
+/// Re-read the input devices for the "no keyboard" notice
+fn poll_keyboard(app: &mut App, scheduler: &mut Scheduler) {
+    app.status.no_keyboard = keyboard::present() == Some(false);
+    scheduler.after(keyboard::POLL, Timer::Keyboard);
+}
+
+/// Queue what changed on screen for the screen reader announcement
+fn announce(app: &mut App, scheduler: &mut Scheduler) {
+    let screen = app.screen();
+    if let Some(ref mut announcer) = app.announcer {
+        if announcer.update(screen, Instant::now()) {
+            scheduler.after(announce::DEBOUNCE, Timer::Announce);
+        }
+    }
+}
+
+/// Speak the announcement once the screen held still
+fn speak(app: &mut App) {
+    if let Some(ref mut announcer) = app.announcer {
+        if let Some(text) = announcer.due(Instant::now()) {
+            announcer.speak(text);
+        }
+    }
+}
+
+/// Redraw the hold progress until the power action starts or the key is let go
+fn poll_hold(app: &mut App, scheduler: &mut Scheduler) {
+    app.poll_hold();
+    if app.power.is_some() {
+        scheduler.after(power::POLL, Timer::Power);
+    } else if app.hold.is_some() {
+        scheduler.after(power::HOLD_TICK, Timer::Hold);
+    }
+}
+
+/// Look for greetd's socket again, backing off, until it is up
+fn poll_greetd(app: &mut App, scheduler: &mut Scheduler) {
+    let next = app.greetd_wait.as_mut().and_then(greetd::Wait::poll);
+    app.status.waiting_greetd = next.is_some();
+    match next {
+        Some(delay) => scheduler.after(delay, Timer::Greetd),
+        None => app.greetd_wait = None,
+    }
+}
+
+/// A keyboard came or went: update the notice now rather than on the next
+/// poll, and put the console keymap back
+#[cfg(feature = "hotplug")]
+fn input_hotplugged(app: &mut App) {
+    app.status.no_keyboard = keyboard::present() == Some(false);
+    console::reload_keymap();
+}
+
+/// Someone is at the machine: restart the idle timers
+fn note_activity(app: &mut App, scheduler: &mut Scheduler) {
+    let now = Instant::now();
+    let dimmed = app.burn_in.as_ref().is_some_and(|burn_in| burn_in.dimmed(now));
+    let effect = app.screensaver.as_ref().is_some_and(Screensaver::running);
+    // Waking a dimmed screen fades it back in
+    if let Some(fade) = app.fade.as_mut().filter(|_| dimmed) {
+        fade.restart(now);
+    }
+    // and types the greeting out again, as does waking the idle effect
+    if let Some(typewriter) = app.typewriter.as_mut().filter(|_| dimmed || effect) {
+        typewriter.restart(now);
+    }
+    if let Some(ref mut burn_in) = app.burn_in {
+        burn_in.input(now);
+        scheduler.after(burn_in.next_change(now), Timer::BurnIn);
+    }
+    schedule_fade(app, scheduler);
+    schedule_typewriter(app, scheduler);
+    if let Some(ref mut screensaver) = app.screensaver {
+        screensaver.input(now);
+    }
+    poll_effect(app, scheduler);
+    if let Some(ref mut idle) = app.idle {
+        idle.activity(now);
+        scheduler.after(idle.next_poll(now), Timer::Idle);
+    }
+}
+
+/// Redraw for the next step of a running fade-in
+fn schedule_fade(app: &App, scheduler: &mut Scheduler) {
+    if let Some(next) = app.fade.as_ref().and_then(|fade| fade.next_frame(Instant::now())) {
+        scheduler.after(next, Timer::Fade);
+    }
+}
+
+/// Redraw for the next character of the greeting, while typing
+fn schedule_typewriter(app: &App, scheduler: &mut Scheduler) {
+    let now = Instant::now();
+    if let Some(next) = app.typewriter.as_ref().and_then(|typewriter| typewriter.next_frame(now)) {
+        scheduler.after(next, Timer::Typewriter);
+    }
+}
+
+/// Effect timer: the next frame, or start the effect once idle on AC power
+fn poll_effect(app: &mut App, scheduler: &mut Scheduler) {
+    let now = Instant::now();
+    let Some(ref mut screensaver) = app.screensaver else {
+        return;
+    };
+    if screensaver.check_due(now) {
+        // A few sysfs reads, quick enough for the event loop
+        screensaver.checked(idle::on_ac(), now);
+    }
+    screensaver.step();
+    if let Some(next) = screensaver.next_poll(now) {
+        scheduler.after(next, Timer::Effect);
+    }
+}
+
+/// Idle timer: check the exclusions in the background, or suspend once the
+/// countdown ran out
+fn poll_idle(app: &mut App, scheduler: &mut Scheduler) {
+    let now = Instant::now();
+    let Some(ref mut idle) = app.idle else {
+        return;
+    };
+    let poll = idle.poll(now);
+    scheduler.after(idle.next_poll(now), Timer::Idle);
+    match poll {
+        idle::Poll::Wait => {}
+        idle::Poll::Check => {
+            let options = idle.options().clone();
+            scheduler.spawn(move || Task::IdleExcluded(idle::excluded(&options)));
+        }
+        idle::Poll::Suspend => {
+            app.idle_suspend();
+            if app.power.is_some() {
+                scheduler.after(power::POLL, Timer::Power);
+            }
+        }
+    }
+}
+
+/// Countdown timer: redraw until it runs out, then start the session.
+/// Returns `true` once it started.
+fn poll_handoff(app: &mut App, scheduler: &mut Scheduler) -> bool {
+    let Some(tick) = app.handoff.and_then(|handoff| handoff.next_tick(Instant::now())) else {
+        return false;
+    };
+    if !tick.is_zero() {
+        scheduler.after(tick, Timer::Handoff);
+        return false;
+    }
+    app.start_session()
+}
+
+/// Act on a key press. Returns `true` once a login succeeded.
+fn handle_key(app: &mut App, scheduler: &mut Scheduler, key: KeyEvent) -> bool {
+    // The attempt under way took the password; keys wait for its verdict,
+    // except Esc, which gives up on it
+    if app.submission == Submission::Sent {
+        if key.code == KeyCode::Esc {
+            app.cancel_auth();
+        }
+        return false;
+    }
+    let key = match app.sticky {
+        Some(ref mut sticky) => match sticky.process(key) {
+            Some(key) => key,
+            None => return false,
+        },
+        None => key,
+    };
+
+    // The top focus context decides what the key means
+    let Some(action) = app.keymap.action(app.focus.top(), key) else {
+        return false;
+    };
+    if action != bindings::Action::Submit {
+        app.perform(action);
+        return false;
+    }
+    // Enter during the countdown starts the session at once
+    if app.handoff.is_some() {
+        return app.start_session();
+    }
+    if !app.submit() {
+        return false;
+    }
+    if let Some((attempt, login)) = app.start_auth() {
+        scheduler.spawn(move || Task::Auth(attempt, login()));
+        schedule_spinner(app, scheduler);
+    }
+    false
+}
+
+/// The backend answered a login attempt. Returns `true` once the session
+/// started.
+fn auth_done(
+    app: &mut App,
+    scheduler: &mut Scheduler,
+    attempt: u64,
+    result: Result<Outcome, AuthError>,
+) -> bool {
+    if app.finish_auth(attempt, result) {
+        return true;
+    }
+    if app.handoff.is_some() {
+        scheduler.after(Duration::ZERO, Timer::Handoff);
+    }
+    false
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+    use crate::app::UsernameField;
+    use crate::config::Config;
+    use crate::focus::{Field, FocusStack};
+    use crate::input::{Modifiers, Scripted};
+    use crate::policy::Policy;
+    use crate::sessions::Session;
+    use crate::testing::Timeline;
+    use crate::validate::Hint;
+
+    /// Step through `script` until the greeter quits or logs in
+    fn run(app: App, script: Scripted) -> (App, Step) {
+        let mut core = GreeterCore::new(app, script);
+        loop {
+            core.drawn();
+            match core.step().unwrap() {
+                Step::Redraw | Step::Repaint => {}
+                step @ (Step::LoggedIn | Step::Quit) => return (core.app, step),
+            }
+        }
+    }
+
+    #[test]
+    fn test_event_loop_headless() {
+        let mut app = App::new(&Config::default(), Policy::default());
+        app.username.clear();
+        app.focus = FocusStack::new(Field::Username);
+
+        // Skip the username, type a password, submit, then quit
+        let mut script = Scripted::typing("", &[KeyCode::Tab]);
+        script.0.extend(Scripted::typing("pw", &[KeyCode::Enter, KeyCode::Esc]).0);
+        let (app, step) = run(app, script);
+        assert_eq!(step, Step::Quit);
+
+        assert_eq!(app.password, "pw");
+        assert_eq!(app.error, None);
+        assert_eq!(app.hint, Some(Hint::error(Field::Username, "Username required")));
+        assert_eq!(app.focus.field(), Field::Username);
+    }
+
+    #[test]
+    fn test_locked_username() {
+        let mut config = Config::default();
+        config.keys.insert("edit_username".into(), "F9".into());
+        let mut app = App::new(&config, Policy::default());
+        app.username = "alice".to_string();
+        app.username_field = UsernameField::Locked;
+        app.focus = FocusStack::new(Field::Username);
+
+        let script = Scripted::typing("x", &[KeyCode::Backspace, KeyCode::Esc]);
+        let (mut app, step) = run(app, script);
+        assert_eq!(step, Step::Quit);
+        assert_eq!(app.username, "alice");
+        assert_eq!(app.hint, Some(Hint::note(Field::Username, "F9 to change the user")));
+
+        app.edit_username();
+        assert_eq!(app.hint, None);
+    }
+
+    #[test]
+    fn test_wheel() {
+        let mut app = App::new(&Config::default(), Policy::default());
+        app.sessions = vec![Session::from_command("sway"), Session::from_command("bash")];
+        app.set_failed_units(vec!["a.service".into(), "b.service".into(), "c.service".into()]);
+        app.show_failed_units();
+
+        // Scroll the list, close it, then the wheel picks the session
+        let esc = InputEvent::Key(KeyEvent::new(KeyCode::Esc, Modifiers::NONE));
+        let script = Scripted(
+            [
+                InputEvent::ScrollDown,
+                InputEvent::ScrollDown,
+                InputEvent::ScrollUp,
+                esc,
+                InputEvent::ScrollDown,
+                esc,
+            ]
+            .into(),
+        );
+        let (app, step) = run(app, script);
+        assert_eq!(step, Step::Quit);
+        assert_eq!(app.units_scroll.offset(), 1);
+        assert_eq!(app.session().name, "bash");
+    }
+
+    #[test]
+    fn test_error_timeout() {
+        let config = Config {
+            error_timeout_secs: 5,
+            keep_errors_while_typing: true,
+            ..Config::default()
+        };
+        let mut app = App::new(&config, Policy::default());
+        let time = Timeline::new();
+        app.error = Some("Authentication failed".to_string());
+        assert_eq!(app.error_drawn(time.secs(0)), Some(Duration::from_secs(5)));
+        assert_eq!(app.error_drawn(time.secs(1)), None);
+
+        // Typing leaves it, the timeout takes it away
+        app.input_char('x');
+        app.expire_error(time.secs(4));
+        assert!(app.error.is_some());
+        app.expire_error(time.secs(5));
+        assert_eq!(app.error, None);
+
+        // By default it stays until the next key
+        let mut app = App::new(&Config::default(), Policy::default());
+        app.error = Some("Authentication failed".to_string());
+        assert_eq!(app.error_drawn(time.secs(0)), None);
+        app.input_char('x');
+        assert_eq!(app.error, None);
+    }
+
+    #[test]
+    fn test_unknown_user() {
+        let unknown = || Err(AuthError::AuthFailed("pam_authenticate: USER_UNKNOWN".to_string()));
+        let mut app = App::new(&Config::default(), Policy::default());
+        app.username = "alcie".to_string();
+        app.focus = FocusStack::new(Field::Password);
+        app.submission = Submission::Sent;
+        app.finish_auth(0, unknown());
+        assert_eq!(app.error, None);
+        assert_eq!(app.hint, Some(Hint::error(Field::Username, "Unknown user")));
+        assert_eq!(app.focus.field(), Field::Username);
+
+        // Hardened setups say no more than for a wrong password
+        let mut app = App::new(&Config::default(), Policy::locked());
+        app.submission = Submission::Sent;
+        app.finish_auth(0, unknown());
+        assert_eq!(app.error.as_deref(), Some("Authentication failed"));
+        assert_eq!(app.focus.field(), Field::Password);
+    }
+
+    #[test]
+    fn test_late_verdict_of_cancelled_login() {
+        let mut app = App::new(&Config::default(), Policy::default());
+        app.username = "alice".to_string();
+        app.focus = FocusStack::new(Field::Password);
+
+        // Submit, the backend hangs, Esc, then submit again
+        app.password = "first".to_string();
+        assert!(app.submit());
+        let (first, _) = app.start_auth().unwrap();
+        app.cancel_auth();
+        app.password = "second".to_string();
+        assert!(app.submit());
+        let (second, _) = app.start_auth().unwrap();
+
+        // The first attempt answers while the second waits for the backend
+        assert!(!app.finish_auth(first, Ok(Outcome::Authenticated(Vec::new()))));
+        assert_eq!(app.submission, Submission::Sent);
+        assert!(app.handoff.is_none());
+
+        let wrong = AuthError::AuthFailed("pam_authenticate: AUTH_ERR".to_string());
+        assert!(!app.finish_auth(second, Err(wrong)));
+        assert_eq!(app.submission, Submission::Editing);
+        assert_eq!(app.error.as_deref(), Some("Wrong password"));
+    }
+}
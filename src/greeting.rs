@@ -0,0 +1,143 @@
+//! Greeting above the login form, by time of day
+//!
+//! With `[greeting] enabled`, a line such as "good evening" sits above the
+//! form. The phrases come in the greeter's language (English, German,
+//! French, Spanish, Italian, Dutch, Portuguese) or from the config, which
+//! can list several per part of the day; the pick changes daily.
+
+use chrono::{Datelike, Timelike};
+
+use crate::config::GreetingConfig;
+use crate::locale::Locale;
+
+/// Built-in morning, afternoon, evening and night phrases by language
+const PHRASES: [(&str, [&str; 4]); 7] = [
+    (
+        "en",
+        [
+            "good morning",
+            "good afternoon",
+            "good evening",
+            "good night",
+        ],
+    ),
+    (
+        "de",
+        ["guten morgen", "guten tag", "guten abend", "gute nacht"],
+    ),
+    ("fr", ["bonjour", "bon après-midi", "bonsoir", "bonne nuit"]),
+    (
+        "es",
+        [
+            "buenos días",
+            "buenas tardes",
+            "buenas tardes",
+            "buenas noches",
+        ],
+    ),
+    (
+        "it",
+        ["buongiorno", "buon pomeriggio", "buonasera", "buonanotte"],
+    ),
+    (
+        "nl",
+        ["goedemorgen", "goedemiddag", "goedenavond", "goedenacht"],
+    ),
+    ("pt", ["bom dia", "boa tarde", "boa noite", "boa noite"]),
+];
+
+/// Phrases for each part of the day: morning, afternoon, evening, night
+#[derive(Debug, Clone)]
+pub struct Greeting([Vec<String>; 4]);
+
+impl Greeting {
+    /// `None` unless enabled. Parts of the day without phrases in the
+    /// config get the built-in one for `locale`.
+    pub fn new(options: &GreetingConfig, locale: Locale) -> Option<Self> {
+        if !options.enabled {
+            return None;
+        }
+        let language = locale.language();
+        let builtin = PHRASES
+            .iter()
+            .find(|(code, _)| *code == language)
+            .map_or(PHRASES[0].1, |&(_, phrases)| phrases);
+        let configured = [
+            &options.morning,
+            &options.afternoon,
+            &options.evening,
+            &options.night,
+        ];
+        Some(Self(std::array::from_fn(|part| {
+            if configured[part].is_empty() {
+                vec![builtin[part].to_string()]
+            } else {
+                configured[part].clone()
+            }
+        })))
+    }
+
+    /// The greeting at `time`
+    pub fn text(&self, time: &impl Timelike, date: &impl Datelike) -> &str {
+        let part = match time.hour() {
+            5..=11 => 0,
+            12..=17 => 1,
+            18..=21 => 2,
+            _ => 3,
+        };
+        let phrases = &self.0[part];
+        let day = usize::try_from(date.num_days_from_ce()).unwrap_or(0);
+        phrases[day % phrases.len()].as_str()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn at(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parts_of_day() {
+        let options = GreetingConfig {
+            enabled: true,
+            ..GreetingConfig::default()
+        };
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let greeting = Greeting::new(&options, Locale::default()).unwrap();
+        assert_eq!(greeting.text(&at(4), &day), "good night");
+        assert_eq!(greeting.text(&at(5), &day), "good morning");
+        assert_eq!(greeting.text(&at(12), &day), "good afternoon");
+        assert_eq!(greeting.text(&at(21), &day), "good evening");
+        assert_eq!(greeting.text(&at(22), &day), "good night");
+
+        let german = Greeting::new(&options, Locale::parse("de_AT.UTF-8")).unwrap();
+        assert_eq!(german.text(&at(19), &day), "guten abend");
+        // No phrases for Finnish: English
+        let finnish = Greeting::new(&options, Locale::parse("fi_FI")).unwrap();
+        assert_eq!(finnish.text(&at(19), &day), "good evening");
+
+        assert!(Greeting::new(&GreetingConfig::default(), Locale::default()).is_none());
+    }
+
+    #[test]
+    fn test_configured_phrases() {
+        let options = GreetingConfig {
+            enabled: true,
+            evening: vec!["evening, operator".to_string(), "still here?".to_string()],
+            ..GreetingConfig::default()
+        };
+        let greeting = Greeting::new(&options, Locale::parse("de_DE")).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        assert_ne!(
+            greeting.text(&at(19), &monday),
+            greeting.text(&at(19), &tuesday)
+        );
+        assert_eq!(greeting.text(&at(8), &monday), "guten morgen");
+    }
+}
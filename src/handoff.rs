@@ -0,0 +1,79 @@
+//! Last chance to pick another session after logging in
+//!
+//! With `[handoff] countdown` set, a successful login doesn't start the
+//! session right away but counts down on screen first ("Starting Hyprland
+//! in 3"). Esc stops the countdown so another session can be picked
+//! without typing the password again; Enter starts it at once.
+
+use std::time::{Duration, Instant};
+
+/// Longest wait between redraws of the countdown
+pub const TICK: Duration = Duration::from_secs(1);
+
+/// An authenticated login waiting for its session to start
+#[derive(Debug, Clone, Copy)]
+pub struct Handoff {
+    /// When the session starts by itself; `None` once stopped
+    deadline: Option<Instant>,
+}
+
+impl Handoff {
+    pub fn new(countdown: Duration, now: Instant) -> Self {
+        Self {
+            deadline: Some(now + countdown),
+        }
+    }
+
+    /// Wait for Enter instead
+    pub const fn stop(&mut self) {
+        self.deadline = None;
+    }
+
+    /// Time left on the countdown, while it runs
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(now))
+    }
+
+    /// Whole seconds shown, counting 3, 2, 1
+    pub fn seconds(&self, now: Instant) -> Option<u64> {
+        self.remaining(now)
+            .map(|left| left.as_secs() + u64::from(left.subsec_nanos() > 0))
+    }
+
+    /// Time until the shown number changes or the session starts
+    pub fn next_tick(&self, now: Instant) -> Option<Duration> {
+        self.remaining(now).map(|left| match left.subsec_nanos() {
+            0 => left.min(TICK),
+            nanos => Duration::from_nanos(u64::from(nanos)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    #[test]
+    fn test_countdown() {
+        let time = Timeline::new();
+        let mut handoff = Handoff::new(Duration::from_secs(3), time.start);
+        assert_eq!(handoff.seconds(time.start), Some(3));
+        assert_eq!(handoff.next_tick(time.start), Some(TICK));
+        assert_eq!(handoff.seconds(time.millis(500)), Some(3));
+        assert_eq!(
+            handoff.next_tick(time.millis(500)),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(handoff.seconds(time.millis(1000)), Some(2));
+        assert_eq!(handoff.seconds(time.millis(2999)), Some(1));
+        assert_eq!(handoff.remaining(time.millis(3500)), Some(Duration::ZERO));
+        assert_eq!(handoff.next_tick(time.millis(3500)), Some(Duration::ZERO));
+
+        handoff.stop();
+        assert_eq!(handoff.seconds(time.millis(1000)), None);
+        assert_eq!(handoff.next_tick(time.millis(1000)), None);
+    }
+}
@@ -0,0 +1,47 @@
+//! systemd-homed integration
+//!
+//! homed users unlock their (encrypted) home directory with the login
+//! password, so the greeter says so. Looked up through `userdbctl`.
+
+use std::process::Command;
+
+use serde::Deserialize;
+
+/// userdb service name of records managed by systemd-homed
+const HOMED_SERVICE: &str = "io.systemd.Home";
+
+/// The parts of a JSON user record we care about
+#[derive(Debug, Deserialize)]
+struct UserRecord {
+    #[serde(default)]
+    service: Option<String>,
+}
+
+/// Whether `username` is managed by systemd-homed
+pub fn is_homed_user(username: &str) -> bool {
+    Command::new("userdbctl")
+        .args(["--output=json", "user", "--", username])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .is_some_and(|output| is_homed_record(&output.stdout))
+}
+
+fn is_homed_record(json: &[u8]) -> bool {
+    serde_json::from_slice::<UserRecord>(json)
+        .is_ok_and(|record| record.service.as_deref() == Some(HOMED_SERVICE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_homed_record() {
+        assert!(is_homed_record(
+            br#"{"userName":"me","service":"io.systemd.Home","uid":60100}"#
+        ));
+        assert!(!is_homed_record(br#"{"userName":"root","uid":0}"#));
+        assert!(!is_homed_record(b"not json"));
+    }
+}
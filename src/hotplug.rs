@@ -0,0 +1,86 @@
+//! Input devices plugged in or removed
+//!
+//! Listens on the kernel's uevent netlink socket, the one udev itself reads,
+//! for `input` devices coming and going. No libudev needed and no special
+//! permissions: anyone may receive the kernel's events. A keyboard shows up
+//! as a burst of them (the device, its event node, its LEDs), reported once.
+
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::thread;
+use std::time::Duration;
+
+use nix::sys::socket::{
+    AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol, SockType, bind, recv, socket,
+};
+
+/// Multicast group of the kernel's own events (udev re-sends on group 2)
+const KERNEL_EVENTS: u32 = 1;
+
+/// Time for the rest of a burst to arrive
+const SETTLE: Duration = Duration::from_millis(300);
+
+/// Uevents are at most a page or two of `KEY=value` lines
+const BUFFER: usize = 8192;
+
+/// Call `notify` from a background thread after an input device was added
+/// or removed, until it returns `false`. Returns `false` when the uevent
+/// socket can't be opened.
+pub fn watch(notify: impl Fn() -> bool + Send + 'static) -> bool {
+    let Ok(socket) = socket(
+        AddressFamily::Netlink,
+        SockType::Datagram,
+        SockFlag::SOCK_CLOEXEC,
+        SockProtocol::NetlinkKObjectUEvent,
+    ) else {
+        return false;
+    };
+    if bind(socket.as_raw_fd(), &NetlinkAddr::new(0, KERNEL_EVENTS)).is_err() {
+        return false;
+    }
+    thread::spawn(move || listen(&socket, &notify));
+    true
+}
+
+fn listen(socket: &OwnedFd, notify: &impl Fn() -> bool) {
+    let mut buffer = [0; BUFFER];
+    while let Ok(read) = recv(socket.as_raw_fd(), &mut buffer, MsgFlags::empty()) {
+        if !is_input_change(&buffer[..read]) {
+            continue;
+        }
+        // Throw away the rest of the burst, whatever it is
+        thread::sleep(SETTLE);
+        while recv(socket.as_raw_fd(), &mut buffer, MsgFlags::MSG_DONTWAIT).is_ok() {}
+        if !notify() {
+            return;
+        }
+    }
+}
+
+/// `add@/devices/…/input/input7` followed by NUL-separated `KEY=value`
+/// fields; an `input` device being added or removed
+fn is_input_change(message: &[u8]) -> bool {
+    let fields: Vec<&[u8]> = message.split(|&byte| byte == 0).collect();
+    fields.contains(&b"SUBSYSTEM=input".as_slice())
+        && (fields.contains(&b"ACTION=add".as_slice())
+            || fields.contains(&b"ACTION=remove".as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_input_change() {
+        let added = b"add@/devices/virtual/input/input7\0ACTION=add\0\
+DEVPATH=/devices/virtual/input/input7\0SUBSYSTEM=input\0SEQNUM=4121\0";
+        assert!(is_input_change(added));
+        let removed = b"remove@/devices/virtual/input/input7/event3\0ACTION=remove\0\
+SUBSYSTEM=input\0DEVNAME=input/event3\0";
+        assert!(is_input_change(removed));
+        let changed = b"change@/devices/virtual/input/input7\0ACTION=change\0SUBSYSTEM=input\0";
+        assert!(!is_input_change(changed));
+        let usb = b"add@/devices/pci0000:00/usb1/1-1\0ACTION=add\0SUBSYSTEM=usb\0";
+        assert!(!is_input_change(usb));
+        assert!(!is_input_change(b""));
+    }
+}
@@ -0,0 +1,219 @@
+//! Suspend the machine after it sat at the login screen for a while
+//!
+//! `[power.idle] suspend_after` minutes without input start a countdown
+//! on screen; a key stops it, otherwise the machine suspends. Before the
+//! countdown the exclusions are checked off the event loop: AC power when
+//! `except_on_ac` is set, and anyone logged in remotely (logind's `Remote`
+//! sessions, e.g. SSH).
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use crate::config::IdleConfig;
+use crate::status;
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// How long an excluded machine waits before checking again
+const RETRY: Duration = Duration::from_mins(1);
+
+/// How often the countdown is redrawn
+const TICK: Duration = Duration::from_secs(1);
+
+/// What the event loop should do on the idle timer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Poll {
+    Wait,
+    /// Idle long enough; check the exclusions in the background
+    Check,
+    /// The countdown ran out
+    Suspend,
+}
+
+#[derive(Debug, Clone)]
+pub struct IdleSuspend {
+    options: IdleConfig,
+    /// When the exclusions are checked next
+    check_at: Instant,
+    /// A check is running in the background
+    checking: bool,
+    /// End of the countdown on screen
+    deadline: Option<Instant>,
+}
+
+impl IdleSuspend {
+    /// `None` unless `suspend_after` is set
+    pub fn new(options: &IdleConfig, now: Instant) -> Option<Self> {
+        (options.suspend_after > 0).then(|| {
+            let mut idle = Self {
+                options: options.clone(),
+                check_at: now,
+                checking: false,
+                deadline: None,
+            };
+            idle.activity(now);
+            idle
+        })
+    }
+
+    const fn after(&self) -> Duration {
+        Duration::from_mins(self.options.suspend_after)
+    }
+
+    const fn countdown(&self) -> Duration {
+        Duration::from_secs(self.options.countdown)
+    }
+
+    /// Someone is there; stop any countdown and start over
+    pub fn activity(&mut self, now: Instant) {
+        self.check_at = now + self.after().saturating_sub(self.countdown());
+        self.checking = false;
+        self.deadline = None;
+    }
+
+    pub fn poll(&mut self, now: Instant) -> Poll {
+        if let Some(deadline) = self.deadline {
+            if now < deadline {
+                return Poll::Wait;
+            }
+            self.activity(now);
+            return Poll::Suspend;
+        }
+        if self.checking || now < self.check_at {
+            return Poll::Wait;
+        }
+        self.checking = true;
+        Poll::Check
+    }
+
+    /// The background check is done; a key pressed meanwhile wins
+    pub fn checked(&mut self, excluded: bool, now: Instant) {
+        if !std::mem::take(&mut self.checking) {
+            return;
+        }
+        if excluded {
+            self.check_at = now + RETRY;
+        } else {
+            self.deadline = Some(now + self.countdown());
+        }
+    }
+
+    /// Time until the idle timer should fire again
+    pub fn next_poll(&self, now: Instant) -> Duration {
+        match self.deadline {
+            Some(deadline) => deadline.saturating_duration_since(now).min(TICK),
+            // The check's result reschedules
+            None if self.checking => RETRY,
+            None => self.check_at.saturating_duration_since(now),
+        }
+    }
+
+    /// Time left on the countdown, while it runs
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(now))
+    }
+
+    pub const fn options(&self) -> &IdleConfig {
+        &self.options
+    }
+}
+
+/// Whether an exclusion applies right now. Blocks on loginctl.
+pub fn excluded(options: &IdleConfig) -> bool {
+    // Without logind nobody is assumed to be logged in remotely
+    (options.except_on_ac && on_ac())
+        || (options.except_remote && status::remote_sessions().is_some_and(|count| count > 0))
+}
+
+/// Mains power is online, or there is no battery at all (a desktop)
+pub fn on_ac() -> bool {
+    let Ok(entries) = fs::read_dir(POWER_SUPPLY) else {
+        return true;
+    };
+    let supplies: Vec<(String, String)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let read = |name: &str| {
+                fs::read_to_string(entry.path().join(name))
+                    .map(|value| value.trim().to_string())
+                    .unwrap_or_default()
+            };
+            (read("type"), read("online"))
+        })
+        .collect();
+    mains_online(&supplies)
+}
+
+/// `(type, online)` of each power supply
+fn mains_online(supplies: &[(String, String)]) -> bool {
+    supplies
+        .iter()
+        .any(|(kind, online)| kind == "Mains" && online == "1")
+        || !supplies.iter().any(|(kind, _)| kind == "Battery")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    #[test]
+    fn test_countdown() {
+        let time = Timeline::new();
+        let options = IdleConfig {
+            suspend_after: 10,
+            ..IdleConfig::default()
+        };
+        let mut idle = IdleSuspend::new(&options, time.start).unwrap();
+        // Check a countdown before the ten minutes are up
+        assert_eq!(idle.next_poll(time.start), Duration::from_mins(9));
+        assert_eq!(idle.poll(time.secs(539)), Poll::Wait);
+        assert_eq!(idle.poll(time.secs(540)), Poll::Check);
+        assert_eq!(idle.poll(time.secs(541)), Poll::Wait);
+        idle.checked(false, time.secs(541));
+        assert_eq!(
+            idle.remaining(time.secs(571)),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(idle.next_poll(time.secs(571)), TICK);
+        assert_eq!(idle.poll(time.secs(600)), Poll::Wait);
+        assert_eq!(idle.poll(time.secs(601)), Poll::Suspend);
+        assert_eq!(idle.remaining(time.secs(601)), None);
+    }
+
+    #[test]
+    fn test_activity_and_exclusions() {
+        let time = Timeline::new();
+        let options = IdleConfig {
+            suspend_after: 10,
+            ..IdleConfig::default()
+        };
+        let mut idle = IdleSuspend::new(&options, time.start).unwrap();
+        assert_eq!(idle.poll(time.secs(540)), Poll::Check);
+        idle.checked(true, time.secs(541));
+        assert_eq!(idle.remaining(time.secs(542)), None);
+        assert_eq!(idle.poll(time.secs(600)), Poll::Wait);
+        assert_eq!(idle.poll(time.secs(601)), Poll::Check);
+
+        // A key while the check runs: its result no longer counts
+        idle.activity(time.secs(602));
+        idle.checked(false, time.secs(603));
+        assert_eq!(idle.remaining(time.secs(603)), None);
+        assert_eq!(idle.next_poll(time.secs(602)), Duration::from_mins(9));
+    }
+
+    #[test]
+    fn test_exclusion_probes() {
+        let supply = |kind: &str, online: &str| (kind.to_string(), online.to_string());
+        assert!(mains_online(&[]));
+        assert!(mains_online(&[supply("Mains", "1"), supply("Battery", "")]));
+        assert!(!mains_online(&[
+            supply("Mains", "0"),
+            supply("Battery", "")
+        ]));
+        assert!(!mains_online(&[supply("Battery", "")]));
+    }
+}
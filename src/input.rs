@@ -0,0 +1,252 @@
+//! Input events independent of the terminal library
+//!
+//! The event loop reads [`InputEvent`]s from an [`EventSource`]. The crossterm
+//! source translates terminal events; tests feed a scripted sequence to
+//! drive the whole interaction loop without a terminal.
+
+use std::io;
+use std::time::Duration;
+
+use crossterm::event::{self as term, KeyEventKind, ModifierKeyCode};
+
+/// Modifier keys held with a key, or latched by sticky keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const NONE: Self = Self(0);
+    pub const SHIFT: Self = Self(1);
+    pub const CONTROL: Self = Self(1 << 1);
+    pub const ALT: Self = Self(1 << 2);
+    pub const SUPER: Self = Self(1 << 3);
+
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    #[must_use]
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// Add `other` if it isn't fully held, remove it otherwise
+    #[must_use]
+    pub const fn toggle(self, other: Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+}
+
+/// The keys grxxt cares about; everything else never reaches the app
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCode {
+    Char(char),
+    F(u8),
+    Enter,
+    Esc,
+    Tab,
+    BackTab,
+    Backspace,
+    Up,
+    Down,
+    /// A modifier pressed on its own (kitty keyboard protocol only)
+    Modifier(Modifiers),
+}
+
+/// A key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl KeyEvent {
+    #[must_use]
+    pub const fn new(code: KeyCode, modifiers: Modifiers) -> Self {
+        Self { code, modifiers }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    Key(KeyEvent),
+    /// The terminal changed size; the next frame is drawn from scratch
+    Resize,
+    /// Mouse wheel or touchpad scrolled up
+    ScrollUp,
+    /// Mouse wheel or touchpad scrolled down
+    ScrollDown,
+}
+
+/// Where the event loop gets its input from
+pub trait EventSource {
+    /// Wait up to `timeout` for the next event; `None` when nothing happened
+    ///
+    /// # Errors
+    ///
+    /// When the source broke; the event loop stops with it.
+    fn next(&mut self, timeout: Duration) -> io::Result<Option<InputEvent>>;
+}
+
+/// Events from the terminal
+pub struct Terminal;
+
+impl EventSource for Terminal {
+    fn next(&mut self, timeout: Duration) -> io::Result<Option<InputEvent>> {
+        if term::poll(timeout)? {
+            Ok(convert(&term::read()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Translate a crossterm event. Releases, repeats, mouse events other than
+/// scrolling, focus events and keys without a use in grxxt are dropped.
+#[allow(
+    clippy::wildcard_enum_match_arm,
+    reason = "KeyCode has 20+ variants from external crate"
+)]
+fn convert(event: &term::Event) -> Option<InputEvent> {
+    let key = match *event {
+        term::Event::Key(key) if key.kind == KeyEventKind::Press => key,
+        term::Event::Resize(..) => return Some(InputEvent::Resize),
+        term::Event::Mouse(mouse) => {
+            return match mouse.kind {
+                term::MouseEventKind::ScrollUp => Some(InputEvent::ScrollUp),
+                term::MouseEventKind::ScrollDown => Some(InputEvent::ScrollDown),
+                _ => None,
+            };
+        }
+        _ => return None,
+    };
+    let code = match key.code {
+        term::KeyCode::Char(c) => KeyCode::Char(c),
+        term::KeyCode::F(n) => KeyCode::F(n),
+        term::KeyCode::Enter => KeyCode::Enter,
+        term::KeyCode::Esc => KeyCode::Esc,
+        term::KeyCode::Tab => KeyCode::Tab,
+        term::KeyCode::BackTab => KeyCode::BackTab,
+        term::KeyCode::Backspace => KeyCode::Backspace,
+        term::KeyCode::Up => KeyCode::Up,
+        term::KeyCode::Down => KeyCode::Down,
+        term::KeyCode::Modifier(code) => KeyCode::Modifier(modifier(code)),
+        _ => return None,
+    };
+    let modifiers = [
+        (term::KeyModifiers::SHIFT, Modifiers::SHIFT),
+        (term::KeyModifiers::CONTROL, Modifiers::CONTROL),
+        (term::KeyModifiers::ALT, Modifiers::ALT),
+        (term::KeyModifiers::SUPER, Modifiers::SUPER),
+    ]
+    .iter()
+    .filter(|(flag, _)| key.modifiers.contains(*flag))
+    .fold(Modifiers::NONE, |held, (_, modifier)| held.union(*modifier));
+    Some(InputEvent::Key(KeyEvent::new(code, modifiers)))
+}
+
+/// The modifier a modifier key stands for
+const fn modifier(code: ModifierKeyCode) -> Modifiers {
+    match code {
+        ModifierKeyCode::LeftShift | ModifierKeyCode::RightShift => Modifiers::SHIFT,
+        ModifierKeyCode::LeftControl | ModifierKeyCode::RightControl => Modifiers::CONTROL,
+        ModifierKeyCode::LeftAlt | ModifierKeyCode::RightAlt => Modifiers::ALT,
+        ModifierKeyCode::LeftSuper | ModifierKeyCode::RightSuper => Modifiers::SUPER,
+        // Level shifts pick characters rather than forming chords; hyper and
+        // meta have no bindings
+        ModifierKeyCode::IsoLevel3Shift
+        | ModifierKeyCode::IsoLevel5Shift
+        | ModifierKeyCode::LeftHyper
+        | ModifierKeyCode::RightHyper
+        | ModifierKeyCode::LeftMeta
+        | ModifierKeyCode::RightMeta => Modifiers::NONE,
+    }
+}
+
+/// Replays a fixed sequence of events, for headless tests of the event loop
+#[cfg(test)]
+pub struct Scripted(pub std::collections::VecDeque<InputEvent>);
+
+#[cfg(test)]
+impl Scripted {
+    /// Key presses typing `text`, followed by `keys`
+    pub fn typing(text: &str, keys: &[KeyCode]) -> Self {
+        let chars = text.chars().map(KeyCode::Char);
+        Self(
+            chars
+                .chain(keys.iter().copied())
+                .map(|code| InputEvent::Key(KeyEvent::new(code, Modifiers::NONE)))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+impl EventSource for Scripted {
+    /// Running out of events is an error so a stuck loop fails the test
+    fn next(&mut self, _timeout: Duration) -> io::Result<Option<InputEvent>> {
+        self.0
+            .pop_front()
+            .map(Some)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "script exhausted"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let press = term::KeyEvent::new(
+            term::KeyCode::Char('u'),
+            term::KeyModifiers::CONTROL | term::KeyModifiers::HYPER,
+        );
+        assert_eq!(
+            convert(&term::Event::Key(press)),
+            Some(InputEvent::Key(KeyEvent::new(
+                KeyCode::Char('u'),
+                Modifiers::CONTROL
+            )))
+        );
+
+        let mut release = press;
+        release.kind = KeyEventKind::Release;
+        assert_eq!(convert(&term::Event::Key(release)), None);
+
+        let home = term::KeyEvent::new(term::KeyCode::Home, term::KeyModifiers::NONE);
+        assert_eq!(convert(&term::Event::Key(home)), None);
+        assert_eq!(
+            convert(&term::Event::Resize(80, 24)),
+            Some(InputEvent::Resize)
+        );
+
+        let mouse = |kind| {
+            term::Event::Mouse(term::MouseEvent {
+                kind,
+                column: 0,
+                row: 0,
+                modifiers: term::KeyModifiers::NONE,
+            })
+        };
+        assert_eq!(
+            convert(&mouse(term::MouseEventKind::ScrollDown)),
+            Some(InputEvent::ScrollDown)
+        );
+        assert_eq!(convert(&mouse(term::MouseEventKind::Moved)), None);
+    }
+
+    #[test]
+    fn test_modifiers() {
+        let held = Modifiers::CONTROL.union(Modifiers::SHIFT);
+        assert!(held.contains(Modifiers::SHIFT));
+        assert!(!held.contains(Modifiers::ALT));
+        assert_eq!(held.difference(Modifiers::SHIFT), Modifiers::CONTROL);
+        assert_eq!(held.toggle(Modifiers::CONTROL), Modifiers::SHIFT);
+    }
+}
@@ -0,0 +1,261 @@
+//! Romaji to kana transliteration for the username field
+//!
+//! The console has no input method, so Japanese account names can't be
+//! typed there. This mode (Ctrl+Space, cycling hiragana, katakana and off)
+//! turns romaji into kana as it is typed, Hepburn style: `ka` gives か,
+//! `kka` gives っか, `nn` or `n` before a consonant gives ん.
+
+/// Hepburn romaji for the hiragana syllables. A syllable is converted as
+/// soon as its spelling is complete, so no entry may be a prefix of another;
+/// a lone `n` is handled separately.
+const TABLE: [(&str, &str); 107] = [
+    ("a", "あ"),
+    ("i", "い"),
+    ("u", "う"),
+    ("e", "え"),
+    ("o", "お"),
+    ("ka", "か"),
+    ("ki", "き"),
+    ("ku", "く"),
+    ("ke", "け"),
+    ("ko", "こ"),
+    ("sa", "さ"),
+    ("shi", "し"),
+    ("si", "し"),
+    ("su", "す"),
+    ("se", "せ"),
+    ("so", "そ"),
+    ("ta", "た"),
+    ("chi", "ち"),
+    ("ti", "ち"),
+    ("tsu", "つ"),
+    ("tu", "つ"),
+    ("te", "て"),
+    ("to", "と"),
+    ("na", "な"),
+    ("ni", "に"),
+    ("nu", "ぬ"),
+    ("ne", "ね"),
+    ("no", "の"),
+    ("ha", "は"),
+    ("hi", "ひ"),
+    ("fu", "ふ"),
+    ("hu", "ふ"),
+    ("he", "へ"),
+    ("ho", "ほ"),
+    ("ma", "ま"),
+    ("mi", "み"),
+    ("mu", "む"),
+    ("me", "め"),
+    ("mo", "も"),
+    ("ya", "や"),
+    ("yu", "ゆ"),
+    ("yo", "よ"),
+    ("ra", "ら"),
+    ("ri", "り"),
+    ("ru", "る"),
+    ("re", "れ"),
+    ("ro", "ろ"),
+    ("wa", "わ"),
+    ("wo", "を"),
+    ("nn", "ん"),
+    ("ga", "が"),
+    ("gi", "ぎ"),
+    ("gu", "ぐ"),
+    ("ge", "げ"),
+    ("go", "ご"),
+    ("za", "ざ"),
+    ("ji", "じ"),
+    ("zi", "じ"),
+    ("zu", "ず"),
+    ("ze", "ぜ"),
+    ("zo", "ぞ"),
+    ("da", "だ"),
+    ("di", "ぢ"),
+    ("du", "づ"),
+    ("de", "で"),
+    ("do", "ど"),
+    ("ba", "ば"),
+    ("bi", "び"),
+    ("bu", "ぶ"),
+    ("be", "べ"),
+    ("bo", "ぼ"),
+    ("pa", "ぱ"),
+    ("pi", "ぴ"),
+    ("pu", "ぷ"),
+    ("pe", "ぺ"),
+    ("po", "ぽ"),
+    ("kya", "きゃ"),
+    ("kyu", "きゅ"),
+    ("kyo", "きょ"),
+    ("sha", "しゃ"),
+    ("shu", "しゅ"),
+    ("sho", "しょ"),
+    ("cha", "ちゃ"),
+    ("chu", "ちゅ"),
+    ("cho", "ちょ"),
+    ("nya", "にゃ"),
+    ("nyu", "にゅ"),
+    ("nyo", "にょ"),
+    ("hya", "ひゃ"),
+    ("hyu", "ひゅ"),
+    ("hyo", "ひょ"),
+    ("mya", "みゃ"),
+    ("myu", "みゅ"),
+    ("myo", "みょ"),
+    ("rya", "りゃ"),
+    ("ryu", "りゅ"),
+    ("ryo", "りょ"),
+    ("gya", "ぎゃ"),
+    ("gyu", "ぎゅ"),
+    ("gyo", "ぎょ"),
+    ("ja", "じゃ"),
+    ("ju", "じゅ"),
+    ("jo", "じょ"),
+    ("bya", "びゃ"),
+    ("byu", "びゅ"),
+    ("byo", "びょ"),
+    ("-", "ー"),
+];
+
+/// Distance from a hiragana to its katakana in Unicode
+const KATAKANA_OFFSET: u32 = 0x60;
+
+/// Kana script being typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    Hiragana,
+    Katakana,
+}
+
+/// Transliteration mode with the romaji typed so far
+#[derive(Debug)]
+pub struct Kana {
+    pub script: Script,
+    /// Romaji that doesn't form a syllable yet, shown after the field
+    pub pending: String,
+}
+
+impl Kana {
+    pub const fn new(script: Script) -> Self {
+        Self {
+            script,
+            pending: String::new(),
+        }
+    }
+
+    /// Feed a typed character. Returns the text to insert, which is empty
+    /// while a syllable is incomplete.
+    pub fn feed(&mut self, c: char) -> String {
+        // Romaji is matched case-insensitively
+        let c = c.to_ascii_lowercase();
+        let mut out = String::new();
+
+        // `n` before anything that can't follow it in a syllable is ん
+        if self.pending == "n" && !matches!(c, 'a' | 'i' | 'u' | 'e' | 'o' | 'y' | 'n') {
+            out.push_str(&self.convert("ん"));
+            self.pending.clear();
+        }
+        // A doubled consonant is a small っ before the syllable
+        if self.pending.len() == 1
+            && self.pending.starts_with(c)
+            && c.is_ascii_alphabetic()
+            && !matches!(c, 'a' | 'i' | 'u' | 'e' | 'o' | 'n')
+        {
+            out.push_str(&self.convert("っ"));
+            self.pending.clear();
+        }
+
+        self.pending.push(c);
+        if let Some(&(_, kana)) = TABLE.iter().find(|(romaji, _)| *romaji == self.pending) {
+            out.push_str(&self.convert(kana));
+            self.pending.clear();
+        } else if !TABLE.iter().any(|(romaji, _)| romaji.starts_with(&self.pending)) {
+            // Not romaji: typed as is
+            out.push_str(&self.pending);
+            self.pending.clear();
+        }
+        out
+    }
+
+    /// Leaving the field or the mode: a trailing `n` is ん, anything else
+    /// incomplete stays latin
+    pub fn flush(&mut self) -> String {
+        let pending = std::mem::take(&mut self.pending);
+        if pending == "n" {
+            self.convert("ん")
+        } else {
+            pending
+        }
+    }
+
+    /// Mode name for the header
+    pub const fn label(&self) -> &'static str {
+        match self.script {
+            Script::Hiragana => "かな",
+            Script::Katakana => "カナ",
+        }
+    }
+
+    fn convert(&self, hiragana: &str) -> String {
+        match self.script {
+            Script::Hiragana => hiragana.to_string(),
+            Script::Katakana => hiragana
+                .chars()
+                .map(|c| match c {
+                    'ぁ'..='ゖ' => char::from_u32(c as u32 + KATAKANA_OFFSET).unwrap_or(c),
+                    _ => c,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_romaji(script: Script, romaji: &str) -> String {
+        let mut kana = Kana::new(script);
+        let mut text: String = romaji.chars().map(|c| kana.feed(c)).collect();
+        text.push_str(&kana.flush());
+        text
+    }
+
+    #[test]
+    fn test_hiragana() {
+        assert_eq!(type_romaji(Script::Hiragana, "sakura"), "さくら");
+        assert_eq!(type_romaji(Script::Hiragana, "kyouko"), "きょうこ");
+        assert_eq!(type_romaji(Script::Hiragana, "shinji"), "しんじ");
+        assert_eq!(type_romaji(Script::Hiragana, "kenta"), "けんた");
+        // `nn` is ん, as in common IMEs
+        assert_eq!(type_romaji(Script::Hiragana, "hannna"), "はんな");
+        assert_eq!(type_romaji(Script::Hiragana, "makoto"), "まこと");
+        assert_eq!(type_romaji(Script::Hiragana, "kin"), "きん");
+    }
+
+    #[test]
+    fn test_small_tsu_and_katakana() {
+        assert_eq!(type_romaji(Script::Hiragana, "hattori"), "はっとり");
+        assert_eq!(type_romaji(Script::Katakana, "rokku"), "ロック");
+        assert_eq!(type_romaji(Script::Katakana, "ke-ki"), "ケーキ");
+    }
+
+    #[test]
+    fn test_non_romaji_passes_through() {
+        assert_eq!(type_romaji(Script::Hiragana, "x1"), "x1");
+        assert_eq!(type_romaji(Script::Hiragana, "ka7"), "か7");
+        // Incomplete syllables stay latin
+        assert_eq!(type_romaji(Script::Hiragana, "sh"), "sh");
+    }
+
+    #[test]
+    fn test_pending_romaji() {
+        let mut kana = Kana::new(Script::Hiragana);
+        assert_eq!(kana.feed('k'), "");
+        assert_eq!(kana.feed('y'), "");
+        assert_eq!(kana.pending, "ky");
+        assert_eq!(kana.feed('a'), "きゃ");
+        assert!(kana.pending.is_empty());
+    }
+}
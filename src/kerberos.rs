@@ -0,0 +1,43 @@
+//! Kerberos / Active Directory login feedback
+//!
+//! `pam_krb5` and sssd report KDC failures as free-form PAM messages that
+//! mean little to users. Recognise the common ones and say what to check.
+
+/// Lowercase fragments of KDC errors and the hint shown instead
+const HINTS: [(&str, &str); 8] = [
+    ("clock skew", "Clock out of sync with the domain. Check time sync"),
+    ("cannot contact any kdc", "Domain controller unreachable. Check network/time"),
+    ("cannot find kdc", "Domain controller unreachable. Check network/time"),
+    ("kdc unreachable", "Domain controller unreachable. Check network/time"),
+    ("preauthentication failed", "Wrong password"),
+    ("client not found in kerberos database", "Unknown domain user"),
+    ("password has expired", "Domain password expired. Change it before logging in"),
+    ("key has expired", "Domain password expired. Change it before logging in"),
+];
+
+/// Actionable replacement for a Kerberos error message, if it is one
+pub fn explain(message: &str) -> Option<&'static str> {
+    let message = message.to_lowercase();
+    HINTS
+        .iter()
+        .find(|(fragment, _)| message.contains(fragment))
+        .map(|(_, hint)| *hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            explain("Clock skew too great while getting initial credentials"),
+            Some("Clock out of sync with the domain. Check time sync")
+        );
+        assert_eq!(
+            explain("Cannot contact any KDC for realm 'CORP.EXAMPLE.COM'"),
+            Some("Domain controller unreachable. Check network/time")
+        );
+        assert_eq!(explain("Authentication failed"), None);
+    }
+}
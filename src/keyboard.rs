@@ -0,0 +1,61 @@
+//! "No keyboard detected" notice
+//!
+//! A greeter without a keyboard is a dead end, and a Bluetooth keyboard
+//! that went to sleep or a loose USB cable look the same as a hung machine.
+//! The kernel's input devices are re-read every few seconds, so the notice
+//! appears and goes away as keyboards are unplugged and plugged in.
+
+use std::fs;
+use std::time::Duration;
+
+/// How often the input devices are read again
+pub const POLL: Duration = Duration::from_secs(2);
+
+const DEVICES: &str = "/proc/bus/input/devices";
+
+/// Whether a keyboard is connected; `None` when the input devices can't be
+/// read (e.g. in a container), so nobody is told to connect one for nothing
+pub fn present() -> Option<bool> {
+    fs::read_to_string(DEVICES)
+        .ok()
+        .map(|devices| any_keyboard(&devices))
+}
+
+/// Some device in `/proc/bus/input/devices` has the keys of a keyboard.
+/// Like udev, that means Esc, the number row and Q to D: bits 1 to 31 of
+/// the `B: KEY=` bitmap, whose lowest word comes last. Power buttons and
+/// media remotes also have keys, but not those.
+fn any_keyboard(devices: &str) -> bool {
+    devices.lines().any(|line| {
+        line.strip_prefix("B: KEY=")
+            .and_then(|bitmap| bitmap.split_whitespace().last())
+            .and_then(|word| u64::from_str_radix(word, 16).ok())
+            .is_some_and(|word| word & 0xFFFF_FFFE == 0xFFFF_FFFE)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_any_keyboard() {
+        let power_button = "\
+I: Bus=0019 Vendor=0000 Product=0001 Version=0000
+N: Name=\"Power Button\"
+H: Handlers=kbd event0
+B: EV=3
+B: KEY=10000000000000 0
+";
+        let keyboard = "\
+I: Bus=0011 Vendor=0001 Product=0001 Version=ab83
+N: Name=\"AT Translated Set 2 keyboard\"
+H: Handlers=sysrq kbd leds event3
+B: EV=120013
+B: KEY=402000000 3803078f800d001 feffffdfffefffff fffffffffffffffe
+";
+        assert!(!any_keyboard(""));
+        assert!(!any_keyboard(power_button));
+        assert!(any_keyboard(&format!("{power_button}\n{keyboard}")));
+    }
+}
@@ -0,0 +1,74 @@
+//! grxxt - A brutalist greetd greeter
+//!
+//! The greeter as a library: [`greeter::GreeterCore`] runs the login flow
+//! without a screen, fed by an [`input::EventSource`] and drawn by
+//! [`ui::render`] into any ratatui backend. The `grxxt` binary puts it on
+//! the TTY; other shells can embed it the same way.
+
+#[cfg(feature = "input-activity")]
+mod activity;
+mod announce;
+pub mod app;
+mod audit;
+pub mod auth;
+mod avatar;
+mod bindings;
+mod burn_in;
+mod checks;
+pub mod cli;
+mod clock;
+mod compose;
+pub mod config;
+pub mod console;
+pub mod debug;
+mod demo;
+mod environment;
+mod effects;
+mod events;
+mod fade;
+mod focus;
+mod greetd;
+pub mod greeter;
+mod greeting;
+mod handoff;
+mod homed;
+#[cfg(feature = "hotplug")]
+mod hotplug;
+mod idle;
+pub mod input;
+mod kana;
+mod keyboard;
+mod kerberos;
+mod locale;
+mod metrics;
+mod notices;
+#[cfg(feature = "pam")]
+mod pam;
+pub mod policy;
+pub mod power;
+pub mod preview;
+mod resources;
+mod rfkill;
+mod scheduler;
+#[cfg(feature = "screenshot")]
+pub mod screenshot;
+mod scroll;
+mod sessions;
+mod state;
+mod status;
+mod sticky;
+mod submission;
+#[cfg(test)]
+mod testing;
+pub mod theme;
+mod typewriter;
+pub mod ui;
+mod undo;
+mod unlock;
+#[cfg(feature = "pam")]
+mod utmp;
+mod validate;
+
+/// Scripted greetd daemon for the fuzz targets
+#[cfg(fuzzing)]
+pub use greetd::fake;
@@ -0,0 +1,184 @@
+//! Locale of the text grxxt shows
+//!
+//! greetd starts the greeter with a bare environment, so the locale comes
+//! from the `locale` config key, then `LC_ALL`/`LC_TIME`/`LANG`, then
+//! /etc/locale.conf. Weekday and month names use chrono's locale data.
+
+use std::env;
+use std::fs;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::environment;
+
+/// Header date: weekday, day and month
+const DATE_FORMAT: &str = "%a %d %b";
+
+/// Header time, and with AM/PM for locales that use a 12-hour clock
+const TIME_FORMAT: &str = "%H:%M";
+const TIME_FORMAT_12H: &str = "%I:%M %p";
+
+/// Languages written right to left: Arabic, Sorani, Dhivehi, Persian,
+/// Hebrew, Pashto, Sindhi, Uyghur, Urdu and Yiddish
+const RTL_LANGUAGES: [&str; 10] = ["ar", "ckb", "dv", "fa", "he", "ps", "sd", "ug", "ur", "yi"];
+
+/// Variables that pick the time locale, strongest first
+const TIME_VARS: [&str; 3] = ["LC_ALL", "LC_TIME", "LANG"];
+
+/// A locale with data for it; the default is English (C/POSIX)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Locale(Option<chrono::Locale>);
+
+impl Locale {
+    /// Locale from the config, else the environment, else locale.conf
+    pub fn detect(configured: Option<&str>) -> Self {
+        if let Some(name) = configured {
+            return Self::parse(name);
+        }
+        let from_env = TIME_VARS
+            .iter()
+            .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty()));
+        let name = from_env.or_else(|| {
+            let content = fs::read_to_string(environment::LOCALE_CONF).ok()?;
+            let vars = environment::parse_env_file(&content);
+            TIME_VARS.iter().find_map(|var| {
+                vars.iter()
+                    .find(|(key, value)| key == var && !value.is_empty())
+                    .map(|(_, value)| value.clone())
+            })
+        });
+        name.map_or_else(Self::default, |name| Self::parse(&name))
+    }
+
+    /// A POSIX locale name such as `de_DE.UTF-8` or `sr_RS@latin`; unknown
+    /// names and C/POSIX fall back to English
+    pub fn parse(name: &str) -> Self {
+        let name = name.trim();
+        // The codeset doesn't matter, the modifier picks a variant
+        let (base, modifier) = name.split_once('@').unwrap_or((name, ""));
+        let base = base.split_once('.').map_or(base, |(base, _)| base);
+        if base == "C" || base == "POSIX" {
+            return Self::default();
+        }
+        let full = if modifier.is_empty() {
+            base.to_string()
+        } else {
+            format!("{base}@{modifier}")
+        };
+        Self(
+            chrono::Locale::try_from(full.as_str())
+                .or_else(|_| chrono::Locale::try_from(base))
+                .ok(),
+        )
+    }
+
+    /// Language code such as `de`; `en` for C/POSIX
+    pub fn language(self) -> String {
+        self.0.map_or_else(
+            || "en".to_string(),
+            |locale| {
+                let name = locale.to_string();
+                name.split(['_', '@']).next().unwrap_or_default().to_string()
+            },
+        )
+    }
+
+    /// The language is written right to left
+    pub fn right_to_left(self) -> bool {
+        RTL_LANGUAGES.contains(&self.language().as_str())
+    }
+
+    /// Date line of the header, e.g. `Mo 03 Mär` for German
+    pub fn date<Tz: TimeZone>(self, time: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        self.format(time, DATE_FORMAT)
+    }
+
+    /// Header time format: a 12-hour clock where the locale's own time
+    /// format uses one (`en_US`), else 24 hours
+    pub fn time_format(self) -> &'static str {
+        // The locale's time (%X) of an afternoon hour shows whether it goes
+        // past 12
+        let afternoon = DateTime::<Utc>::from_timestamp(13 * 3600, 0);
+        let (Some(locale), Some(afternoon)) = (self.0, afternoon) else {
+            return TIME_FORMAT;
+        };
+        let sample = afternoon.format_localized("%X", locale).to_string();
+        if sample.contains("13") {
+            TIME_FORMAT
+        } else {
+            TIME_FORMAT_12H
+        }
+    }
+
+    /// `time` formatted with a strftime string that is known to be valid,
+    /// with names in this locale
+    pub fn format<Tz: TimeZone>(self, time: &DateTime<Tz>, format: &str) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        self.0.map_or_else(
+            || time.format(format).to_string(),
+            |locale| time.format_localized(format, locale).to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_localized_date() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 4, 12, 0, 0).unwrap();
+        assert_eq!(Locale::default().date(&time), "Mon 04 Mar");
+        assert_eq!(Locale::parse("de_DE.UTF-8").date(&time), "Mo 04 Mär");
+        assert_eq!(Locale::parse("fr_FR").date(&time), "lun. 04 mars");
+    }
+
+    #[test]
+    fn test_time_format() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 4, 21, 5, 0).unwrap();
+        let format = |locale: Locale| locale.format(&time, locale.time_format());
+        assert_eq!(format(Locale::default()), "21:05");
+        assert_eq!(format(Locale::parse("de_DE")), "21:05");
+        assert_eq!(format(Locale::parse("en_GB.UTF-8")), "21:05");
+        assert_eq!(format(Locale::parse("en_US.UTF-8")), "09:05 PM");
+    }
+
+    #[test]
+    fn test_language() {
+        assert_eq!(Locale::default().language(), "en");
+        assert_eq!(Locale::parse("pt_BR.UTF-8").language(), "pt");
+        assert_eq!(Locale::parse("sr_RS@latin").language(), "sr");
+    }
+
+    #[test]
+    fn test_right_to_left() {
+        assert!(Locale::parse("ar_EG.UTF-8").right_to_left());
+        assert!(Locale::parse("he_IL").right_to_left());
+        assert!(!Locale::parse("de_DE").right_to_left());
+        assert!(!Locale::default().right_to_left());
+    }
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!(Locale::parse("C"), Locale::default());
+        assert_eq!(Locale::parse("POSIX"), Locale::default());
+        assert_eq!(Locale::parse("C.UTF-8"), Locale::default());
+        assert_eq!(Locale::parse("xx_YY.UTF-8"), Locale::default());
+        assert_eq!(
+            Locale::parse("sr_RS.UTF-8@latin"),
+            Locale(Some(chrono::Locale::sr_RS_latin))
+        );
+        // Unknown modifiers keep the base locale
+        assert_eq!(
+            Locale::parse("de_DE@nonsense"),
+            Locale(Some(chrono::Locale::de_DE))
+        );
+    }
+}
@@ -1,112 +1,219 @@
 //! grxxt - A brutalist greetd greeter
 //!
-//! A TUI-based greeter that runs directly on the TTY.
+//! A TUI-based greeter that runs directly on the TTY: sets the terminal up,
+//! draws the [`GreeterCore`] into it and puts it back afterwards.
 
-mod app;
-mod avatar;
-mod config;
-mod greetd;
-mod power;
-mod theme;
-mod ui;
 
-use std::io::stdout;
-use std::time::Duration;
+use std::io::{stdout, Write as _};
+use std::path::Path;
+use std::thread;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use ratatui::prelude::*;
 
-use app::App;
-use config::Config;
-use power::{reboot, shutdown, suspend};
+use grxxt::app::App;
+use grxxt::auth;
+use grxxt::cli::Args;
+use grxxt::config::{self, Config};
+use grxxt::greeter::{GreeterCore, Step};
+use grxxt::policy::Policy;
+use grxxt::power::Inhibitor;
+use grxxt::theme::Theme;
+use grxxt::{console, debug, input, preview, ui};
 
 fn main() -> Result<()> {
-    // Load configuration
-    let config = Config::load();
+    // Load configuration; command-line flags take precedence
+    let args = Args::parse();
+    if let Some(ref help) = args.help {
+        write!(stdout(), "{help}")?;
+        return Ok(());
+    }
+    if args.version {
+        writeln!(stdout(), "grxxt {}", env!("CARGO_PKG_VERSION"))?;
+        return Ok(());
+    }
+    if let Some(ref options) = args.preview {
+        return preview::run(options);
+    }
+    let config_path = args.config.as_deref().map_or_else(|| Config::path(), Path::new);
+    if args.check_config {
+        return check_config(config_path);
+    }
+    if args.debug && debug::enable() {
+        debug::log(format_args!("grxxt {} starting", env!("CARGO_PKG_VERSION")));
+    }
+    let mut config = Config::load_from(config_path);
+    debug::log(format_args!("config {}", config_path.display()));
+    if args.socket.is_some() {
+        config.socket = args.socket;
+    }
+    if args.session.is_some() {
+        config.session = args.session;
+    }
+    if let Some(ref path) = args.theme {
+        debug::log(format_args!("theme from {path}"));
+        config.theme = Config::load_from(Path::new(path)).theme;
+    }
+    config.remember |= args.remember;
+    let mut policy = Policy::load();
+    if args.demo {
+        // Pretend logins, and nothing that reaches outside the greeter. The
+        // demo backend also leaves the state files alone.
+        config.backend = config::Backend::Demo;
+        config.events_socket = None;
+        config.metrics_file = None;
+        config.unlock = None;
+        config.keymap = None;
+        config.checks.clear();
+        policy.power.clear();
+    }
 
-    // Setup terminal
+    // Setup terminal. Low-resource mode draws on the main screen directly
+    // instead of paying for an alternate screen buffer.
     terminal::enable_raw_mode()?;
-    stdout().execute(EnterAlternateScreen)?;
+    if !config.low_resource {
+        stdout().execute(EnterAlternateScreen)?;
+    }
     stdout().execute(cursor::Hide)?;
+    // For the scroll wheel; a VT without gpm ignores it
+    stdout().execute(EnableMouseCapture)?;
+
+    // Sticky keys need lone modifier presses, which only terminals with the
+    // kitty keyboard protocol report
+    let enhanced =
+        config.sticky_keys && terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced {
+        stdout().execute(PushKeyboardEnhancementFlags(
+            KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES,
+        ))?;
+    }
 
     let backend = CrosstermBackend::new(stdout());
     let mut terminal = Terminal::new(backend)?;
+    if config.low_resource {
+        terminal.clear()?;
+    }
 
-    // Run the application
-    let result = run(&mut terminal, &config);
+    // Run the application; the keymap is in place before the first key
+    let keymap = config.keymap.as_deref().is_some_and(console::set_keymap);
+    let inhibitor = Inhibitor::start(&config.power.inhibit);
+    let mut core = GreeterCore::new(App::new(&config, policy), input::Terminal);
+    if core.app.theme.large_text {
+        console::set_large_font(&core.app.theme.large_font);
+    }
+    let result = run(&mut terminal, &mut core);
+    if core.app.theme.large_text {
+        console::restore_font();
+    }
+    if keymap {
+        console::restore_keymap();
+    }
+    if let Some(inhibitor) = inhibitor {
+        inhibitor.release();
+    }
 
     // Restore terminal
+    if enhanced {
+        stdout().execute(PopKeyboardEnhancementFlags)?;
+    }
+    stdout().execute(DisableMouseCapture)?;
     stdout().execute(cursor::Show)?;
-    stdout().execute(LeaveAlternateScreen)?;
+    if config.low_resource {
+        terminal.clear()?;
+    } else {
+        stdout().execute(LeaveAlternateScreen)?;
+    }
     terminal::disable_raw_mode()?;
 
-    result
+    // Backends that own session startup take over the restored TTY
+    if result? {
+        auth::lock(&core.app.backend).handoff()?;
+    }
+
+    Ok(())
 }
 
-fn run<B: Backend>(terminal: &mut Terminal<B>, config: &Config) -> Result<()> {
-    let mut app = App::new(config);
+/// `--check-config`: print what's wrong with the config, failing if anything
+fn check_config(path: &Path) -> Result<()> {
+    let config = Config::check(path).map_err(|e| anyhow!("{}: {e}", path.display()))?;
+    let theme = Theme::from(&config.theme).contrast_warnings();
+    let clock = config.clock.format_warnings();
+    let warnings: Vec<String> = theme
+        .iter()
+        .map(|warning| format!("theme: {warning}"))
+        .chain(clock.iter().map(|warning| format!("clock: {warning}")))
+        .collect();
+    let mut out = stdout().lock();
+    for warning in &warnings {
+        writeln!(out, "{}: {warning}", path.display())?;
+    }
+    if !warnings.is_empty() {
+        bail!("{} problem(s) in {}", warnings.len(), path.display());
+    }
+    writeln!(out, "{}: ok", path.display())?;
+    Ok(())
+}
 
+/// Draw the greeter and do what its steps ask until it is done. Returns
+/// `true` once a login succeeded.
+fn run<B: Backend>(terminal: &mut Terminal<B>, core: &mut GreeterCore) -> Result<bool> {
     loop {
-        // Render
-        terminal.draw(|frame| ui::render(frame, &mut app))?;
-
-        // Handle events with 500ms timeout for clock updates
-        if event::poll(Duration::from_millis(500))? {
-            if let Event::Key(key) = event::read()? {
-                // Only handle key press events, not release
-                if key.kind != KeyEventKind::Press {
-                    continue;
-                }
-
-                #[allow(clippy::wildcard_enum_match_arm, reason = "KeyCode has 20+ variants from external crate")]
-                match key.code {
-                    // Power controls
-                    KeyCode::F(1) => shutdown(),
-                    KeyCode::F(2) => reboot(),
-                    KeyCode::F(3) => suspend(),
-
-                    // Quit (development only)
-                    KeyCode::Esc => app.quit(),
-
-                    // Navigation
-                    KeyCode::Tab => {
-                        if key.modifiers.contains(KeyModifiers::SHIFT) {
-                            app.prev_field();
-                        } else {
-                            app.next_field();
-                        }
-                    }
-                    KeyCode::BackTab => app.prev_field(),
-
-                    // Input
-                    KeyCode::Char(c) => app.input_char(c),
-                    KeyCode::Backspace => app.backspace(),
-
-                    // Submit
-                    KeyCode::Enter => {
-                        if app.submit() {
-                            terminal.draw(|frame| ui::render(frame, &mut app))?;
-                            if app.authenticate() {
-                                break;
-                            }
-                        }
-                    }
-
-                    _ => {}
-                }
+        let frame = terminal.draw(|frame| ui::render(frame, &mut core.app))?;
+        if screenshot(frame.buffer, &mut core.app) {
+            continue;
+        }
+        core.drawn();
+        match core.step()? {
+            Step::Redraw => {}
+            Step::Repaint => terminal.clear()?,
+            Step::LoggedIn => {
+                welcome(terminal, &mut core.app)?;
+                return Ok(true);
             }
+            Step::Quit => return Ok(false),
         }
+    }
+}
 
-        if app.should_quit {
-            break;
+/// Save the frame just drawn when F12 asked for it in demo mode. Returns
+/// whether it failed, to draw the error at once.
+#[cfg(feature = "screenshot")]
+fn screenshot(buffer: &Buffer, app: &mut App) -> bool {
+    if !app.screenshot.as_mut().is_some_and(std::mem::take) {
+        return false;
+    }
+    match grxxt::screenshot::save(buffer, &app.theme) {
+        Ok(path) => {
+            debug::log(format_args!("screenshot {}", path.display()));
+            false
+        }
+        Err(e) => {
+            app.error = Some(format!("Can't save the screenshot: {e}"));
+            true
         }
     }
+}
+
+#[cfg(not(feature = "screenshot"))]
+const fn screenshot(_buffer: &Buffer, _app: &mut App) -> bool {
+    false
+}
 
+/// Confirm the login before the screen is handed over
+fn welcome<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    let pause = app.success_duration();
+    if !pause.is_zero() {
+        terminal.draw(|frame| ui::render(frame, app))?;
+        thread::sleep(pause);
+    }
     Ok(())
 }
@@ -0,0 +1,130 @@
+//! Prometheus textfile metrics
+//!
+//! With `metrics_file` set (e.g. inside `node_exporter`'s textfile collector
+//! directory) grxxt rewrites the file after every login attempt. greetd
+//! restarts the greeter for each login, so counters are carried over from
+//! the previous file.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+const ATTEMPTS: &str = "grxxt_auth_attempts_total";
+const FAILURES: &str = "grxxt_auth_failures_total";
+const TIME_TO_LOGIN: &str = "grxxt_time_to_login_seconds";
+const UPTIME: &str = "grxxt_greeter_uptime_seconds";
+
+/// Login counters and timings for one greeter
+pub struct Metrics {
+    path: PathBuf,
+    started: Instant,
+    attempts: u64,
+    failures: u64,
+    /// Greeter start to successful login, for the last login
+    time_to_login: Option<f64>,
+}
+
+impl Metrics {
+    /// Metrics written to `path`, continuing the counters found there
+    pub fn open(path: &str) -> Self {
+        let previous = fs::read_to_string(path).unwrap_or_default();
+        Self {
+            path: PathBuf::from(path),
+            started: Instant::now(),
+            attempts: counter(&previous, ATTEMPTS),
+            failures: counter(&previous, FAILURES),
+            time_to_login: None,
+        }
+    }
+
+    pub fn record_attempt(&mut self) {
+        self.attempts += 1;
+        self.write();
+    }
+
+    pub fn record_failure(&mut self) {
+        self.failures += 1;
+        self.write();
+    }
+
+    pub fn record_login(&mut self) {
+        self.time_to_login = Some(self.started.elapsed().as_secs_f64());
+        self.write();
+    }
+
+    /// Replace the file atomically so the collector never reads half of it.
+    /// Failures are ignored — metrics never get in the way of logging in.
+    fn write(&self) {
+        let tmp = self.path.with_extension("prom.tmp");
+        if fs::write(&tmp, self.render()).is_ok() {
+            fs::rename(&tmp, &self.path).ok();
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = format!(
+            "# HELP {ATTEMPTS} Login attempts submitted at the greeter.\n\
+             # TYPE {ATTEMPTS} counter\n\
+             {ATTEMPTS} {}\n\
+             # HELP {FAILURES} Login attempts rejected by the auth backend.\n\
+             # TYPE {FAILURES} counter\n\
+             {FAILURES} {}\n\
+             # HELP {UPTIME} Time the current greeter has been shown.\n\
+             # TYPE {UPTIME} gauge\n\
+             {UPTIME} {:.3}\n",
+            self.attempts,
+            self.failures,
+            self.started.elapsed().as_secs_f64(),
+        );
+        if let Some(seconds) = self.time_to_login {
+            write!(
+                out,
+                "# HELP {TIME_TO_LOGIN} Greeter start to successful login.\n\
+                 # TYPE {TIME_TO_LOGIN} gauge\n\
+                 {TIME_TO_LOGIN} {seconds:.3}\n"
+            )
+            .ok();
+        }
+        out
+    }
+}
+
+/// Value of counter `name` in a textfile, 0 when missing
+fn counter(text: &str, name: &str) -> u64 {
+    text.lines()
+        .filter_map(|line| line.split_once(' '))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_carry_over() {
+        let path = std::env::temp_dir().join(format!("grxxt-metrics-{}.prom", std::process::id()));
+        let path_str = path.to_string_lossy();
+
+        let mut metrics = Metrics::open(&path_str);
+        metrics.record_attempt();
+        metrics.record_failure();
+        metrics.record_attempt();
+        metrics.record_login();
+
+        let text = fs::read_to_string(&path).unwrap_or_default();
+        assert_eq!(counter(&text, ATTEMPTS), 2);
+        assert_eq!(counter(&text, FAILURES), 1);
+        assert!(text.contains(TIME_TO_LOGIN));
+
+        let mut next = Metrics::open(&path_str);
+        next.record_attempt();
+        let text = fs::read_to_string(&path).unwrap_or_default();
+        assert_eq!(counter(&text, ATTEMPTS), 3);
+        assert!(!text.contains(TIME_TO_LOGIN));
+
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,119 @@
+//! Auth stack notices taking turns
+//!
+//! PAM and Kerberos can send several informational messages at once
+//! (password expiry, last login, ticket status) and the message area holds
+//! only a few rows. With `notice_rotate_secs` they are shown one at a time
+//! with their position, e.g. `Password expires in 3 days (1/3)`, so none is
+//! cut off on a screen nobody scrolls.
+
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+pub struct Rotation {
+    /// How long each notice is shown; `None` shows them all at once
+    interval: Option<Duration>,
+    /// The notices taking turns; a different list starts over
+    notices: Vec<String>,
+    /// The one on screen
+    index: usize,
+    /// When the next one is due
+    due: Option<Instant>,
+}
+
+impl Rotation {
+    pub fn new(interval: Option<Duration>) -> Self {
+        Self {
+            interval,
+            ..Self::default()
+        }
+    }
+
+    /// The screen shows `notices` since `now`. Returns how long until the
+    /// next one when they just started taking turns.
+    pub fn drawn(&mut self, notices: &[String], now: Instant) -> Option<Duration> {
+        let Some(interval) = self.interval.filter(|_| notices.len() > 1) else {
+            self.notices.clear();
+            self.due = None;
+            return None;
+        };
+        if self.notices == notices {
+            return None;
+        }
+        self.notices = notices.to_vec();
+        self.index = 0;
+        self.due = Some(now + interval);
+        Some(interval)
+    }
+
+    /// Move on to the next notice when it is due. Returns how long until
+    /// the one after; `None` from a timer of a list no longer on screen.
+    pub fn advance(&mut self, now: Instant) -> Option<Duration> {
+        let interval = self.interval?;
+        if self.notices.len() < 2 || self.due.is_none_or(|due| now < due) {
+            return None;
+        }
+        self.index = (self.index + 1) % self.notices.len();
+        self.due = Some(now + interval);
+        Some(interval)
+    }
+
+    /// `notices` as shown: the current one with its position while they
+    /// take turns, otherwise all of them
+    pub fn show<'a>(&self, notices: &'a [String]) -> Vec<Cow<'a, str>> {
+        match notices.get(self.index) {
+            Some(notice) if self.notices.len() > 1 && self.notices == notices => {
+                let position = format!("({}/{})", self.index + 1, notices.len());
+                vec![Cow::Owned(format!("{notice} {position}"))]
+            }
+            Some(_) | None => notices
+                .iter()
+                .map(|notice| Cow::Borrowed(notice.as_str()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    #[test]
+    fn test_rotation() {
+        let time = Timeline::new();
+        let notices = ["first".to_string(), "second".to_string()];
+        let mut rotation = Rotation::new(Some(Duration::from_secs(5)));
+
+        assert_eq!(
+            rotation.drawn(&notices, time.secs(0)),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(rotation.drawn(&notices, time.secs(1)), None);
+        assert_eq!(rotation.show(&notices), ["first (1/2)"]);
+        // Not due yet
+        assert_eq!(rotation.advance(time.secs(4)), None);
+        assert_eq!(rotation.advance(time.secs(5)), Some(Duration::from_secs(5)));
+        assert_eq!(rotation.show(&notices), ["second (2/2)"]);
+        assert_eq!(
+            rotation.advance(time.secs(10)),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(rotation.show(&notices), ["first (1/2)"]);
+
+        // A single notice doesn't turn
+        let single = ["only".to_string()];
+        assert_eq!(rotation.drawn(&single, time.secs(11)), None);
+        assert_eq!(rotation.advance(time.secs(15)), None);
+        assert_eq!(rotation.show(&single), ["only"]);
+    }
+
+    #[test]
+    fn test_off_shows_all() {
+        let notices = ["first".to_string(), "second".to_string()];
+        let mut rotation = Rotation::new(None);
+        assert_eq!(rotation.drawn(&notices, Instant::now()), None);
+        assert_eq!(rotation.show(&notices), ["first", "second"]);
+    }
+}
@@ -0,0 +1,345 @@
+//! Direct PAM backend (experimental)
+//!
+//! lemurs-style login without greetd: grxxt (running as root) authenticates
+//! through PAM, opens the session, and spawns the session command as the user
+//! once the terminal has been restored.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, ExitStatus};
+use std::ptr;
+
+use nix::unistd::{getgrouplist, setgid, setgroups, setuid, User};
+
+use crate::auth::{AuthBackend, AuthError, Outcome};
+use crate::state;
+use crate::utmp;
+
+/// PAM service used unless `pam_service` is set (`/etc/pam.d/login` exists
+/// everywhere)
+const DEFAULT_SERVICE: &CStr = c"login";
+
+const PAM_SUCCESS: c_int = 0;
+const PAM_PROMPT_ECHO_OFF: c_int = 1;
+const PAM_PROMPT_ECHO_ON: c_int = 2;
+const PAM_ERROR_MSG: c_int = 3;
+const PAM_TEXT_INFO: c_int = 4;
+const PAM_BUF_ERR: c_int = 5;
+const PAM_CONV_ERR: c_int = 19;
+const PAM_ESTABLISH_CRED: c_int = 0x2;
+const PAM_DELETE_CRED: c_int = 0x4;
+
+#[repr(C)]
+struct PamHandle {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct PamMessage {
+    msg_style: c_int,
+    msg: *const c_char,
+}
+
+#[repr(C)]
+struct PamResponse {
+    resp: *mut c_char,
+    resp_retcode: c_int,
+}
+
+type ConvFn =
+    extern "C" fn(c_int, *mut *const PamMessage, *mut *mut PamResponse, *mut c_void) -> c_int;
+
+#[repr(C)]
+struct PamConv {
+    conv: Option<ConvFn>,
+    appdata_ptr: *mut c_void,
+}
+
+#[link(name = "pam")]
+extern "C" {
+    fn pam_start(
+        service: *const c_char,
+        user: *const c_char,
+        conv: *const PamConv,
+        handle: *mut *mut PamHandle,
+    ) -> c_int;
+    fn pam_authenticate(handle: *mut PamHandle, flags: c_int) -> c_int;
+    fn pam_acct_mgmt(handle: *mut PamHandle, flags: c_int) -> c_int;
+    fn pam_setcred(handle: *mut PamHandle, flags: c_int) -> c_int;
+    fn pam_open_session(handle: *mut PamHandle, flags: c_int) -> c_int;
+    fn pam_close_session(handle: *mut PamHandle, flags: c_int) -> c_int;
+    fn pam_end(handle: *mut PamHandle, status: c_int) -> c_int;
+    fn pam_strerror(handle: *mut PamHandle, errnum: c_int) -> *const c_char;
+    fn pam_getenvlist(handle: *mut PamHandle) -> *mut *mut c_char;
+}
+
+/// Answers handed to the PAM conversation callback
+struct Credentials {
+    username: CString,
+    password: CString,
+    /// Info and error texts PAM modules showed (`pam_krb5`/sssd ticket status)
+    messages: RefCell<Vec<String>>,
+}
+
+/// Conversation callback: username for visible prompts, password for secret
+/// ones; info and error texts are collected for the UI
+extern "C" fn converse(
+    num_msg: c_int,
+    msg: *mut *const PamMessage,
+    resp: *mut *mut PamResponse,
+    appdata: *mut c_void,
+) -> c_int {
+    let Ok(count) = usize::try_from(num_msg) else {
+        return PAM_CONV_ERR;
+    };
+
+    // SAFETY: appdata is the `Credentials` box owned by the live `Session`
+    let creds = unsafe { &*appdata.cast::<Credentials>() };
+
+    // SAFETY: PAM frees the reply array and strings with free(3)
+    let replies = unsafe { libc::calloc(count, size_of::<PamResponse>()) }.cast::<PamResponse>();
+    if replies.is_null() {
+        return PAM_BUF_ERR;
+    }
+
+    for i in 0..count {
+        // SAFETY: PAM passes `num_msg` valid message pointers (Linux-PAM layout)
+        let message = unsafe { &**msg.add(i) };
+        let answer = match message.msg_style {
+            PAM_PROMPT_ECHO_OFF => &creds.password,
+            PAM_PROMPT_ECHO_ON => &creds.username,
+            PAM_ERROR_MSG | PAM_TEXT_INFO if !message.msg.is_null() => {
+                // SAFETY: PAM message texts are NUL-terminated strings
+                let text = unsafe { CStr::from_ptr(message.msg) };
+                creds
+                    .messages
+                    .borrow_mut()
+                    .push(text.to_string_lossy().into_owned());
+                continue;
+            }
+            _ => continue,
+        };
+        // SAFETY: `replies` holds `count` zeroed entries
+        unsafe { (*replies.add(i)).resp = libc::strdup(answer.as_ptr()) };
+    }
+
+    // SAFETY: `resp` is a valid out-pointer supplied by PAM
+    unsafe { *resp = replies };
+    PAM_SUCCESS
+}
+
+/// An authenticated PAM transaction; closed and ended on drop
+struct Session {
+    handle: *mut PamHandle,
+    opened: bool,
+    username: String,
+    command: String,
+    extra_env: Vec<String>,
+    // Referenced by the conversation callback for the lifetime of `handle`
+    creds: Box<Credentials>,
+}
+
+// SAFETY: PAM handles aren't tied to the thread that opened them, and the
+// backend's mutex keeps them to one thread at a time
+unsafe impl Send for Session {}
+
+impl Session {
+    fn check(&self, status: c_int) -> Result<(), AuthError> {
+        if status == PAM_SUCCESS {
+            return Ok(());
+        }
+        // A module's own message ("Clock skew too great") beats the generic one
+        if let Some(msg) = self.creds.messages.borrow().last() {
+            return Err(AuthError::AuthFailed(msg.clone()));
+        }
+        // SAFETY: pam_strerror returns a static string for any status
+        let msg = unsafe { CStr::from_ptr(pam_strerror(self.handle, status)) };
+        Err(AuthError::AuthFailed(msg.to_string_lossy().into_owned()))
+    }
+
+    /// Environment exported by PAM modules (`pam_env`, `pam_systemd`, ...)
+    fn env(&self) -> Vec<(String, String)> {
+        // SAFETY: the list and its strings are malloc'd and owned by us
+        let list = unsafe { pam_getenvlist(self.handle) };
+        if list.is_null() {
+            return Vec::new();
+        }
+
+        let mut vars = Vec::new();
+        let mut i = 0;
+        loop {
+            // SAFETY: the list is NULL-terminated
+            let entry = unsafe { *list.add(i) };
+            if entry.is_null() {
+                break;
+            }
+            // SAFETY: entry is a valid C string until freed below
+            let pair = unsafe { CStr::from_ptr(entry) }
+                .to_string_lossy()
+                .into_owned();
+            if let Some((key, value)) = pair.split_once('=') {
+                vars.push((key.to_string(), value.to_string()));
+            }
+            // SAFETY: entry was allocated by PAM with malloc(3)
+            unsafe { libc::free(entry.cast()) };
+            i += 1;
+        }
+        // SAFETY: the list itself was allocated with malloc(3)
+        unsafe { libc::free(list.cast()) };
+        vars
+    }
+
+    /// Spawn the session command as the user through their login shell and wait for it
+    fn run(&self) -> Result<(), AuthError> {
+        let user = User::from_name(&self.username)
+            .ok()
+            .flatten()
+            .ok_or_else(|| AuthError::AuthFailed(format!("Unknown user {}", self.username)))?;
+        let name = CString::new(self.username.as_str())
+            .map_err(|e| AuthError::ProtocolError(e.to_string()))?;
+        let (uid, gid) = (user.uid, user.gid);
+        // Looked up before the fork: reading the group database isn't
+        // async-signal-safe
+        let groups =
+            getgrouplist(&name, gid).map_err(|e| AuthError::ProtocolError(e.to_string()))?;
+
+        let mut cmd = Command::new(&user.shell);
+        cmd.arg("-l")
+            .arg("-c")
+            .arg(format!("exec {}", self.command))
+            .current_dir(&user.dir)
+            .env("HOME", &user.dir)
+            .env("SHELL", &user.shell)
+            .env("USER", &self.username)
+            .env("LOGNAME", &self.username)
+            .envs(self.extra_env.iter().filter_map(|var| var.split_once('=')))
+            .envs(self.env());
+
+        // SAFETY: only async-signal-safe libc calls run between fork and exec
+        unsafe {
+            cmd.pre_exec(move || {
+                setgroups(&groups)?;
+                setgid(gid)?;
+                setuid(uid)?;
+                Ok(())
+            });
+        }
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| AuthError::ProtocolError(e.to_string()))?;
+
+        // Account the session like agetty/login do, so `who` and `last` see it
+        let record = utmp::Record::login(&self.username, child.id());
+        let status = child.wait();
+        record.logout();
+        // Only an error or a signal can count towards a crash loop
+        if status.as_ref().is_ok_and(ExitStatus::success) {
+            state::clear_launch();
+        }
+        status
+            .map(drop)
+            .map_err(|e| AuthError::ProtocolError(e.to_string()))
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // SAFETY: handle came from a successful pam_start and is ended once
+        unsafe {
+            if self.opened {
+                pam_close_session(self.handle, 0);
+                pam_setcred(self.handle, PAM_DELETE_CRED);
+            }
+            pam_end(self.handle, PAM_SUCCESS);
+        }
+    }
+}
+
+/// Direct PAM backend; holds the authenticated transaction until handoff
+pub struct Pam {
+    service: CString,
+    session: Option<Session>,
+}
+
+impl Pam {
+    /// Authenticate through `service` (`/etc/pam.d/<service>`), or `login`
+    pub fn new(service: Option<&str>) -> Self {
+        let service = service
+            .and_then(|service| CString::new(service).ok())
+            .unwrap_or_else(|| DEFAULT_SERVICE.into());
+        Self {
+            service,
+            session: None,
+        }
+    }
+}
+
+impl AuthBackend for Pam {
+    fn authenticate(&mut self, username: &str, password: &str) -> Result<Outcome, AuthError> {
+        let creds = Box::new(Credentials {
+            username: CString::new(username).map_err(|e| AuthError::AuthFailed(e.to_string()))?,
+            password: CString::new(password).map_err(|e| AuthError::AuthFailed(e.to_string()))?,
+            messages: RefCell::new(Vec::new()),
+        });
+        let conv = PamConv {
+            conv: Some(converse),
+            appdata_ptr: ptr::from_ref(&*creds).cast_mut().cast(),
+        };
+
+        let mut handle = ptr::null_mut();
+        // SAFETY: all pointers are valid; PAM copies `conv` during pam_start
+        let status = unsafe {
+            pam_start(
+                self.service.as_ptr(),
+                creds.username.as_ptr(),
+                &raw const conv,
+                &raw mut handle,
+            )
+        };
+        if status != PAM_SUCCESS || handle.is_null() {
+            return Err(AuthError::ConnectionFailed("pam_start failed".into()));
+        }
+
+        let mut session = Session {
+            handle,
+            opened: false,
+            username: username.to_string(),
+            command: String::new(),
+            extra_env: Vec::new(),
+            creds,
+        };
+
+        // SAFETY: handle is valid for the lifetime of `session`
+        unsafe {
+            session.check(pam_authenticate(handle, 0))?;
+            session.check(pam_acct_mgmt(handle, 0))?;
+            session.check(pam_setcred(handle, PAM_ESTABLISH_CRED))?;
+            session.check(pam_open_session(handle, 0))?;
+        }
+        session.opened = true;
+
+        let messages = session.creds.messages.take();
+        self.session = Some(session);
+        Ok(Outcome::Authenticated(messages))
+    }
+
+    fn start(&mut self, session_cmd: &str, env: &[String]) -> Result<(), AuthError> {
+        let session = self
+            .session
+            .as_mut()
+            .ok_or_else(|| AuthError::ProtocolError("Not authenticated".into()))?;
+        session.command = session_cmd.to_string();
+        session.extra_env = env.to_vec();
+        Ok(())
+    }
+
+    fn cancel(&mut self) {
+        self.session = None;
+    }
+
+    fn handoff(&mut self) -> Result<(), AuthError> {
+        self.session.take().map_or(Ok(()), |session| session.run())
+    }
+}
@@ -0,0 +1,140 @@
+//! Administrator policy for locking the greeter down
+//!
+//! Reads /etc/greetd/grxxt-policy.toml. Unlike the config, which covers how
+//! the greeter looks and behaves, the policy limits what users may do from
+//! it, so fleet management can ship it without touching anyone's theme.
+
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::power::Action;
+
+const POLICY_PATH: &str = "/etc/greetd/grxxt-policy.toml";
+
+/// What the greeter offers; a missing file allows everything
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(clippy::struct_excessive_bools, reason = "independent permissions of the policy file")]
+pub struct Policy {
+    /// Power actions with a key and a header button
+    #[serde(default = "default_power")]
+    pub power: Vec<Action>,
+
+    /// The quit key (Esc) exits the greeter
+    #[serde(default = "default_allowed")]
+    pub quit: bool,
+
+    /// Sessions other than the default can be picked (F4)
+    #[serde(default = "default_allowed")]
+    pub session_picker: bool,
+
+    /// A crash-looping session switches to `[fallback] session`
+    #[serde(default = "default_allowed")]
+    pub fallback: bool,
+
+    /// A username that doesn't exist is told apart from a wrong password,
+    /// where the auth stack says which it was
+    #[serde(default = "default_allowed")]
+    pub reveal_unknown_users: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            power: default_power(),
+            quit: true,
+            session_picker: true,
+            fallback: true,
+            reveal_unknown_users: true,
+        }
+    }
+}
+
+fn default_power() -> Vec<Action> {
+    vec![
+        Action::Shutdown,
+        Action::Reboot,
+        Action::Suspend,
+        Action::SuspendThenHibernate,
+    ]
+}
+
+const fn default_allowed() -> bool {
+    true
+}
+
+impl Policy {
+    /// Nothing beyond logging in
+    #[must_use]
+    pub const fn locked() -> Self {
+        Self {
+            power: Vec::new(),
+            quit: false,
+            session_picker: false,
+            fallback: false,
+            reveal_unknown_users: false,
+        }
+    }
+
+    /// Load the system policy
+    #[must_use]
+    pub fn load() -> Self {
+        Self::load_from(Path::new(POLICY_PATH))
+    }
+
+    /// Load the policy from `path`; a missing file allows everything
+    #[must_use]
+    pub fn load_from(path: &Path) -> Self {
+        fs::read_to_string(path).map_or_else(|_| Self::default(), |content| Self::parse(&content))
+    }
+
+    /// Parse policy text. Unlike the config a broken policy locks everything:
+    /// a typo must not quietly hand out what the administrator took away.
+    #[must_use]
+    pub fn parse(content: &str) -> Self {
+        toml::from_str(content).unwrap_or_else(|_| Self::locked())
+    }
+
+    #[must_use]
+    pub fn allows_power(&self, action: Action) -> bool {
+        self.power.contains(&action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_policy_allows_everything() {
+        let policy = Policy::load_from(Path::new("/nonexistent/grxxt-policy.toml"));
+        assert_eq!(policy, Policy::default());
+        assert!(policy.allows_power(Action::Suspend));
+        assert!(policy.quit);
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        let policy = Policy::parse(
+            r#"
+power = ["reboot", "suspend-then-hibernate"]
+quit = false
+"#,
+        );
+        assert!(policy.allows_power(Action::Reboot));
+        assert!(policy.allows_power(Action::SuspendThenHibernate));
+        assert!(!policy.allows_power(Action::Shutdown));
+        assert!(!policy.quit);
+        // Unset entries stay allowed
+        assert!(policy.session_picker);
+        assert!(policy.fallback);
+        assert!(policy.reveal_unknown_users);
+    }
+
+    #[test]
+    fn test_broken_policy_locks() {
+        assert_eq!(Policy::parse("power = \"all\""), Policy::locked());
+        assert_eq!(Policy::parse("quitt = false"), Policy::locked());
+    }
+}
@@ -1,15 +1,449 @@
 //! Power management commands (shutdown, reboot, suspend)
+//!
+//! Actions run `systemctl` in the background, after an optional `sync` and,
+//! for a reboot into another system, `bootctl set-oneshot` or `grub-reboot`;
+//! the UI keeps drawing and polls [`Pending`] to learn how the action went.
+//! An [`Inhibitor`] can keep logind from acting on the lid switch and
+//! similar keys while the greeter is up.
 
-use std::process::Command;
+use std::borrow::Cow;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
-pub fn shutdown() {
-    Command::new("systemctl").arg("poweroff").spawn().ok();
+use serde::Deserialize;
+
+use crate::config::{BootEntry, Bootloader, PowerConfig};
+
+/// How often a running action is checked on
+pub const POLL: Duration = Duration::from_millis(500);
+
+/// Wall clock running ahead of the monotonic clock by more than this
+/// between two polls means the machine was asleep
+const SLEEP_THRESHOLD: Duration = Duration::from_secs(2);
+
+/// Give up waiting for a suspend that never showed up as a clock jump
+const SUSPEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a key is held to confirm its action
+const HOLD_DURATION: Duration = Duration::from_secs(2);
+
+/// Longer than keyboards wait before auto-repeating (250-660 ms); no repeat
+/// for this long means the key was let go
+const REPEAT_GAP: Duration = Duration::from_millis(750);
+
+/// How often the hold progress bar is redrawn
+pub const HOLD_TICK: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Shutdown,
+    Reboot,
+    Suspend,
+    #[serde(rename = "suspend-then-hibernate")]
+    SuspendThenHibernate,
+}
+
+impl Action {
+    /// `systemctl` subcommand
+    #[must_use]
+    pub const fn verb(self) -> &'static str {
+        match self {
+            Self::Shutdown => "poweroff",
+            Self::Reboot => "reboot",
+            Self::Suspend => "suspend",
+            Self::SuspendThenHibernate => "suspend-then-hibernate",
+        }
+    }
+
+    /// The machine goes to sleep and comes back to the greeter
+    #[must_use]
+    pub const fn sleeps(self) -> bool {
+        matches!(self, Self::Suspend | Self::SuspendThenHibernate)
+    }
+
+    /// Question asked before the action when it needs confirming
+    #[must_use]
+    pub const fn question(self) -> &'static str {
+        match self {
+            Self::Shutdown => "shut down?",
+            Self::Reboot => "reboot?",
+            Self::Suspend => "suspend?",
+            Self::SuspendThenHibernate => "suspend, then hibernate?",
+        }
+    }
+
+    /// Shown while the key is held to confirm the action
+    #[must_use]
+    pub const fn hold_prompt(self) -> &'static str {
+        match self {
+            Self::Shutdown => "hold to shut down",
+            Self::Reboot => "hold to reboot",
+            Self::Suspend => "hold to suspend",
+            Self::SuspendThenHibernate => "hold to suspend, then hibernate",
+        }
+    }
+
+    /// Shown while the action is in progress
+    #[must_use]
+    pub const fn message(self) -> &'static str {
+        match self {
+            Self::Shutdown => "shutting down...",
+            Self::Reboot => "rebooting...",
+            Self::Suspend | Self::SuspendThenHibernate => "suspending...",
+        }
+    }
 }
 
-pub fn reboot() {
-    Command::new("systemctl").arg("reboot").spawn().ok();
+/// Where a running action stands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum State {
+    Running,
+    /// Back from suspend; whatever is on screen or queued is stale
+    Resumed,
+    /// `systemctl` refused (e.g. polkit denied the request), with its
+    /// reason when it gave one
+    Failed(Option<String>),
 }
 
-pub fn suspend() {
-    Command::new("systemctl").arg("suspend").spawn().ok();
+/// A power key held down to confirm its action. Terminals don't report
+/// releases, so the key counts as held while its auto-repeat keeps coming.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hold {
+    pub action: Action,
+    started: Instant,
+    last: Instant,
+}
+
+/// How a hold is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoldState {
+    Holding,
+    Released,
+    Confirmed,
+}
+
+impl Hold {
+    #[must_use]
+    pub const fn new(action: Action, now: Instant) -> Self {
+        Self {
+            action,
+            started: now,
+            last: now,
+        }
+    }
+
+    /// The key repeated
+    pub const fn repeat(&mut self, now: Instant) {
+        self.last = now;
+    }
+
+    #[must_use]
+    pub fn state(&self, now: Instant) -> HoldState {
+        if now.duration_since(self.last) > REPEAT_GAP {
+            HoldState::Released
+        } else if now.duration_since(self.started) >= HOLD_DURATION {
+            HoldState::Confirmed
+        } else {
+            HoldState::Holding
+        }
+    }
+
+    /// Share of the hold done, from 0 to 1
+    #[must_use]
+    pub fn progress(&self, now: Instant) -> f64 {
+        (now.duration_since(self.started).as_secs_f64() / HOLD_DURATION.as_secs_f64()).min(1.0)
+    }
+}
+
+/// One command of a power action; they run one after the other
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    /// Flush filesystems first
+    Sync,
+    /// Make the next boot start another entry
+    Oneshot(BootEntry),
+    /// Hand the action to systemd
+    Systemctl,
+}
+
+/// A power action handed to systemd
+pub struct Pending {
+    pub action: Action,
+    no_block: bool,
+    /// Entry a reboot starts instead of the default
+    target: Option<BootEntry>,
+    /// Command running now
+    step: Step,
+    /// Steps left, last one first
+    next: Vec<Step>,
+    child: Child,
+    started: Instant,
+    /// Last (monotonic, wall) clock sample, to notice time spent asleep
+    sample: (Instant, SystemTime),
+}
+
+/// Ask systemd for `action` without waiting for it, flushing filesystems
+/// first when shutting down or rebooting with `sync` set and pointing the
+/// bootloader at `target` before a reboot
+#[must_use]
+pub fn start(action: Action, options: &PowerConfig, target: Option<&BootEntry>) -> Option<Pending> {
+    let target = target.filter(|_| action == Action::Reboot).cloned();
+    let mut next = vec![Step::Systemctl];
+    if let Some(ref entry) = target {
+        next.push(Step::Oneshot(entry.clone()));
+    }
+    if options.sync && !action.sleeps() {
+        next.push(Step::Sync);
+    }
+    let step = next.pop()?;
+    let child = spawn(&step, action, options.no_block)?;
+    let now = Instant::now();
+    Some(Pending {
+        action,
+        no_block: options.no_block,
+        target,
+        step,
+        next,
+        child,
+        started: now,
+        sample: (now, SystemTime::now()),
+    })
+}
+
+/// Output is kept off the screen; stderr is read back if a step fails
+fn spawn(step: &Step, action: Action, no_block: bool) -> Option<Child> {
+    let mut command = match *step {
+        Step::Sync => Command::new("sync"),
+        Step::Oneshot(ref entry) => match entry.loader {
+            Bootloader::SystemdBoot => {
+                let mut command = Command::new("bootctl");
+                command.args(["set-oneshot", &entry.entry]);
+                command
+            }
+            Bootloader::Grub => {
+                let mut command = Command::new("grub-reboot");
+                command.arg(&entry.entry);
+                command
+            }
+        },
+        Step::Systemctl => {
+            let mut command = Command::new("systemctl");
+            if no_block {
+                command.arg("--no-block");
+            }
+            command.arg(action.verb());
+            command
+        }
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()
+}
+
+impl Pending {
+    /// Shown while the action is in progress; a slow sync shows how long
+    /// it has been going, so a stuck disk doesn't look like a hung greeter
+    #[must_use]
+    pub fn message(&self) -> Cow<'static, str> {
+        match (&self.step, &self.target) {
+            (Step::Sync, _) => Cow::Owned(format!(
+                "syncing disks... {}s",
+                self.started.elapsed().as_secs()
+            )),
+            (Step::Oneshot(_) | Step::Systemctl, Some(entry)) => {
+                Cow::Owned(format!("rebooting into {}...", entry.label))
+            }
+            (Step::Oneshot(_) | Step::Systemctl, None) => Cow::Borrowed(self.action.message()),
+        }
+    }
+
+    /// Check on the action; cheap enough to call on every tick
+    pub fn poll(&mut self) -> State {
+        if self.step != Step::Systemctl {
+            return self.poll_step();
+        }
+        let now = (Instant::now(), SystemTime::now());
+        let mono = now.0.duration_since(self.sample.0);
+        let wall = now.1.duration_since(self.sample.1).unwrap_or_default();
+        self.sample = now;
+
+        match self.child.try_wait() {
+            Ok(Some(status)) if !status.success() => return State::Failed(self.reason()),
+            Err(_) => return State::Failed(None),
+            Ok(_) => {}
+        }
+        if !self.action.sleeps() {
+            return State::Running;
+        }
+        if slept(mono, wall) || self.started.elapsed() > SUSPEND_TIMEOUT {
+            State::Resumed
+        } else {
+            State::Running
+        }
+    }
+
+    /// Move on once the step before systemctl is done. A failed sync
+    /// doesn't hold the action back, the kernel syncs on the way down; a
+    /// boot entry that couldn't be set does, rather than reboot into the
+    /// wrong system.
+    fn poll_step(&mut self) -> State {
+        match self.child.try_wait() {
+            Ok(None) => return State::Running,
+            Ok(Some(status)) if !status.success() && self.step != Step::Sync => {
+                return State::Failed(self.reason());
+            }
+            Err(_) if self.step != Step::Sync => return State::Failed(None),
+            Ok(Some(_)) | Err(_) => {}
+        }
+        let Some(step) = self.next.pop() else {
+            return State::Failed(None);
+        };
+        let Some(child) = spawn(&step, self.action, self.no_block) else {
+            return State::Failed(None);
+        };
+        self.step = step;
+        self.child = child;
+        let now = Instant::now();
+        self.started = now;
+        self.sample = (now, SystemTime::now());
+        State::Running
+    }
+
+    /// Done with the action once resumed: reap systemctl, which may still
+    /// be finishing, without holding up the event loop
+    pub fn reap(mut self) {
+        if matches!(self.child.try_wait(), Ok(None)) {
+            thread::spawn(move || self.child.wait());
+        }
+    }
+
+    /// First line the step printed before exiting
+    fn reason(&mut self) -> Option<String> {
+        let mut output = String::new();
+        self.child.stderr.take()?.read_to_string(&mut output).ok()?;
+        first_line(&output)
+    }
+}
+
+/// Key handling logind can be kept from acting on while the greeter runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Inhibit {
+    /// Blanking and automatic suspend when idle
+    Idle,
+    HandlePowerKey,
+    HandleSuspendKey,
+    HandleHibernateKey,
+    /// Closing the lid
+    HandleLidSwitch,
+}
+
+impl Inhibit {
+    /// Name in `systemd-inhibit --what`
+    const fn what(self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::HandlePowerKey => "handle-power-key",
+            Self::HandleSuspendKey => "handle-suspend-key",
+            Self::HandleHibernateKey => "handle-hibernate-key",
+            Self::HandleLidSwitch => "handle-lid-switch",
+        }
+    }
+}
+
+/// A logind inhibitor lock held by `systemd-inhibit`. The command it runs
+/// waits on a pipe from grxxt, so the lock goes away with grxxt even if it
+/// crashes.
+pub struct Inhibitor(Child);
+
+impl Inhibitor {
+    /// Take the lock for `what`; `None` when there is nothing to inhibit or
+    /// `systemd-inhibit` isn't there
+    pub fn start(what: &[Inhibit]) -> Option<Self> {
+        if what.is_empty() {
+            return None;
+        }
+        let names: Vec<&str> = what.iter().map(|inhibit| inhibit.what()).collect();
+        Command::new("systemd-inhibit")
+            .arg(format!("--what={}", names.join(":")))
+            .args(["--who=grxxt", "--why=Login screen", "--mode=block", "cat"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()
+            .map(Self)
+    }
+
+    /// Let logind act again, before the session starts
+    pub fn release(mut self) {
+        // Closing the pipe ends `cat`, and with it the lock
+        drop(self.0.stdin.take());
+        self.0.wait().ok();
+    }
+}
+
+/// First non-empty line, e.g. `Failed to power off system: Access denied`
+fn first_line(output: &str) -> Option<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// `Instant` stops while suspended but the wall clock keeps going
+fn slept(mono: Duration, wall: Duration) -> bool {
+    wall.saturating_sub(mono) > SLEEP_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    #[test]
+    fn test_slept() {
+        let tick = Duration::from_millis(500);
+        assert!(!slept(tick, tick));
+        assert!(!slept(tick, tick + Duration::from_secs(1)));
+        assert!(slept(tick, Duration::from_mins(5)));
+        // Wall clock stepped backwards (NTP) is not a resume
+        assert!(!slept(tick, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_hold() {
+        let time = Timeline::new();
+        let mut hold = Hold::new(Action::Reboot, time.start);
+        // Keyboards wait before the first repeat
+        assert_eq!(hold.state(time.millis(500)), HoldState::Holding);
+        hold.repeat(time.millis(600));
+        for ms in (630..2000).step_by(30) {
+            hold.repeat(time.millis(ms));
+        }
+        assert!((hold.progress(time.millis(1000)) - 0.5).abs() < f64::EPSILON);
+        assert_eq!(hold.state(time.millis(2000)), HoldState::Confirmed);
+        assert!((hold.progress(time.millis(5000)) - 1.0).abs() < f64::EPSILON);
+
+        // Let go early
+        let hold = Hold::new(Action::Shutdown, time.start);
+        assert_eq!(hold.state(time.millis(1000)), HoldState::Released);
+    }
+
+    #[test]
+    fn test_failure_reason() {
+        assert_eq!(
+            first_line("\nFailed to suspend system: Access denied\nSee logs\n").as_deref(),
+            Some("Failed to suspend system: Access denied")
+        );
+        assert_eq!(first_line(" \n"), None);
+    }
 }
@@ -0,0 +1,200 @@
+//! `grxxt preview`: the greeter's screens printed to stdout
+//!
+//! Draws a few representative states (idle, typing the password, an error,
+//! authenticating, a power confirmation) with the theme of a config file and
+//! prints them as ANSI text, so a theme can be worked on without greetd.
+//! Tools like `freeze` or `ansilove` turn the output into images.
+
+use std::io::{self, Write as _};
+use std::path::Path;
+
+use anyhow::Result;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::App;
+use crate::cli::Preview;
+use crate::config::Config;
+use crate::focus::{Context, Field, FocusStack};
+use crate::policy::Policy;
+use crate::power::Action;
+use crate::state;
+use crate::submission::Submission;
+use crate::theme::Theme;
+use crate::ui;
+
+/// Puts a fresh app into the state shown
+type Setup = fn(&mut App);
+
+/// States shown, by name
+const STATES: [(&str, Setup); 5] = [
+    ("idle", |_| {}),
+    ("password", |app| {
+        app.username = "alice".to_string();
+        app.password = "hunter2".to_string();
+        app.focus = FocusStack::new(Field::Password);
+    }),
+    ("error", |app| {
+        app.username = "alice".to_string();
+        app.focus = FocusStack::new(Field::Password);
+        app.error = Some("Authentication failed".to_string());
+    }),
+    ("authenticating", |app| {
+        app.username = "alice".to_string();
+        app.password = "hunter2".to_string();
+        app.focus = FocusStack::new(Field::Password);
+        app.submission = Submission::Sent;
+    }),
+    ("power", |app| {
+        app.focus.push(Context::Confirm(Action::Shutdown));
+    }),
+];
+
+/// Print the screens with the theme in `options`
+///
+/// # Errors
+///
+/// When stdout can't be written.
+pub fn run(options: &Preview) -> Result<()> {
+    let mut config = options
+        .theme
+        .as_deref()
+        .map_or_else(Config::load, |path| Config::load_from(Path::new(path)));
+    // Nothing outside the terminal besides reading the config: no sockets,
+    // state files or image probing
+    state::disable();
+    config.events_socket = None;
+    config.metrics_file = None;
+    config.low_resource = true;
+    config.display.burn_in = false;
+
+    let mut out = io::stdout().lock();
+    for warning in Theme::from(&config.theme).contrast_warnings() {
+        writeln!(out, "warning: {warning}")?;
+    }
+    for (name, setup) in STATES {
+        let mut app = App::new(&config, Policy::load());
+        app.username.clear();
+        app.error = None;
+        app.focus = FocusStack::new(Field::Username);
+        setup(&mut app);
+
+        let mut terminal = Terminal::new(TestBackend::new(options.width, options.height))?;
+        terminal.draw(|frame| ui::render(frame, &mut app))?;
+        writeln!(out, "── {name} ──")?;
+        out.write_all(ansi(terminal.backend().buffer()).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// `buffer` as lines of text with SGR escapes
+fn ansi(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for y in 0..buffer.area.height {
+        let mut style = None;
+        let mut hidden = 0;
+        for x in 0..buffer.area.width {
+            // Cells under the right half of a wide character
+            if hidden > 0 {
+                hidden -= 1;
+                continue;
+            }
+            let cell = &buffer[(x, y)];
+            let cell_style = (cell.fg, cell.bg, cell.modifier);
+            if style != Some(cell_style) {
+                text.push_str(&sgr(cell.fg, cell.bg, cell.modifier));
+                style = Some(cell_style);
+            }
+            text.push_str(cell.symbol());
+            hidden = cell.symbol().width().saturating_sub(1);
+        }
+        text.push_str("\x1b[0m\n");
+    }
+    text
+}
+
+/// Escape that resets and then sets these colors and attributes
+fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let mut codes = vec!["0".to_string()];
+    for (flag, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if modifier.contains(flag) {
+            codes.push(code.to_string());
+        }
+    }
+    codes.extend(color(fg, 38));
+    codes.extend(color(bg, 48));
+    let mut escape = String::from("\x1b[");
+    escape.push_str(&codes.join(";"));
+    escape.push('m');
+    escape
+}
+
+/// SGR parameters of a foreground (`base` 38) or background (48) color
+fn color(color: Color, base: u8) -> Option<String> {
+    let index = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => return Some(format!("{base};2;{r};{g};{b}")),
+        Color::Indexed(index) => index,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+    };
+    Some(format!("{base};5;{index}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    #[test]
+    fn test_ansi() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 4, 2));
+        buffer.set_string(0, 0, "ok", Style::default().fg(Color::Rgb(1, 2, 3)));
+        buffer.set_string(0, 1, "日x", Style::default());
+        assert_eq!(
+            ansi(&buffer),
+            "\x1b[0;38;2;1;2;3mok\x1b[0m  \x1b[0m\n\x1b[0m日x \x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_sgr() {
+        assert_eq!(
+            sgr(
+                Color::Indexed(215),
+                Color::Black,
+                Modifier::BOLD | Modifier::REVERSED
+            ),
+            "\x1b[0;1;7;38;5;215;48;5;0m"
+        );
+        assert_eq!(
+            sgr(Color::Reset, Color::Reset, Modifier::empty()),
+            "\x1b[0m"
+        );
+    }
+}
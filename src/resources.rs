@@ -0,0 +1,102 @@
+//! Low disk space and memory warnings before logging in
+//!
+//! A full /home makes most desktops fail to start in confusing ways, so
+//! the free space of the `[resources] disks` and the available memory are
+//! checked once at startup. Anything under its threshold is shown as a
+//! banner below the login form.
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+
+use nix::sys::statvfs::statvfs;
+
+use crate::config::ResourcesConfig;
+
+const MEMINFO: &str = "/proc/meminfo";
+
+/// Warnings such as `Low disk space: 2% free`. Blocks on the filesystems.
+pub fn check(options: &ResourcesConfig) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if options.min_disk_free > 0 {
+        // /home is often on the root filesystem; warn about it once
+        let mut devices = Vec::new();
+        for path in &options.disks {
+            let Ok(metadata) = fs::metadata(path) else {
+                continue;
+            };
+            if devices.contains(&metadata.dev()) {
+                continue;
+            }
+            devices.push(metadata.dev());
+            let free = disk_free(path);
+            if let Some(free) = free.filter(|&free| free < u64::from(options.min_disk_free)) {
+                warnings.push(disk_warning(path, free));
+            }
+        }
+    }
+    if options.min_memory > 0 {
+        let available = fs::read_to_string(MEMINFO)
+            .ok()
+            .and_then(|meminfo| memory_available(&meminfo));
+        if let Some(available) =
+            available.filter(|&available| available < u64::from(options.min_memory))
+        {
+            warnings.push(format!("Low memory: {available}% available"));
+        }
+    }
+    warnings
+}
+
+/// Percent of the filesystem unprivileged users may still fill
+fn disk_free(path: &str) -> Option<u64> {
+    let stat = statvfs(path).ok()?;
+    percent(stat.blocks_available(), stat.blocks())
+}
+
+fn disk_warning(path: &str, free: u64) -> String {
+    if path == "/" {
+        format!("Low disk space: {free}% free")
+    } else {
+        format!("Low disk space on {path}: {free}% free")
+    }
+}
+
+/// `MemAvailable` as a percent of `MemTotal`
+fn memory_available(meminfo: &str) -> Option<u64> {
+    let field = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            value.split_whitespace().next()?.parse::<u64>().ok()
+        })
+    };
+    percent(field("MemAvailable")?, field("MemTotal")?)
+}
+
+fn percent(part: u64, total: u64) -> Option<u64> {
+    (total > 0).then(|| part.saturating_mul(100) / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_available() {
+        let meminfo = "\
+MemTotal:       16000000 kB
+MemFree:          200000 kB
+MemAvailable:     480000 kB
+Buffers:          100000 kB
+";
+        assert_eq!(memory_available(meminfo), Some(3));
+        // Kernels before 3.14 have no MemAvailable
+        assert_eq!(memory_available("MemTotal: 16000000 kB\n"), None);
+        assert_eq!(memory_available("MemTotal: 0 kB\nMemAvailable: 0 kB\n"), None);
+    }
+
+    #[test]
+    fn test_disk_warning() {
+        assert_eq!(disk_warning("/", 2), "Low disk space: 2% free");
+        assert_eq!(disk_warning("/home", 0), "Low disk space on /home: 0% free");
+    }
+}
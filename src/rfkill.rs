@@ -0,0 +1,106 @@
+//! Airplane mode: whether every radio is switched off
+//!
+//! `/dev/rfkill` reports each radio (Wi-Fi, Bluetooth, WWAN, ...) as an
+//! event when opened; a radio is off when it's soft-blocked (`rfkill
+//! block`) or hard-blocked (a hardware switch). systemd gives the active
+//! seat's session access to the device, so the greeter can also switch all
+//! radios at once, as a key does when bound to `airplane`.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+
+use nix::fcntl::OFlag;
+
+const DEVICE: &str = "/dev/rfkill";
+
+/// Size of the kernel's original `struct rfkill_event`, which it still
+/// accepts and returns when asked for that much
+const EVENT_SIZE: usize = 8;
+
+/// Event ops
+const OP_ADD: u8 = 0;
+const OP_DEL: u8 = 1;
+const OP_CHANGE: u8 = 2;
+const OP_CHANGE_ALL: u8 = 3;
+
+/// All radio types
+const TYPE_ALL: u8 = 0;
+
+/// Whether all radios are off; `false` without radios or access
+pub fn airplane() -> bool {
+    let Ok(mut device) = open(false) else {
+        return false;
+    };
+    let mut events = Vec::new();
+    let mut event = [0; EVENT_SIZE];
+    // One event per read, until the initial list is through
+    while matches!(device.read(&mut event), Ok(EVENT_SIZE)) {
+        events.extend_from_slice(&event);
+    }
+    all_blocked(&events)
+}
+
+/// Switch all radios off, or back on. Hardware switches win.
+pub fn set_airplane(on: bool) -> io::Result<()> {
+    let event = [0, 0, 0, 0, TYPE_ALL, OP_CHANGE_ALL, u8::from(on), 0];
+    open(true)?.write_all(&event)
+}
+
+fn open(write: bool) -> io::Result<File> {
+    OpenOptions::new()
+        .read(!write)
+        .write(write)
+        .custom_flags(OFlag::O_NONBLOCK.bits())
+        .open(DEVICE)
+}
+
+/// Replay `events` and see whether any radio is left, all of them blocked
+fn all_blocked(events: &[u8]) -> bool {
+    let mut radios = BTreeMap::new();
+    for event in events.chunks_exact(EVENT_SIZE) {
+        let index = u32::from_ne_bytes([event[0], event[1], event[2], event[3]]);
+        let blocked = event[6] != 0 || event[7] != 0;
+        match event[5] {
+            OP_ADD | OP_CHANGE => {
+                radios.insert(index, blocked);
+            }
+            OP_DEL => {
+                radios.remove(&index);
+            }
+            _ => {}
+        }
+    }
+    !radios.is_empty() && radios.values().all(|&blocked| blocked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(index: u32, op: u8, soft: bool, hard: bool) -> Vec<u8> {
+        let mut event = index.to_ne_bytes().to_vec();
+        event.extend([1, op, u8::from(soft), u8::from(hard)]);
+        event
+    }
+
+    #[test]
+    fn test_all_blocked() {
+        assert!(!all_blocked(&[]));
+        let wifi_off = event(0, OP_ADD, true, false);
+        let bluetooth_on = event(1, OP_ADD, false, false);
+        assert!(all_blocked(&wifi_off));
+        assert!(!all_blocked(
+            &[wifi_off.clone(), bluetooth_on.clone()].concat()
+        ));
+        // The switch on the side of the laptop
+        let bluetooth_switched = event(1, OP_CHANGE, false, true);
+        assert!(all_blocked(
+            &[wifi_off.clone(), bluetooth_on.clone(), bluetooth_switched].concat()
+        ));
+        // A USB dongle unplugged
+        let dongle_gone = event(1, OP_DEL, false, false);
+        assert!(all_blocked(&[wifi_off, bluetooth_on, dongle_gone].concat()));
+    }
+}
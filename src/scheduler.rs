@@ -0,0 +1,294 @@
+//! Frame scheduler: one place the event loop waits on
+//!
+//! Input, timers and background tasks all wake the loop through a single
+//! channel. A thread pumps the [`EventSource`] into it, background jobs send
+//! their result when done, and the wait times out at the earliest timer, so
+//! nothing is polled on a fixed interval.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use chrono::{Local, Timelike};
+
+use crate::auth::{AuthError, Outcome};
+use crate::input::{EventSource, InputEvent};
+
+/// How long the input thread blocks before checking whether to stop
+const INPUT_POLL: Duration = Duration::from_millis(100);
+
+/// Something that should happen at a given time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timer {
+    /// The minute (or second) changed; redraw the clock
+    Clock,
+    /// Re-query the header status indicators
+    Status,
+    /// Check on the running power action
+    Power,
+    /// Redraw the hold progress and check whether the key is still held
+    Hold,
+    /// Move or dim the picture against burn-in
+    BurnIn,
+    /// Check whether to count down to an idle suspend
+    Idle,
+    /// Run the health checks
+    Checks,
+    /// Redraw the countdown to the session start, or start it
+    Handoff,
+    /// Draw the next step of the fade-in
+    Fade,
+    /// Type the next character of the greeting
+    Typewriter,
+    /// Draw the next frame of the idle effect, or see whether to start it
+    Effect,
+    /// Look for a keyboard again
+    Keyboard,
+    /// Look for greetd's socket again
+    Greetd,
+    /// Speak what changed on screen, if it held still
+    Announce,
+    /// Take an error message away that has been up long enough
+    Error,
+    /// Show the next of several notices
+    Notice,
+    /// Turn the spinner while the backend checks a login
+    Spinner,
+}
+
+/// Result of a background job
+#[derive(Debug, PartialEq, Eq)]
+pub enum Task {
+    /// timedated's `NTPSynchronized`, when it could be read
+    ClockSync(Option<bool>),
+    /// Remote logind sessions, when logind could be asked
+    RemoteSessions(Option<usize>),
+    /// Failed systemd units, when systemctl could be asked
+    FailedUnits(Option<Vec<String>>),
+    /// Problems found by the health checks
+    Health(Vec<String>),
+    /// Low disk space and memory found at startup
+    Resources(Vec<String>),
+    /// Class of the greeter's session, when it isn't `greeter`
+    SessionClass(Option<String>),
+    /// Whether every radio is switched off
+    Airplane(bool),
+    /// Whether systemd-homed manages the user, by username
+    Homed(String, bool),
+    /// Whether an exclusion keeps the idle machine awake
+    IdleExcluded(bool),
+    /// The backend's verdict on a login attempt, by its number
+    Auth(u64, Result<Outcome, AuthError>),
+}
+
+/// Why the event loop woke up
+#[derive(Debug, PartialEq, Eq)]
+pub enum Wake {
+    Input(InputEvent),
+    /// An input device reported something the terminal may not have seen
+    #[cfg(feature = "input-activity")]
+    Activity,
+    /// An input device was plugged in or removed
+    #[cfg(feature = "hotplug")]
+    Hotplug,
+    Timer(Timer),
+    Task(Task),
+}
+
+pub struct Scheduler {
+    tx: Sender<io::Result<Wake>>,
+    rx: Receiver<io::Result<Wake>>,
+    timers: Vec<(Instant, Timer)>,
+    stop: Arc<AtomicBool>,
+    input: Option<JoinHandle<()>>,
+}
+
+impl Scheduler {
+    /// Start reading input from `source` on a background thread
+    pub fn new(mut source: impl EventSource + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let input = {
+            let (tx, stop) = (tx.clone(), Arc::clone(&stop));
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let event = match source.next(INPUT_POLL) {
+                        Ok(Some(event)) => Ok(Wake::Input(event)),
+                        Ok(None) => continue,
+                        Err(e) => Err(e),
+                    };
+                    let failed = event.is_err();
+                    if tx.send(event).is_err() || failed {
+                        break;
+                    }
+                }
+            })
+        };
+        Self {
+            tx,
+            rx,
+            timers: Vec::new(),
+            stop,
+            input: Some(input),
+        }
+    }
+
+    /// Fire `timer` after `delay`, replacing an earlier schedule of it
+    pub fn after(&mut self, delay: Duration, timer: Timer) {
+        self.timers.retain(|&(_, scheduled)| scheduled != timer);
+        self.timers.push((Instant::now() + delay, timer));
+    }
+
+    /// Run `job` on its own thread; its result wakes the loop as [`Wake::Task`]
+    pub fn spawn(&self, job: impl FnOnce() -> Task + Send + 'static) {
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            tx.send(Ok(Wake::Task(job()))).ok();
+        });
+    }
+
+    /// Wake the loop with [`Wake::Activity`] when an input device reports
+    /// anything; `false` when no device could be read
+    #[cfg(feature = "input-activity")]
+    pub fn watch_activity(&self) -> bool {
+        let tx = self.tx.clone();
+        crate::activity::watch(move || tx.send(Ok(Wake::Activity)).is_ok())
+    }
+
+    /// Wake the loop with [`Wake::Hotplug`] when an input device is added
+    /// or removed; `false` when the kernel's events can't be received
+    #[cfg(feature = "hotplug")]
+    pub fn watch_hotplug(&self) -> bool {
+        let tx = self.tx.clone();
+        crate::hotplug::watch(move || tx.send(Ok(Wake::Hotplug)).is_ok())
+    }
+
+    /// Wait for the next input event, due timer or finished task
+    pub fn next(&mut self) -> io::Result<Wake> {
+        loop {
+            let now = Instant::now();
+            let earliest = self
+                .timers
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (at, _))| *at)
+                .map(|(index, &(at, _))| (index, at));
+            let received = match earliest {
+                Some((index, at)) if at <= now => {
+                    return Ok(Wake::Timer(self.timers.swap_remove(index).1));
+                }
+                Some((_, at)) => self.rx.recv_timeout(at - now),
+                None => self.rx.recv().map_err(RecvTimeoutError::from),
+            };
+            match received {
+                Ok(wake) => return wake,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::other("scheduler channel closed"));
+                }
+            }
+        }
+    }
+
+    /// Drop input that queued up while it shouldn't count (e.g. keys typed
+    /// as the machine went to sleep); timers and task results are kept
+    pub fn discard_input(&self) {
+        let kept: Vec<_> = self
+            .rx
+            .try_iter()
+            .filter(|wake| !matches!(wake, Ok(Wake::Input(_))))
+            .collect();
+        for wake in kept {
+            self.tx.send(wake).ok();
+        }
+    }
+}
+
+impl Drop for Scheduler {
+    /// Stop reading input so a session started on this TTY gets every key
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(input) = self.input.take() {
+            input.join().ok();
+        }
+    }
+}
+
+/// Time until the wall clock reaches the next full second
+pub fn until_next_second() -> Duration {
+    let into_second = Duration::from_nanos(u64::from(Local::now().nanosecond()));
+    Duration::from_secs(1).saturating_sub(into_second)
+}
+
+/// Time until the wall clock reaches the next full minute
+pub fn until_next_minute() -> Duration {
+    let now = Local::now();
+    let into_minute = Duration::from_secs(u64::from(now.second()))
+        + Duration::from_nanos(u64::from(now.nanosecond()));
+    Duration::from_mins(1).saturating_sub(into_minute)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+    use crate::input::{KeyCode, Scripted};
+
+    /// Input that never arrives
+    struct Idle;
+
+    impl EventSource for Idle {
+        fn next(&mut self, timeout: Duration) -> io::Result<Option<InputEvent>> {
+            thread::sleep(timeout);
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn test_timers_fire_in_order() {
+        let mut scheduler = Scheduler::new(Idle);
+        scheduler.after(Duration::from_millis(40), Timer::Status);
+        scheduler.after(Duration::from_millis(10), Timer::Clock);
+        // Rescheduling replaces the earlier entry
+        scheduler.after(Duration::from_millis(20), Timer::Clock);
+
+        let start = Instant::now();
+        assert_eq!(scheduler.next().unwrap(), Wake::Timer(Timer::Clock));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(scheduler.next().unwrap(), Wake::Timer(Timer::Status));
+    }
+
+    #[test]
+    fn test_task_wakes_before_timer() {
+        let mut scheduler = Scheduler::new(Idle);
+        scheduler.after(Duration::from_mins(1), Timer::Clock);
+        scheduler.spawn(|| Task::ClockSync(Some(true)));
+        assert_eq!(
+            scheduler.next().unwrap(),
+            Wake::Task(Task::ClockSync(Some(true)))
+        );
+    }
+
+    #[test]
+    fn test_input_and_discard() {
+        let mut scheduler = Scheduler::new(Scripted::typing("ab", &[]));
+        assert!(matches!(
+            scheduler.next().unwrap(),
+            Wake::Input(InputEvent::Key(key)) if key.code == KeyCode::Char('a')
+        ));
+        // The rest of the script (and its end) may still be in flight
+        thread::sleep(Duration::from_millis(50));
+        scheduler.discard_input();
+        // Only the end-of-script error is left
+        assert!(scheduler.next().is_err());
+    }
+
+    #[test]
+    fn test_until_next_minute() {
+        assert!(until_next_minute() <= Duration::from_mins(1));
+        assert!(until_next_second() <= Duration::from_secs(1));
+    }
+}
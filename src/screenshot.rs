@@ -0,0 +1,260 @@
+//! Screenshots from demo mode
+//!
+//! With the `screenshot` feature, F12 in `grxxt --demo` saves the screen as
+//! a PNG in the current directory, for theme galleries and bug reports. The
+//! cell grid is drawn with an embedded 9×18 bitmap font, which covers
+//! Latin-1; box drawing and block characters, which it lacks, are drawn as
+//! lines and blocks, and anything else shows as `?`.
+
+use std::convert::Infallible;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::Local;
+use embedded_graphics::mono_font::iso_8859_1::{FONT_9X18, FONT_9X18_BOLD};
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
+use embedded_graphics::prelude::{DrawTarget, Drawable, OriginDimensions, Pixel, Point, Size};
+use embedded_graphics::text::{Baseline, Text};
+use image::{Rgb, RgbImage};
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::Modifier;
+
+use crate::theme::Theme;
+
+/// Pixels of a cell
+const WIDTH: u32 = 9;
+const HEIGHT: u32 = 18;
+
+/// Save `buffer`, a drawn frame, as `grxxt-<date>-<time>.png`. Returns the
+/// file's path.
+///
+/// # Errors
+///
+/// When the file can't be written.
+pub fn save(buffer: &Buffer, theme: &Theme) -> Result<PathBuf> {
+    let path = PathBuf::from(Local::now().format("grxxt-%Y%m%d-%H%M%S.png").to_string());
+    render(buffer, theme).save(&path)?;
+    Ok(path)
+}
+
+/// The frame as an image
+fn render(buffer: &Buffer, theme: &Theme) -> RgbImage {
+    let width = u32::from(buffer.area.width);
+    let mut canvas = Canvas(RgbImage::new(
+        width * WIDTH,
+        u32::from(buffer.area.height) * HEIGHT,
+    ));
+    // Rows of `width` cells; an empty buffer has none
+    for (index, cell) in (0u32..).zip(&buffer.content) {
+        canvas.cell(index % width * WIDTH, index / width * HEIGHT, cell, theme);
+    }
+    canvas.0
+}
+
+/// Image the font is drawn into
+struct Canvas(RgbImage);
+
+impl Canvas {
+    /// Draw `cell` with its top left corner at `x`, `y`
+    fn cell(&mut self, x: u32, y: u32, cell: &Cell, theme: &Theme) {
+        let mut fg = theme.rgb(cell.fg, true);
+        let mut bg = theme.rgb(cell.bg, false);
+        if cell.modifier.contains(Modifier::REVERSED) {
+            (fg, bg) = (bg, fg);
+        }
+        if cell.modifier.contains(Modifier::DIM) {
+            fg = blend(bg, fg, 2);
+        }
+        self.fill(x, y, WIDTH, HEIGHT, bg);
+        if cell.modifier.contains(Modifier::HIDDEN) {
+            return;
+        }
+        let symbol = cell.symbol();
+        let mut chars = symbol.chars();
+        let drawn = match (chars.next(), chars.next()) {
+            (Some(c), None) => self.box_drawing(x, y, c, fg) || self.block(x, y, c, fg, bg),
+            _ => false,
+        };
+        if !drawn && symbol != " " {
+            let font = if cell.modifier.contains(Modifier::BOLD) {
+                &FONT_9X18_BOLD
+            } else {
+                &FONT_9X18
+            };
+            let style = MonoTextStyle::new(font, Rgb888::new(fg[0], fg[1], fg[2]));
+            let origin = Point::new(
+                i32::try_from(x).unwrap_or(i32::MAX),
+                i32::try_from(y).unwrap_or(i32::MAX),
+            );
+            let Ok(_) = Text::with_baseline(symbol, origin, style, Baseline::Top).draw(self);
+        }
+        if cell.modifier.contains(Modifier::UNDERLINED) {
+            self.fill(x, y + HEIGHT - 2, WIDTH, 1, fg);
+        }
+    }
+
+    /// Lines of `─│┌` and the like, light, heavy or double, from the middle
+    /// of the cell to its edges. Returns whether `c` is one of them.
+    fn box_drawing(&mut self, x: u32, y: u32, c: char, color: [u8; 3]) -> bool {
+        // Arms up, down, left and right: 1 light, 2 heavy, 3 double
+        let [up, down, left, right] = match c {
+            '─' => [0, 0, 1, 1],
+            '│' => [1, 1, 0, 0],
+            '┌' | '╭' => [0, 1, 0, 1],
+            '┐' | '╮' => [0, 1, 1, 0],
+            '└' | '╰' => [1, 0, 0, 1],
+            '┘' | '╯' => [1, 0, 1, 0],
+            '├' => [1, 1, 0, 1],
+            '┤' => [1, 1, 1, 0],
+            '┬' => [0, 1, 1, 1],
+            '┴' => [1, 0, 1, 1],
+            '┼' => [1, 1, 1, 1],
+            '━' => [0, 0, 2, 2],
+            '┃' => [2, 2, 0, 0],
+            '┏' => [0, 2, 0, 2],
+            '┓' => [0, 2, 2, 0],
+            '┗' => [2, 0, 0, 2],
+            '┛' => [2, 0, 2, 0],
+            '┣' => [2, 2, 0, 2],
+            '┫' => [2, 2, 2, 0],
+            '┳' => [0, 2, 2, 2],
+            '┻' => [2, 0, 2, 2],
+            '╋' => [2, 2, 2, 2],
+            '═' => [0, 0, 3, 3],
+            '║' => [3, 3, 0, 0],
+            '╔' => [0, 3, 0, 3],
+            '╗' => [0, 3, 3, 0],
+            '╚' => [3, 0, 0, 3],
+            '╝' => [3, 0, 3, 0],
+            '╠' => [3, 3, 0, 3],
+            '╣' => [3, 3, 3, 0],
+            '╦' => [0, 3, 3, 3],
+            '╩' => [3, 0, 3, 3],
+            '╬' => [3, 3, 3, 3],
+            _ => return false,
+        };
+        let (middle_x, middle_y) = (x + WIDTH / 2, y + HEIGHT / 2);
+        // Offsets of the strokes across an arm
+        let strokes = |weight: u8| -> &[(i32, u32)] {
+            match weight {
+                1 => &[(0, 1)],
+                2 => &[(-1, 3)],
+                3 => &[(-2, 1), (2, 1)],
+                _ => &[],
+            }
+        };
+        // Arms overlap in the middle
+        let (tall, wide) = (HEIGHT / 2 + 1, WIDTH / 2 + 1);
+        let column = |offset| middle_x.saturating_add_signed(offset);
+        let row = |offset| middle_y.saturating_add_signed(offset);
+        for &(offset, thickness) in strokes(up) {
+            self.fill(column(offset), y, thickness, tall, color);
+        }
+        for &(offset, thickness) in strokes(down) {
+            self.fill(
+                column(offset),
+                middle_y,
+                thickness,
+                HEIGHT - HEIGHT / 2,
+                color,
+            );
+        }
+        for &(offset, thickness) in strokes(left) {
+            self.fill(x, row(offset), wide, thickness, color);
+        }
+        for &(offset, thickness) in strokes(right) {
+            self.fill(middle_x, row(offset), WIDTH - WIDTH / 2, thickness, color);
+        }
+        true
+    }
+
+    /// `█▀▄▌▐` and the shades `░▒▓`. Returns whether `c` is one of them.
+    fn block(&mut self, x: u32, y: u32, c: char, fg: [u8; 3], bg: [u8; 3]) -> bool {
+        let (half_width, half_height) = (WIDTH / 2, HEIGHT / 2);
+        match c {
+            '█' => self.fill(x, y, WIDTH, HEIGHT, fg),
+            '▀' => self.fill(x, y, WIDTH, half_height, fg),
+            '▄' => self.fill(x, y + half_height, WIDTH, HEIGHT - half_height, fg),
+            '▌' => self.fill(x, y, half_width, HEIGHT, fg),
+            '▐' => self.fill(x + half_width, y, WIDTH - half_width, HEIGHT, fg),
+            '░' => self.fill(x, y, WIDTH, HEIGHT, blend(bg, fg, 4)),
+            '▒' => self.fill(x, y, WIDTH, HEIGHT, blend(bg, fg, 2)),
+            '▓' => self.fill(x, y, WIDTH, HEIGHT, blend(fg, bg, 4)),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Paint a rectangle; what is off the image is left out
+    fn fill(&mut self, x: u32, y: u32, width: u32, height: u32, color: [u8; 3]) {
+        for py in y..y + height {
+            for px in x..x + width {
+                if let Some(pixel) = self.0.get_pixel_mut_checked(px, py) {
+                    *pixel = Rgb(color);
+                }
+            }
+        }
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        Size::new(self.0.width(), self.0.height())
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+    where
+        I: IntoIterator<Item = Pixel<Rgb888>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(point.x), u32::try_from(point.y)) {
+                self.fill(x, y, 1, 1, [color.r(), color.g(), color.b()]);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `1 / part` of the way from one color to another
+fn blend(from: [u8; 3], to: [u8; 3], part: u16) -> [u8; 3] {
+    let channel = |index: usize| {
+        let mixed = (u16::from(from[index]) * (part - 1) + u16::from(to[index])) / part;
+        u8::try_from(mixed).unwrap_or(u8::MAX)
+    };
+    [channel(0), channel(1), channel(2)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ratatui::layout::Rect;
+    use ratatui::style::{Color, Style};
+
+    #[test]
+    fn test_render() {
+        let theme = Theme::default();
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 3, 2));
+        buffer.set_string(0, 0, "a█─", Style::default().fg(Color::Rgb(255, 0, 0)));
+        let image = render(&buffer, &theme);
+        assert_eq!(image.dimensions(), (27, 36));
+
+        let background = Rgb(theme.rgb(Color::Reset, false));
+        // The block fills its cell, the line crosses the middle of its own
+        assert_eq!(*image.get_pixel(9, 0), Rgb([255, 0, 0]));
+        assert_eq!(*image.get_pixel(17, 17), Rgb([255, 0, 0]));
+        assert_eq!(*image.get_pixel(18, 9), Rgb([255, 0, 0]));
+        assert_eq!(*image.get_pixel(18, 0), background);
+        // The glyph leaves the corner of its cell alone
+        assert_eq!(*image.get_pixel(0, 0), background);
+        assert!(image
+            .enumerate_pixels()
+            .any(|(x, y, &pixel)| x < 9 && y < 18 && pixel == Rgb([255, 0, 0])));
+    }
+}
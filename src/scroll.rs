@@ -0,0 +1,78 @@
+//! Scroll position of a list longer than its box
+//!
+//! Keys and the mouse wheel move a list a line at a time and never past its
+//! last line; the list can be replaced while open (a fresh list of failed
+//! units) and keeps its place when it still can. How much is hidden above
+//! and below decides the scrollbar the UI draws next to it.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Scroll {
+    /// First line shown
+    offset: usize,
+    /// Lines in the list
+    length: usize,
+}
+
+impl Scroll {
+    /// A list of `length` lines, scrolled to the top
+    pub const fn new(length: usize) -> Self {
+        Self { offset: 0, length }
+    }
+
+    /// First line shown
+    pub const fn offset(self) -> usize {
+        self.offset
+    }
+
+    /// Lines in the list
+    pub const fn length(self) -> usize {
+        self.length
+    }
+
+    pub const fn up(&mut self) {
+        self.offset = self.offset.saturating_sub(1);
+    }
+
+    /// Down a line; the last line stays in view
+    pub const fn down(&mut self) {
+        if self.offset + 1 < self.length {
+            self.offset += 1;
+        }
+    }
+
+    /// The list now has `length` lines
+    pub fn resize(&mut self, length: usize) {
+        self.length = length;
+        self.offset = self.offset.min(length.saturating_sub(1));
+    }
+
+    /// Lines are hidden above and below a box of `rows` lines
+    pub const fn more(self, rows: usize) -> (bool, bool) {
+        (self.offset > 0, self.offset + rows < self.length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scroll_bounds() {
+        let mut scroll = Scroll::new(3);
+        scroll.up();
+        assert_eq!(scroll.offset(), 0);
+        assert_eq!(scroll.more(2), (false, true));
+        for _ in 0..5 {
+            scroll.down();
+        }
+        assert_eq!(scroll.offset(), 2);
+        assert_eq!(scroll.more(2), (true, false));
+
+        // A shorter list keeps as much of the place as it can
+        scroll.resize(2);
+        assert_eq!(scroll.offset(), 1);
+        scroll.resize(0);
+        assert_eq!(scroll.offset(), 0);
+        assert_eq!(scroll.more(2), (false, false));
+    }
+}
@@ -0,0 +1,456 @@
+//! Session discovery
+//!
+//! Lists desktop sessions from freedesktop `.desktop` entries and builds the
+//! command that launches each one: Wayland sessions optionally through uwsm,
+//! X11 sessions through an X server wrapper (`startx` by default).
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::config::{LogMode, SessionLogConfig, SessionsConfig};
+
+const WAYLAND_SESSIONS: &str = "/usr/share/wayland-sessions";
+const X_SESSIONS: &str = "/usr/share/xsessions";
+const GREETD_CONFIG: &str = "/etc/greetd/config.toml";
+
+/// Rotates the previous log and redirects the session into the new one.
+/// Runs as the user, so `~` and `$HOME` resolve to their home.
+const LOG_WRAPPER: &str = r#"log="$1"; shift
+case "$log" in "~/"*) log="$HOME/${log#\~/}" ;; esac
+mkdir -p "$(dirname "$log")"
+[ -f "$log" ] && mv -f "$log" "$log.old"
+exec "$@" >"$log" 2>&1"#;
+
+/// How discovered sessions are launched
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Launcher {
+    /// Run the entry's `Exec` line as-is
+    #[default]
+    Direct,
+    /// Run the entry as a systemd user session via `uwsm start -- <id>.desktop`
+    Uwsm,
+}
+
+/// Display protocol of a session: the directory of its `.desktop` file,
+/// unless `[sessions.kind]` says otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Wayland,
+    X11,
+    /// Configured command line of unknown type (a script, a shell)
+    #[serde(rename = "shell")]
+    Command,
+}
+
+impl Kind {
+    /// `XDG_SESSION_TYPE` value
+    pub const fn xdg_type(self) -> Option<&'static str> {
+        match self {
+            Self::Wayland => Some("wayland"),
+            Self::X11 => Some("x11"),
+            Self::Command => None,
+        }
+    }
+
+    /// Badge shown next to the session name
+    pub const fn badge(self) -> &'static str {
+        match self {
+            Self::Wayland => "wayland",
+            Self::X11 => "x11",
+            Self::Command => "shell",
+        }
+    }
+}
+
+/// A selectable session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub name: String,
+    pub command: String,
+    pub kind: Kind,
+}
+
+impl Session {
+    /// Session for a raw command, named after its executable
+    pub fn from_command(command: &str) -> Self {
+        let program = command.split_whitespace().next().unwrap_or(command);
+        let name = program.rsplit('/').next().unwrap_or(program);
+        Self {
+            name: name.to_string(),
+            command: command.to_string(),
+            kind: Kind::Command,
+        }
+    }
+}
+
+/// A parsed `[Desktop Entry]` section
+#[derive(Debug, PartialEq, Eq)]
+struct DesktopEntry {
+    name: String,
+    exec: String,
+}
+
+/// Installed sessions from the Wayland and X11 session directories,
+/// launched according to `launcher`. X11 commands are prefixed with
+/// `x11_wrapper`, which starts the X server (empty: run as-is).
+pub fn discover(launcher: Launcher, x11_wrapper: &str, config: &SessionsConfig) -> Vec<Session> {
+    let mut scanned = scan(Path::new(WAYLAND_SESSIONS), Kind::Wayland, |file, exec| {
+        launch_command(file, exec, launcher)
+    });
+    scanned.extend(scan(Path::new(X_SESSIONS), Kind::X11, |_, exec| {
+        Some(x11_command(x11_wrapper, exec))
+    }));
+    arrange(scanned, config)
+}
+
+/// Apply the `[sessions]` exclusions, names, extra entries, kinds and order
+/// to scanned `(desktop id, session)` pairs
+fn arrange(scanned: Vec<(String, Session)>, config: &SessionsConfig) -> Vec<Session> {
+    let mut sessions: Vec<(String, Session)> = scanned
+        .into_iter()
+        .filter(|(id, _)| !config.exclude.iter().any(|pattern| glob_match(pattern, id)))
+        .map(|(id, mut session)| {
+            if let Some(name) = config.rename.get(&id) {
+                session.name.clone_from(name);
+            }
+            (id, session)
+        })
+        .collect();
+    sessions.extend(config.extra.iter().map(|extra| {
+        let session = Session {
+            name: extra.name.clone(),
+            command: extra.command.clone(),
+            kind: extra.kind.unwrap_or(Kind::Command),
+        };
+        (extra.name.clone(), session)
+    }));
+    for (id, session) in &mut sessions {
+        if let Some(&kind) = config
+            .kind
+            .get(id.as_str())
+            .or_else(|| config.kind.get(&session.name))
+        {
+            session.kind = kind;
+        }
+    }
+
+    let rank = |id: &str, session: &Session| {
+        config
+            .order
+            .iter()
+            .position(|key| key == id || *key == session.name)
+            .unwrap_or(config.order.len())
+    };
+    sessions.sort_by(|(a_id, a), (b_id, b)| {
+        rank(a_id, a)
+            .cmp(&rank(b_id, b))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    sessions.into_iter().map(|(_, session)| session).collect()
+}
+
+/// Match `text` against a pattern where `*` stands for any run of characters
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`: the whole text must match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Sessions in `dir` with their desktop ids (file name without `.desktop`).
+/// `launch` builds the command from the file name and `Exec` line.
+fn scan(
+    dir: &Path,
+    kind: Kind,
+    launch: impl Fn(&str, &str) -> Option<String>,
+) -> Vec<(String, Session)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "desktop" {
+                return None;
+            }
+            let file = path.file_name()?.to_str()?.to_string();
+            let id = path.file_stem()?.to_str()?.to_string();
+            let desktop = parse_desktop_entry(&fs::read_to_string(&path).ok()?)?;
+            let command = launch(&file, &desktop.exec)?;
+            Some((
+                id,
+                Session {
+                    name: desktop.name,
+                    command,
+                    kind,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Build the launch command for a desktop entry.
+/// Entries that already wrap themselves in uwsm are skipped in uwsm mode
+/// so each desktop appears once.
+fn launch_command(id: &str, exec: &str, launcher: Launcher) -> Option<String> {
+    let self_managed = exec.split_whitespace().next() == Some("uwsm");
+    match launcher {
+        Launcher::Direct => Some(exec.to_string()),
+        Launcher::Uwsm if self_managed => None,
+        Launcher::Uwsm => Some(format!("uwsm start -- {id}")),
+    }
+}
+
+/// X11 sessions need a server; greetd only runs the command
+fn x11_command(wrapper: &str, exec: &str) -> String {
+    if wrapper.trim().is_empty() {
+        exec.to_string()
+    } else {
+        format!("{} {exec}", wrapper.trim())
+    }
+}
+
+/// Parse the `[Desktop Entry]` group of a `.desktop` file.
+/// Hidden and `NoDisplay` entries are skipped; `Exec` field codes are stripped.
+fn parse_desktop_entry(content: &str) -> Option<DesktopEntry> {
+    let mut in_entry = false;
+    let mut name = None;
+    let mut exec = None;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "Name" => name = Some(value.trim().to_string()),
+            "Exec" => exec = Some(strip_field_codes(value.trim())),
+            "Hidden" | "NoDisplay" if value.trim() == "true" => return None,
+            _ => {}
+        }
+    }
+
+    Some(DesktopEntry {
+        name: name?,
+        exec: exec.filter(|e| !e.is_empty())?,
+    })
+}
+
+/// Wrap a session command so its output is captured per `log`
+pub fn with_logging(command: &str, log: &SessionLogConfig) -> String {
+    let args = shell_words::split(command).unwrap_or_else(|_| vec![command.to_string()]);
+    let wrapper: Vec<&str> = match log.mode {
+        LogMode::None => return command.to_string(),
+        LogMode::File => vec!["sh", "-c", LOG_WRAPPER, "grxxt-session", &log.path],
+        LogMode::Journal => vec!["systemd-cat", "-t", "grxxt-session"],
+    };
+    shell_words::join(wrapper.into_iter().chain(args.iter().map(String::as_str)))
+}
+
+/// The session greetd itself would run: `initial_session` (autologin), or
+/// `default_session` when that isn't a greeter (including grxxt itself).
+pub fn greetd_default() -> Option<String> {
+    parse_greetd_default(&fs::read_to_string(GREETD_CONFIG).ok()?)
+}
+
+fn parse_greetd_default(content: &str) -> Option<String> {
+    let config: toml::Table = toml::from_str(content).ok()?;
+    let command = |section: &str| {
+        config
+            .get(section)?
+            .get("command")?
+            .as_str()
+            .map(str::to_string)
+    };
+
+    command("initial_session").or_else(|| {
+        command("default_session").filter(|cmd| {
+            !cmd.split_whitespace()
+                .any(|word| word.contains("greet") || word.contains("grxxt"))
+        })
+    })
+}
+
+/// Remove `%f`, `%U`, ... placeholders which make no sense for a session
+fn strip_field_codes(exec: &str) -> String {
+    exec.split_whitespace()
+        .filter(|arg| !(arg.len() == 2 && arg.starts_with('%')))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_desktop_entry() {
+        let entry = parse_desktop_entry(
+            "[Desktop Entry]\nName=Hyprland\nExec=Hyprland %U\nType=Application\n\n[Desktop Action x]\nName=Other\n",
+        )
+        .unwrap();
+        assert_eq!(entry.name, "Hyprland");
+        assert_eq!(entry.exec, "Hyprland");
+
+        assert_eq!(
+            parse_desktop_entry("[Desktop Entry]\nName=X\nExec=x\nNoDisplay=true\n"),
+            None
+        );
+        assert_eq!(parse_desktop_entry("[Desktop Entry]\nName=X\n"), None);
+    }
+
+    #[test]
+    fn test_launch_command() {
+        assert_eq!(
+            launch_command("sway.desktop", "sway", Launcher::Direct).as_deref(),
+            Some("sway")
+        );
+        assert_eq!(
+            launch_command("sway.desktop", "sway", Launcher::Uwsm).as_deref(),
+            Some("uwsm start -- sway.desktop")
+        );
+        assert_eq!(
+            launch_command(
+                "hyprland-uwsm.desktop",
+                "uwsm start -- hyprland.desktop",
+                Launcher::Uwsm
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_x11_command() {
+        assert_eq!(
+            x11_command("startx /usr/bin/env", "startplasma-x11"),
+            "startx /usr/bin/env startplasma-x11"
+        );
+        assert_eq!(x11_command("", "i3"), "i3");
+    }
+
+    #[test]
+    fn test_parse_greetd_default() {
+        let greeter_only =
+            "[default_session]\ncommand = \"/usr/local/bin/grxxt\"\nuser = \"greeter\"\n";
+        assert_eq!(parse_greetd_default(greeter_only), None);
+
+        let autologin =
+            format!("{greeter_only}\n[initial_session]\ncommand = \"sway\"\nuser = \"me\"\n");
+        assert_eq!(parse_greetd_default(&autologin).as_deref(), Some("sway"));
+
+        let plain = "[default_session]\ncommand = \"Hyprland\"\n";
+        assert_eq!(parse_greetd_default(plain).as_deref(), Some("Hyprland"));
+    }
+
+    #[test]
+    fn test_with_logging() {
+        let mut log = SessionLogConfig::default();
+        assert_eq!(
+            with_logging("sway --unsupported-gpu", &log),
+            "sway --unsupported-gpu"
+        );
+
+        log.mode = LogMode::Journal;
+        assert_eq!(
+            with_logging("sway --unsupported-gpu", &log),
+            "systemd-cat -t grxxt-session sway --unsupported-gpu"
+        );
+
+        log.mode = LogMode::File;
+        let wrapped = shell_words::split(&with_logging("sway", &log)).unwrap();
+        assert_eq!(wrapped[..2], ["sh", "-c"]);
+        assert_eq!(
+            wrapped[3..],
+            ["grxxt-session", "~/.local/share/grxxt/session.log", "sway"]
+        );
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("plasma-xorg", "plasma-xorg"));
+        assert!(!glob_match("plasma", "plasma-xorg"));
+        assert!(glob_match("*-xorg", "plasma-xorg"));
+        assert!(!glob_match("*-xorg", "plasma"));
+        assert!(glob_match("hypr*", "hyprland-uwsm"));
+        assert!(glob_match("*land*", "hyprland-uwsm"));
+        assert!(glob_match("*", ""));
+        // The prefix and suffix may not overlap
+        assert!(!glob_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_arrange() {
+        let session = |name: &str| Session {
+            name: name.to_string(),
+            command: name.to_lowercase(),
+            kind: Kind::Wayland,
+        };
+        let scanned = vec![
+            ("sway".to_string(), session("Sway")),
+            ("plasma".to_string(), session("Plasma")),
+            ("plasma-xorg".to_string(), session("Plasma (X11)")),
+            ("hyprland".to_string(), session("Hyprland")),
+        ];
+        let config: SessionsConfig = toml::from_str(
+            r#"
+exclude = ["*-xorg"]
+order = ["Shell", "hyprland"]
+extra = [{ name = "Shell", command = "/bin/bash" }]
+
+[rename]
+sway = "Sway (tiling)"
+
+[kind]
+plasma = "x11"
+"#,
+        )
+        .unwrap();
+
+        let arranged: Vec<(String, Kind)> = arrange(scanned, &config)
+            .into_iter()
+            .map(|session| (session.name, session.kind))
+            .collect();
+        assert_eq!(
+            arranged,
+            [
+                ("Shell".to_string(), Kind::Command),
+                ("Hyprland".to_string(), Kind::Wayland),
+                ("Plasma".to_string(), Kind::X11),
+                ("Sway (tiling)".to_string(), Kind::Wayland),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_session_from_command() {
+        let session = Session::from_command("/usr/local/bin/start-hyprland.sh --flag");
+        assert_eq!(session.name, "start-hyprland.sh");
+        assert_eq!(session.command, "/usr/local/bin/start-hyprland.sh --flag");
+    }
+}
@@ -0,0 +1,210 @@
+//! Greeter state that survives restarts
+//!
+//! Stored under `/run/grxxt` (tmpfs, created by the shipped tmpfiles.d entry)
+//! so it is gone after a reboot. Only the last user is kept in
+//! `/var/cache/grxxt`. Passwords are never written.
+
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const STATE_DIR: &str = "/run/grxxt";
+const DRAFT_FILE: &str = "draft";
+const LAUNCH_FILE: &str = "launch";
+const CACHE_DIR: &str = "/var/cache/grxxt";
+const LAST_USER_FILE: &str = "last-user";
+
+/// Set once state is neither read nor written
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Last session started from the greeter
+#[derive(Debug, PartialEq, Eq)]
+pub struct Launch {
+    pub command: String,
+    /// Quick failures of this command in a row before this launch
+    pub failures: u32,
+    /// Seconds since the epoch
+    at: u64,
+}
+
+impl Launch {
+    /// The greeter is back within `min_uptime` of the launch, so the session
+    /// most likely died on startup. A session seen to exit cleanly has no
+    /// launch left to ask.
+    pub fn crashed(&self, min_uptime: Duration) -> bool {
+        now().saturating_sub(self.at) < min_uptime.as_secs()
+    }
+}
+
+/// Leave the state files alone from now on (demo mode, theme previews)
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+fn enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed)
+}
+
+/// Username that was being typed when the previous greeter instance died
+pub fn load_draft() -> Option<String> {
+    if !enabled() {
+        return None;
+    }
+    read_draft(Path::new(STATE_DIR))
+}
+
+/// Remember the username being typed; an empty name removes the draft.
+/// Failures are ignored — restoring the draft is a convenience.
+pub fn save_draft(username: &str) {
+    if !enabled() {
+        return;
+    }
+    write_draft(Path::new(STATE_DIR), username);
+}
+
+/// Forget the draft (after a successful login)
+pub fn clear_draft() {
+    if !enabled() {
+        return;
+    }
+    write_draft(Path::new(STATE_DIR), "");
+}
+
+/// User of the last successful login
+pub fn load_last_user() -> Option<String> {
+    if !enabled() {
+        return None;
+    }
+    read_user(&Path::new(CACHE_DIR).join(LAST_USER_FILE))
+}
+
+/// Remember who logged in, for password-first mode
+pub fn save_last_user(username: &str) {
+    if !enabled() {
+        return;
+    }
+    write_user(&Path::new(CACHE_DIR).join(LAST_USER_FILE), username);
+}
+
+/// Session launched by the previous greeter instance
+pub fn load_launch() -> Option<Launch> {
+    if !enabled() {
+        return None;
+    }
+    read_launch(Path::new(STATE_DIR))
+}
+
+/// Remember the session being started, for crash-loop detection
+pub fn save_launch(command: &str, failures: u32) {
+    if !enabled() {
+        return;
+    }
+    write_launch(Path::new(STATE_DIR), command, failures);
+}
+
+/// The session exited cleanly: a logout, not a crash, however soon it came
+#[cfg(feature = "pam")]
+pub fn clear_launch() {
+    if !enabled() {
+        return;
+    }
+    fs::remove_file(Path::new(STATE_DIR).join(LAUNCH_FILE)).ok();
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// `<time> <failures> <command>`; the command may contain spaces
+fn read_launch(dir: &Path) -> Option<Launch> {
+    let content = fs::read_to_string(dir.join(LAUNCH_FILE)).ok()?;
+    let mut fields = content.trim_end().splitn(3, ' ');
+    let at = fields.next()?.parse().ok()?;
+    let failures = fields.next()?.parse().ok()?;
+    let command = fields.next().filter(|c| !c.is_empty())?.to_string();
+    Some(Launch {
+        command,
+        failures,
+        at,
+    })
+}
+
+fn write_launch(dir: &Path, command: &str, failures: u32) {
+    fs::write(
+        dir.join(LAUNCH_FILE),
+        format!("{} {failures} {command}\n", now()),
+    )
+    .ok();
+}
+
+fn read_draft(dir: &Path) -> Option<String> {
+    read_user(&dir.join(DRAFT_FILE))
+}
+
+fn write_draft(dir: &Path, username: &str) {
+    write_user(&dir.join(DRAFT_FILE), username);
+}
+
+fn read_user(path: &Path) -> Option<String> {
+    let user = fs::read_to_string(path).ok()?;
+    let user = user.trim();
+    (!user.is_empty()).then(|| user.to_string())
+}
+
+fn write_user(path: &Path, username: &str) {
+    if username.is_empty() {
+        fs::remove_file(path).ok();
+    } else {
+        fs::write(path, username).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draft_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("grxxt-state-{}", std::process::id()));
+        fs::create_dir_all(&dir).ok();
+
+        assert_eq!(read_draft(&dir), None);
+        write_draft(&dir, "sandman");
+        assert_eq!(read_draft(&dir).as_deref(), Some("sandman"));
+        write_draft(&dir, "");
+        assert_eq!(read_draft(&dir), None);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_launch_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("grxxt-launch-{}", std::process::id()));
+        fs::create_dir_all(&dir).ok();
+
+        assert_eq!(read_launch(&dir), None);
+        write_launch(&dir, "uwsm start hyprland.desktop", 2);
+        let launch = read_launch(&dir);
+        assert_eq!(
+            launch.as_ref().map(|l| l.command.as_str()),
+            Some("uwsm start hyprland.desktop")
+        );
+        assert_eq!(launch.as_ref().map(|l| l.failures), Some(2));
+        assert!(launch.is_some_and(|l| l.crashed(Duration::from_secs(30))));
+
+        let old = Launch {
+            command: "sway".to_string(),
+            failures: 0,
+            at: now() - 60,
+        };
+        assert!(!old.crashed(Duration::from_secs(30)));
+
+        fs::write(dir.join(LAUNCH_FILE), "garbage").ok();
+        assert_eq!(read_launch(&dir), None);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,229 @@
+//! System status indicators shown in the header
+//!
+//! Queried through systemd's command-line tools and refreshed on a slow
+//! interval; every query spawns one of them, so the event loop runs them as
+//! background tasks. Anything that can't be determined is simply not shown.
+
+use std::env;
+use std::fs;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// How often indicators are re-queried
+pub const REFRESH: Duration = Duration::from_secs(30);
+
+/// Current system status
+#[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools, reason = "independent indicators")]
+pub struct Status {
+    /// timedated reports the clock as not NTP-synchronized. Wrong clocks
+    /// break Kerberos/AD logins with otherwise opaque errors.
+    pub clock_unsynced: bool,
+    /// logind sessions of users logged in remotely (SSH), so nobody powers
+    /// off a machine someone else is using
+    pub remote_sessions: usize,
+    /// Problems found by the `[[checks]]`
+    pub health: Vec<String>,
+    /// Low disk space or memory, shown below the form
+    pub resources: Vec<String>,
+    /// systemd units in the failed state
+    pub failed_units: Vec<String>,
+    /// logind class of the greeter's own session, when it isn't `greeter`
+    pub session_class: Option<String>,
+    /// Every radio is switched off
+    pub airplane: bool,
+    /// No keyboard is connected
+    pub no_keyboard: bool,
+    /// greetd's socket isn't up yet
+    pub waiting_greetd: bool,
+}
+
+impl Status {
+    /// e.g. `2 remote sessions`
+    pub fn remote_label(&self) -> Option<String> {
+        match self.remote_sessions {
+            0 => None,
+            1 => Some("1 remote session".to_string()),
+            count => Some(format!("{count} remote sessions")),
+        }
+    }
+
+    /// e.g. `3 failed units`
+    pub fn failed_label(&self) -> Option<String> {
+        match self.failed_units.len() {
+            0 => None,
+            1 => Some("1 failed unit".to_string()),
+            count => Some(format!("{count} failed units")),
+        }
+    }
+}
+
+/// Id of the logind session grxxt runs in, which greetd opens with class
+/// `greeter`: `XDG_SESSION_ID`, else the session scope of its cgroup.
+/// Hooks get it as `GRXXT_SESSION_ID`.
+pub fn greeter_session() -> Option<&'static str> {
+    static SESSION: OnceLock<Option<String>> = OnceLock::new();
+    SESSION
+        .get_or_init(|| {
+            env::var("XDG_SESSION_ID")
+                .ok()
+                .filter(|id| !id.is_empty())
+                .or_else(|| session_from_cgroup(&fs::read_to_string("/proc/self/cgroup").ok()?))
+        })
+        .as_deref()
+}
+
+/// `c1` from `0::/user.slice/user-973.slice/session-c1.scope`
+fn session_from_cgroup(cgroup: &str) -> Option<String> {
+    cgroup.lines().find_map(|line| {
+        let (_, path) = line.rsplit_once(':')?;
+        let scope = path.rsplit('/').next()?;
+        let id = scope.strip_prefix("session-")?.strip_suffix(".scope")?;
+        Some(id.to_string())
+    })
+}
+
+/// Class of the greeter's session when it's anything but `greeter` (e.g.
+/// `user`, when greetd's PAM stack lacks `pam_systemd`)
+pub fn wrong_session_class() -> Option<String> {
+    let output = Command::new("loginctl")
+        .args(["show-session", greeter_session()?, "--property=Class", "--value"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let class = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !class.is_empty() && class != "greeter").then_some(class)
+}
+
+/// timedated's `NTPSynchronized` property
+pub fn ntp_synchronized() -> Option<bool> {
+    let output = Command::new("timedatectl")
+        .args(["show", "--property=NTPSynchronized", "--value"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_bool(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Units systemd lists as failed
+pub fn failed_units() -> Option<Vec<String>> {
+    let output = Command::new("systemctl")
+        .args(["list-units", "--state=failed", "--no-legend", "--plain", "--full"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(parse_units(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// First column of `systemctl list-units --plain`
+fn parse_units(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Number of logind sessions with `Remote=yes`
+pub fn remote_sessions() -> Option<usize> {
+    let list = Command::new("loginctl")
+        .args(["list-sessions", "--no-legend"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !list.status.success() {
+        return None;
+    }
+    let list = String::from_utf8_lossy(&list.stdout);
+    let ids: Vec<&str> = list
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    if ids.is_empty() {
+        return Some(0);
+    }
+    let output = Command::new("loginctl")
+        .arg("show-session")
+        .args(&ids)
+        .args(["--property=Remote", "--value"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    Some(count_remote(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// `loginctl show-session --value` prints one line per session
+fn count_remote(output: &str) -> usize {
+    output
+        .lines()
+        .filter(|line| parse_bool(line) == Some(true))
+        .count()
+}
+
+/// Parse a systemd boolean property value
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_from_cgroup() {
+        assert_eq!(
+            session_from_cgroup("0::/user.slice/user-973.slice/session-c1.scope\n").as_deref(),
+            Some("c1")
+        );
+        // cgroup v1 lists a line per controller
+        let v1 = "12:pids:/user.slice/user-973.slice/session-4.scope\n\
+                  1:name=systemd:/user.slice/user-973.slice/session-4.scope\n";
+        assert_eq!(session_from_cgroup(v1).as_deref(), Some("4"));
+        assert_eq!(session_from_cgroup("0::/system.slice/greetd.service\n"), None);
+    }
+
+    #[test]
+    fn test_parse_bool() {
+        assert_eq!(parse_bool("yes\n"), Some(true));
+        assert_eq!(parse_bool("no\n"), Some(false));
+        assert_eq!(parse_bool(""), None);
+    }
+
+    #[test]
+    fn test_remote_sessions() {
+        assert_eq!(count_remote("no\nyes\nyes\n"), 2);
+        assert_eq!(count_remote(""), 0);
+        let status = |remote_sessions| Status {
+            remote_sessions,
+            ..Status::default()
+        };
+        assert_eq!(status(0).remote_label(), None);
+        assert_eq!(status(1).remote_label().as_deref(), Some("1 remote session"));
+        assert_eq!(status(3).remote_label().as_deref(), Some("3 remote sessions"));
+    }
+
+    #[test]
+    fn test_failed_units() {
+        let output = "\
+nfs-mount.service loaded failed failed NFS mount
+backup.timer      loaded failed failed Nightly backup
+";
+        let status = Status {
+            failed_units: parse_units(output),
+            ..Status::default()
+        };
+        assert_eq!(status.failed_units, ["nfs-mount.service", "backup.timer"]);
+        assert_eq!(status.failed_label().as_deref(), Some("2 failed units"));
+        assert_eq!(Status::default().failed_label(), None);
+    }
+}
@@ -0,0 +1,88 @@
+//! Sticky keys: sequential modifier entry for chorded bindings
+//!
+//! With `sticky_keys = true`, pressing and releasing a modifier latches it
+//! for the next key, so Ctrl, then U acts like Ctrl+U. Pressing the same
+//! modifier again unlatches it. Lone modifier presses are only reported by
+//! terminals speaking the kitty keyboard protocol; the Linux console doesn't.
+
+use crate::input::{KeyCode, KeyEvent, Modifiers};
+
+/// Modifiers waiting for the next key
+#[derive(Debug, Default)]
+pub struct StickyKeys {
+    pub latched: Modifiers,
+}
+
+impl StickyKeys {
+    /// Feed a key press. Returns the event to dispatch, with latched
+    /// modifiers applied, or `None` when the press only (un)latched one.
+    pub fn process(&mut self, mut key: KeyEvent) -> Option<KeyEvent> {
+        if let KeyCode::Modifier(modifier) = key.code {
+            self.latched = self.latched.toggle(modifier);
+            return None;
+        }
+
+        let latched = std::mem::take(&mut self.latched);
+        if latched.contains(Modifiers::SHIFT) {
+            if let KeyCode::Char(c) = key.code {
+                key.code = KeyCode::Char(c.to_ascii_uppercase());
+            }
+        }
+        key.modifiers = key.modifiers.union(latched);
+        Some(key)
+    }
+
+    /// Latched modifiers for display, e.g. `CTRL+ALT`
+    pub fn label(&self) -> Option<String> {
+        let names: Vec<&str> = [
+            (Modifiers::CONTROL, "CTRL"),
+            (Modifiers::ALT, "ALT"),
+            (Modifiers::SHIFT, "SHIFT"),
+            (Modifiers::SUPER, "SUPER"),
+        ]
+        .iter()
+        .filter(|(modifier, _)| self.latched.contains(*modifier))
+        .map(|(_, name)| *name)
+        .collect();
+        (!names.is_empty()).then(|| names.join("+"))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, Modifiers::NONE)
+    }
+
+    #[test]
+    fn test_latched_modifier_applies_once() {
+        let mut sticky = StickyKeys::default();
+        assert!(sticky
+            .process(press(KeyCode::Modifier(Modifiers::CONTROL)))
+            .is_none());
+        assert_eq!(sticky.label().as_deref(), Some("CTRL"));
+
+        let key = sticky.process(press(KeyCode::Char('u'))).unwrap();
+        assert_eq!(key.modifiers, Modifiers::CONTROL);
+        assert_eq!(sticky.label(), None);
+
+        let key = sticky.process(press(KeyCode::Char('u'))).unwrap();
+        assert_eq!(key.modifiers, Modifiers::NONE);
+    }
+
+    #[test]
+    fn test_second_press_unlatches() {
+        let mut sticky = StickyKeys::default();
+        let shift = press(KeyCode::Modifier(Modifiers::SHIFT));
+        sticky.process(shift);
+        sticky.process(shift);
+        assert_eq!(sticky.latched, Modifiers::NONE);
+
+        sticky.process(shift);
+        let key = sticky.process(press(KeyCode::Char('a'))).unwrap();
+        assert_eq!(key.code, KeyCode::Char('A'));
+    }
+}
@@ -0,0 +1,118 @@
+//! Where a login attempt stands, from typing to the session starting
+//!
+//! The backend is called on a background thread while the screen keeps
+//! drawing, with a spinner, and keys pressed meanwhile (an impatient second
+//! Enter) are dropped; Esc cancels the attempt. Each step below is allowed
+//! from one state only, so one Enter is one attempt.
+
+use std::time::Duration;
+
+/// How often the spinner turns while the backend works
+pub const SPINNER_TICK: Duration = Duration::from_millis(150);
+
+/// Spinner frames; plain ASCII, which every console font has
+const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// State of the login attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Submission {
+    /// Filling in the form; Enter sends it
+    #[default]
+    Editing,
+    /// The backend is checking the credentials
+    Sent,
+    /// Credentials accepted; the session hasn't started (countdown)
+    Authenticated,
+    /// The session is starting; nothing more to do
+    Granted,
+}
+
+impl Submission {
+    /// Enter on a complete form. `false` while an attempt is under way.
+    pub fn send(&mut self) -> bool {
+        self.step(Self::Editing, Self::Sent)
+    }
+
+    /// The backend accepted the credentials
+    pub fn accept(&mut self) -> bool {
+        self.step(Self::Sent, Self::Authenticated)
+    }
+
+    /// The session of an accepted login started
+    pub fn grant(&mut self) -> bool {
+        self.step(Self::Authenticated, Self::Granted)
+    }
+
+    /// The backend refused, asked another question or couldn't start the
+    /// session, or the login was cancelled: back to the form
+    pub const fn reject(&mut self) {
+        if matches!(self, Self::Sent | Self::Authenticated) {
+            *self = Self::Editing;
+        }
+    }
+
+    fn step(&mut self, from: Self, to: Self) -> bool {
+        let allowed = *self == from;
+        if allowed {
+            *self = to;
+        }
+        allowed
+    }
+}
+
+/// Spinner frame `elapsed` after the attempt was sent
+pub fn spinner(elapsed: Duration) -> &'static str {
+    let frame = elapsed.as_millis() / SPINNER_TICK.as_millis();
+    SPINNER[usize::try_from(frame % 4).unwrap_or_default()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_attempt_at_a_time() {
+        let mut submission = Submission::default();
+        assert!(!submission.accept());
+        assert!(submission.send());
+        // A second Enter while the first is out
+        assert!(!submission.send());
+        submission.reject();
+        assert_eq!(submission, Submission::Editing);
+        assert!(submission.send());
+    }
+
+    #[test]
+    fn test_login() {
+        let mut submission = Submission::default();
+        assert!(!submission.grant());
+        assert!(submission.send());
+        assert!(!submission.grant());
+        assert!(submission.accept());
+        assert!(!submission.send());
+        assert!(!submission.accept());
+        assert!(submission.grant());
+        // Nothing undoes a started session
+        submission.reject();
+        assert_eq!(submission, Submission::Granted);
+        assert!(!submission.send());
+    }
+
+    #[test]
+    fn test_session_fails_to_start() {
+        let mut submission = Submission::default();
+        assert!(submission.send());
+        assert!(submission.accept());
+        submission.reject();
+        assert_eq!(submission, Submission::Editing);
+        assert!(!submission.grant());
+    }
+
+    #[test]
+    fn test_spinner() {
+        assert_eq!(spinner(Duration::ZERO), "|");
+        assert_eq!(spinner(SPINNER_TICK), "/");
+        assert_eq!(spinner(SPINNER_TICK * 3 + SPINNER_TICK / 2), "\\");
+        assert_eq!(spinner(SPINNER_TICK * 4), "|");
+    }
+}
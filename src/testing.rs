@@ -0,0 +1,27 @@
+//! Helpers shared by the unit tests
+
+use std::time::{Duration, Instant};
+
+/// Points in time after the start of a timer test
+#[derive(Debug, Clone, Copy)]
+pub struct Timeline {
+    pub start: Instant,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+
+    /// `secs` seconds after the start
+    pub fn secs(self, secs: u64) -> Instant {
+        self.start + Duration::from_secs(secs)
+    }
+
+    /// `millis` milliseconds after the start
+    pub fn millis(self, millis: u64) -> Instant {
+        self.start + Duration::from_millis(millis)
+    }
+}
@@ -1,8 +1,105 @@
 //! Zodiac brutalist theme for ratatui
+//!
+//! The theme is drawn in true color and brought down to what the terminal
+//! can show afterwards: the nearest of 256, 16 or 8 colors from terminfo's
+//! count, or plain bold and reverse text under `NO_COLOR` or on terminals
+//! without color (braille displays, serial consoles).
 
-use ratatui::style::Color;
+use std::borrow::Cow;
+use std::env;
+use std::process::{Command, Stdio};
 
-use crate::config::ThemeConfig;
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::{Color, Modifier};
+
+use crate::config::{
+    AvatarBorder, AvatarStyle, BorderSet, FocusIndicator, InputStyle, MessageTransform,
+    ScrollbarStyle, ThemeConfig,
+};
+
+/// Colors the terminal can show
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Palette {
+    /// No color; accents become bold, highlights reverse video
+    Mono,
+    Ansi8,
+    Ansi16,
+    Ansi256,
+    #[default]
+    TrueColor,
+}
+
+impl Palette {
+    /// From `NO_COLOR`, `COLORTERM` and terminfo's color count for `TERM`.
+    /// Spawns `tput`.
+    pub fn detect() -> Self {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        if no_color {
+            return Self::Mono;
+        }
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        Self::from_terminal(&colorterm, &term, terminfo_colors)
+    }
+
+    fn from_terminal(colorterm: &str, term: &str, colors: impl FnOnce() -> Option<i32>) -> Self {
+        if matches!(colorterm, "truecolor" | "24bit") {
+            return Self::TrueColor;
+        }
+        if term == "dumb" {
+            return Self::Mono;
+        }
+        // Without terminfo, trust the terminal as before
+        match colors() {
+            None => Self::TrueColor,
+            Some(256..) => Self::Ansi256,
+            Some(16..=255) => Self::Ansi16,
+            Some(8..=15) => Self::Ansi8,
+            Some(_) => Self::Mono,
+        }
+    }
+}
+
+/// terminfo's `colors` for `TERM`; -1 when the terminal has none
+fn terminfo_colors() -> Option<i32> {
+    let output = Command::new("tput")
+        .arg("colors")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// xterm's values of the 16 ANSI colors
+const ANSI: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+/// Channel levels of the 6×6×6 cube in the 256-color palette
+const CUBE: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+
+/// WCAG's minimum contrast ratio for bold text and interface parts; the
+/// theme's colors are mostly used for those
+const MIN_CONTRAST: f64 = 3.0;
 
 /// Theme colors for the TUI
 #[derive(Debug, Clone)]
@@ -11,6 +108,24 @@ pub struct Theme {
     pub foreground: Color,
     pub accent: Color,
     pub error: Color,
+    pub palette: Palette,
+    /// Maximum rows of the message area (at least 1)
+    pub message_lines: u16,
+    pub message_transform: MessageTransform,
+    /// Large text mode: big clock digits and, on a VT, a large console font
+    pub large_text: bool,
+    pub large_font: String,
+    pub input_style: InputStyle,
+    pub focus_indicator: FocusIndicator,
+    pub scrollbar: ScrollbarStyle,
+    /// Border sets of the input fields, the popups and the avatar frame
+    /// (`None`: no frame)
+    pub input_border: BorderSet,
+    pub popup_border: BorderSet,
+    pub avatar_border: Option<BorderSet>,
+    pub avatar: AvatarStyle,
+    /// Mirrored layout for right-to-left languages
+    pub rtl: bool,
 }
 
 impl Default for Theme {
@@ -20,6 +135,19 @@ impl Default for Theme {
             foreground: Color::Rgb(0xf6, 0xf1, 0xe3),
             accent: Color::Rgb(0xf1, 0xc3, 0x5f),
             error: Color::Rgb(0xd1, 0x4b, 0x64),
+            palette: Palette::TrueColor,
+            message_lines: 3,
+            message_transform: MessageTransform::Upper,
+            large_text: false,
+            large_font: "ter-v32b".to_string(),
+            input_style: InputStyle::Box,
+            focus_indicator: FocusIndicator::Color,
+            scrollbar: ScrollbarStyle::Bar,
+            input_border: BorderSet::Plain,
+            popup_border: BorderSet::Plain,
+            avatar_border: Some(BorderSet::Plain),
+            avatar: AvatarStyle::default(),
+            rtl: false,
         }
     }
 }
@@ -33,10 +161,260 @@ impl From<&ThemeConfig> for Theme {
                 .unwrap_or(Color::Rgb(0xf6, 0xf1, 0xe3)),
             accent: parse_hex_color(&config.accent).unwrap_or(Color::Rgb(0xf1, 0xc3, 0x5f)),
             error: parse_hex_color(&config.error).unwrap_or(Color::Rgb(0xd1, 0x4b, 0x64)),
+            palette: Palette::TrueColor,
+            message_lines: config.message_lines.max(1),
+            message_transform: config.message_transform,
+            large_text: config.large_text,
+            large_font: config.large_font.clone(),
+            input_style: config.input_style,
+            focus_indicator: config.focus_indicator,
+            scrollbar: config.scrollbar,
+            input_border: config.borders.input.unwrap_or(config.border),
+            popup_border: config.borders.popup.unwrap_or(config.border),
+            avatar_border: config
+                .avatar
+                .border
+                .map_or(Some(config.border), AvatarBorder::set),
+            avatar: config.avatar,
+            rtl: false,
         }
     }
 }
 
+impl Theme {
+    /// Error message as displayed; the stored message keeps its original case
+    #[must_use]
+    pub fn message<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.message_transform {
+            MessageTransform::Upper => Cow::Owned(text.to_uppercase()),
+            MessageTransform::None => Cow::Borrowed(text),
+            MessageTransform::Lower => Cow::Owned(text.to_lowercase()),
+        }
+    }
+
+    /// Text and background pairs that are hard to read, e.g. `error on
+    /// background: contrast 2.1:1`
+    #[must_use]
+    pub fn contrast_warnings(&self) -> Vec<String> {
+        let mut pairs = vec![
+            ("foreground", self.foreground, "background", self.background),
+            ("accent", self.accent, "background", self.background),
+            ("error", self.error, "background", self.background),
+        ];
+        // Filled fields draw the text in the background color on a bar
+        if self.input_style == InputStyle::Filled {
+            pairs.push(("background", self.background, "foreground", self.foreground));
+            pairs.push(("background", self.background, "accent", self.accent));
+        }
+        pairs
+            .into_iter()
+            .filter_map(|(text, text_color, bg, bg_color)| {
+                let ratio = contrast(text_color, bg_color)?;
+                (ratio < MIN_CONTRAST).then(|| {
+                    format!("{text} on {bg}: contrast {ratio:.1}:1, below {MIN_CONTRAST}:1")
+                })
+            })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn with_palette(self, palette: Palette) -> Self {
+        Self { palette, ..self }
+    }
+
+    /// ASCII borders everywhere unless the font has box-drawing characters
+    #[must_use]
+    pub fn with_box_drawing(self, available: bool) -> Self {
+        if available {
+            return self;
+        }
+        Self {
+            input_border: BorderSet::Ascii,
+            popup_border: BorderSet::Ascii,
+            avatar_border: self.avatar_border.map(|_| BorderSet::Ascii),
+            ..self
+        }
+    }
+
+    /// Layout mirrored for right-to-left text
+    #[must_use]
+    pub fn with_rtl(self, rtl: bool) -> Self {
+        Self { rtl, ..self }
+    }
+
+    /// Part of the way from the background to a drawn frame, `level`
+    /// thousandths of it. Colors that can't be blended are dimmed for the
+    /// first half.
+    pub fn fade(&self, buffer: &mut Buffer, level: u32) {
+        let blend = matches!(self.palette, Palette::TrueColor | Palette::Ansi256);
+        for cell in &mut buffer.content {
+            match (blend, mix(self.background, cell.fg, level)) {
+                (true, Some(fg)) => cell.fg = fg,
+                _ if level < 500 => cell.modifier.insert(Modifier::DIM),
+                _ => {}
+            }
+            if blend {
+                cell.bg = mix(self.background, cell.bg, level).unwrap_or(cell.bg);
+            }
+        }
+    }
+
+    /// Bring a drawn frame down to the terminal's palette
+    pub fn degrade(&self, buffer: &mut Buffer) {
+        if self.palette == Palette::TrueColor {
+            return;
+        }
+        for cell in &mut buffer.content {
+            if self.palette == Palette::Mono {
+                self.monochrome(cell);
+            } else {
+                cell.fg = nearest(cell.fg, self.palette);
+                cell.bg = nearest(cell.bg, self.palette);
+            }
+        }
+    }
+
+    /// RGB value of a cell's color; the terminal's own colors are taken to
+    /// be the theme's
+    #[cfg(feature = "screenshot")]
+    #[must_use]
+    pub fn rgb(&self, color: Color, foreground: bool) -> [u8; 3] {
+        let ansi = |index: usize| ANSI.get(index).map_or([0; 3], |&rgb| rgb.into());
+        match color {
+            Color::Reset if foreground && self.foreground != Color::Reset => {
+                self.rgb(self.foreground, true)
+            }
+            Color::Reset if !foreground && self.background != Color::Reset => {
+                self.rgb(self.background, false)
+            }
+            Color::Reset => ansi(if foreground { 7 } else { 0 }),
+            Color::Rgb(r, g, b) => [r, g, b],
+            Color::Indexed(index @ 0..16) => ansi(usize::from(index)),
+            Color::Indexed(index @ 16..232) => {
+                let level = |n: u8| CUBE[usize::from(n % 6)];
+                let n = index - 16;
+                [level(n / 36), level(n / 6), level(n)]
+            }
+            Color::Indexed(index) => [8 + (index - 232) * 10; 3],
+            Color::Black => ansi(0),
+            Color::Red => ansi(1),
+            Color::Green => ansi(2),
+            Color::Yellow => ansi(3),
+            Color::Blue => ansi(4),
+            Color::Magenta => ansi(5),
+            Color::Cyan => ansi(6),
+            Color::Gray => ansi(7),
+            Color::DarkGray => ansi(8),
+            Color::LightRed => ansi(9),
+            Color::LightGreen => ansi(10),
+            Color::LightYellow => ansi(11),
+            Color::LightBlue => ansi(12),
+            Color::LightMagenta => ansi(13),
+            Color::LightCyan => ansi(14),
+            Color::White => ansi(15),
+        }
+    }
+
+    /// Colored text turns bold and colored bars (filled fields) reverse
+    fn monochrome(&self, cell: &mut Cell) {
+        if cell.bg != Color::Reset && cell.bg != self.background {
+            cell.modifier.insert(Modifier::REVERSED);
+        } else if cell.fg == self.accent || cell.fg == self.error {
+            cell.modifier.insert(Modifier::BOLD);
+        }
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
+    }
+}
+
+/// The closest color of `palette` to an RGB color; others are kept
+fn nearest(color: Color, palette: Palette) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    let closest = |colors: &[(u8, u8, u8)]| {
+        (0u8..)
+            .zip(colors)
+            .min_by_key(|&(_, &rgb)| distance((r, g, b), rgb))
+            .map_or(0, |(index, _)| index)
+    };
+    match palette {
+        Palette::Ansi8 => Color::Indexed(closest(&ANSI[..8])),
+        Palette::Ansi16 => Color::Indexed(closest(&ANSI)),
+        Palette::Ansi256 => cube_or_gray(r, g, b),
+        Palette::Mono | Palette::TrueColor => color,
+    }
+}
+
+/// The closer of the nearest cube color and the nearest gray of the
+/// 256-color palette
+fn cube_or_gray(r: u8, g: u8, b: u8) -> Color {
+    let level = |channel: u8| {
+        (0u8..)
+            .zip(CUBE)
+            .min_by_key(|&(_, level)| level.abs_diff(channel))
+            .map_or(0, |(index, _)| index)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (
+        CUBE[usize::from(ri)],
+        CUBE[usize::from(gi)],
+        CUBE[usize::from(bi)],
+    );
+    // 24 grays from 8 to 238 in steps of 10
+    let average = (u16::from(r) + u16::from(g) + u16::from(b)) / 3;
+    let step = u8::try_from((average.saturating_sub(3) / 10).min(23)).unwrap_or(23);
+    let gray = 8 + step * 10;
+    if distance((r, g, b), (gray, gray, gray)) < distance((r, g, b), cube) {
+        Color::Indexed(232 + step)
+    } else {
+        Color::Indexed(16 + 36 * ri + 6 * gi + bi)
+    }
+}
+
+/// `level` thousandths of the way from one RGB color to another
+fn mix(from: Color, to: Color, level: u32) -> Option<Color> {
+    let (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) = (from, to) else {
+        return None;
+    };
+    let level = level.min(1000);
+    let channel = |a: u8, b: u8| {
+        let mixed = (u32::from(a) * (1000 - level) + u32::from(b) * level) / 1000;
+        u8::try_from(mixed).unwrap_or(u8::MAX)
+    };
+    Some(Color::Rgb(channel(r1, r2), channel(g1, g2), channel(b1, b2)))
+}
+
+/// WCAG contrast ratio of two RGB colors, 1 to 21
+fn contrast(a: Color, b: Color) -> Option<f64> {
+    let (a, b) = (luminance(a)?, luminance(b)?);
+    Some((a.max(b) + 0.05) / (a.min(b) + 0.05))
+}
+
+/// WCAG relative luminance of an RGB color
+fn luminance(color: Color) -> Option<f64> {
+    let Color::Rgb(r, g, b) = color else {
+        return None;
+    };
+    let linear = |channel: u8| {
+        let c = f64::from(channel) / 255.0;
+        if c <= 0.039_28 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    Some(0.0722f64.mul_add(linear(b), 0.2126f64.mul_add(linear(r), 0.7152 * linear(g))))
+}
+
+/// Squared distance of two RGB colors
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    [(r1, r2), (g1, g2), (b1, b2)]
+        .iter()
+        .map(|&(a, b)| u32::from(a.abs_diff(b)).pow(2))
+        .sum()
+}
+
 /// Parse a hex color string like "#0b0a13" into a ratatui Color
 fn parse_hex_color(s: &str) -> Option<Color> {
     let s = s.strip_prefix('#')?;
@@ -64,4 +442,114 @@ mod tests {
         assert_eq!(parse_hex_color("invalid"), None);
         assert_eq!(parse_hex_color("#fff"), None);
     }
+
+    #[test]
+    fn test_palette_detection() {
+        let detect = |colorterm, term, colors| Palette::from_terminal(colorterm, term, || colors);
+        assert_eq!(detect("truecolor", "xterm-256color", Some(256)), Palette::TrueColor);
+        assert_eq!(detect("", "xterm-256color", Some(256)), Palette::Ansi256);
+        assert_eq!(detect("", "linux", Some(8)), Palette::Ansi8);
+        assert_eq!(detect("", "vt100", Some(-1)), Palette::Mono);
+        assert_eq!(detect("", "dumb", None), Palette::Mono);
+        // No terminfo entry: colors as before
+        assert_eq!(detect("", "foot", None), Palette::TrueColor);
+    }
+
+    #[test]
+    fn test_nearest_colors() {
+        let theme = Theme::default();
+        assert_eq!(nearest(theme.accent, Palette::Ansi256), Color::Indexed(215));
+        assert_eq!(nearest(theme.background, Palette::Ansi256), Color::Indexed(233));
+        assert_eq!(nearest(theme.error, Palette::Ansi8), Color::Indexed(1));
+        assert_eq!(nearest(theme.foreground, Palette::Ansi16), Color::Indexed(7));
+        assert_eq!(nearest(Color::Reset, Palette::Ansi8), Color::Reset);
+    }
+
+    #[test]
+    fn test_contrast() {
+        let black = Color::Rgb(0, 0, 0);
+        let white = Color::Rgb(0xff, 0xff, 0xff);
+        assert!((contrast(black, white).unwrap() - 21.0).abs() < 0.01);
+        assert!((contrast(white, white).unwrap() - 1.0).abs() < 0.01);
+        assert_eq!(contrast(Color::Reset, white), None);
+
+        assert!(Theme::default().contrast_warnings().is_empty());
+        let theme = Theme {
+            error: Color::Rgb(0x30, 0x20, 0x30),
+            ..Theme::default()
+        };
+        assert_eq!(
+            theme.contrast_warnings(),
+            ["error on background: contrast 1.3:1, below 3:1"]
+        );
+    }
+
+    #[test]
+    fn test_fade() {
+        let theme = Theme::default();
+        let mut buffer = Buffer::empty(ratatui::layout::Rect::new(0, 0, 2, 1));
+        buffer[(0, 0)].set_fg(Color::Rgb(0xff, 0xff, 0xff)).set_bg(theme.background);
+        theme.fade(&mut buffer, 250);
+        assert_eq!(buffer[(0, 0)].fg, Color::Rgb(0x48, 0x47, 0x4e));
+        assert_eq!(buffer[(0, 0)].bg, theme.background);
+        assert!(!buffer[(0, 0)].modifier.contains(Modifier::DIM));
+        // Reset can't be blended
+        assert!(buffer[(1, 0)].modifier.contains(Modifier::DIM));
+
+        let theme = theme.with_palette(Palette::Ansi16);
+        let mut buffer = Buffer::empty(ratatui::layout::Rect::new(0, 0, 1, 1));
+        buffer[(0, 0)].set_fg(theme.foreground);
+        theme.fade(&mut buffer, 600);
+        assert_eq!(buffer[(0, 0)].fg, theme.foreground);
+        assert!(!buffer[(0, 0)].modifier.contains(Modifier::DIM));
+    }
+
+    #[test]
+    fn test_monochrome() {
+        let theme = Theme {
+            palette: Palette::Mono,
+            ..Theme::default()
+        };
+        let mut buffer = Buffer::empty(ratatui::layout::Rect::new(0, 0, 3, 1));
+        buffer[(0, 0)].set_fg(theme.accent).set_bg(theme.background);
+        buffer[(1, 0)].set_fg(theme.background).set_bg(theme.accent);
+        buffer[(2, 0)].set_fg(theme.foreground).set_bg(theme.background);
+        theme.degrade(&mut buffer);
+        assert_eq!(buffer[(0, 0)].modifier, Modifier::BOLD);
+        assert_eq!(buffer[(1, 0)].modifier, Modifier::REVERSED);
+        assert_eq!(buffer[(2, 0)].modifier, Modifier::empty());
+        assert!(buffer
+            .content
+            .iter()
+            .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+    }
+
+    #[test]
+    fn test_message_transform() {
+        let mut theme = Theme::default();
+        assert_eq!(theme.message("Authentication failed"), "AUTHENTICATION FAILED");
+        theme.message_transform = MessageTransform::None;
+        assert_eq!(theme.message("Passwort abgelaufen: ändern"), "Passwort abgelaufen: ändern");
+        theme.message_transform = MessageTransform::Lower;
+        assert_eq!(theme.message("PAM Error"), "pam error");
+    }
+
+    #[test]
+    fn test_border_sets() {
+        let toml = r#"
+border = "double"
+[borders]
+input = "heavy"
+[avatar]
+border = "none"
+"#;
+        let theme = Theme::from(&toml::from_str::<ThemeConfig>(toml).unwrap());
+        assert_eq!(theme.input_border, BorderSet::Thick);
+        assert_eq!(theme.popup_border, BorderSet::Double);
+        assert_eq!(theme.avatar_border, None);
+
+        let theme = Theme::default().with_box_drawing(false);
+        assert_eq!(theme.input_border, BorderSet::Ascii);
+        assert_eq!(theme.avatar_border, Some(BorderSet::Ascii));
+    }
 }
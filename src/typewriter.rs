@@ -0,0 +1,88 @@
+//! Typewriter reveal of the greeting
+//!
+//! With `[greeting] typewriter` set, the greeting is typed out a character
+//! every that many milliseconds behind a `_` cursor, at startup and again
+//! when a key wakes a screen that was dimmed or running an idle effect.
+
+use std::time::{Duration, Instant};
+
+use crate::config::GreetingConfig;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Typewriter {
+    /// Time per character
+    pace: Duration,
+    /// Start of the running reveal; `None` once the text is complete
+    started: Option<Instant>,
+}
+
+impl Typewriter {
+    /// `None` unless enabled; the first reveal starts at `now`
+    pub fn new(options: &GreetingConfig, now: Instant) -> Option<Self> {
+        (options.enabled && options.typewriter > 0).then(|| Self {
+            pace: Duration::from_millis(options.typewriter),
+            started: Some(now),
+        })
+    }
+
+    /// Type the text out again
+    pub const fn restart(&mut self, now: Instant) {
+        self.started = Some(now);
+    }
+
+    /// `text` as far as it is typed at `now`, with the cursor while typing
+    pub fn reveal(&mut self, text: &str, now: Instant) -> String {
+        let typed = self.started.and_then(|started| {
+            let chars = now.duration_since(started).as_nanos() / self.pace.as_nanos();
+            usize::try_from(chars)
+                .ok()
+                .filter(|&chars| chars < text.chars().count())
+        });
+        let Some(typed) = typed else {
+            self.started = None;
+            return text.to_string();
+        };
+        let mut shown: String = text.chars().take(typed).collect();
+        shown.push('_');
+        shown
+    }
+
+    /// Time until the next character, while typing
+    pub fn next_frame(&self, now: Instant) -> Option<Duration> {
+        let elapsed = now.duration_since(self.started?).as_nanos() % self.pace.as_nanos();
+        let elapsed = Duration::from_nanos(u64::try_from(elapsed).unwrap_or_default());
+        Some(self.pace.saturating_sub(elapsed))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    use crate::testing::Timeline;
+
+    #[test]
+    fn test_typewriter() {
+        let time = Timeline::new();
+        let mut options = GreetingConfig {
+            enabled: true,
+            ..GreetingConfig::default()
+        };
+        assert!(Typewriter::new(&options, time.start).is_none());
+
+        options.typewriter = 50;
+        let mut typewriter = Typewriter::new(&options, time.start).unwrap();
+        assert_eq!(typewriter.reveal("hello", time.start), "_");
+        assert_eq!(typewriter.reveal("hello", time.millis(120)), "he_");
+        assert_eq!(
+            typewriter.next_frame(time.millis(120)),
+            Some(Duration::from_millis(30))
+        );
+        assert_eq!(typewriter.reveal("hello", time.millis(250)), "hello");
+        assert_eq!(typewriter.next_frame(time.millis(250)), None);
+
+        typewriter.restart(time.millis(1000));
+        assert_eq!(typewriter.reveal("hello", time.millis(1160)), "hel_");
+    }
+}
@@ -1,20 +1,67 @@
 //! TUI rendering logic
 
+use std::borrow::Cow;
+use std::time::Instant;
+
 use chrono::Local;
 use ratatui::{
-    layout::{Alignment, Constraint, Layout, Rect},
-    style::{Modifier, Style},
+    layout::{Alignment, Constraint, Layout, Margin, Rect},
+    style::{Color, Modifier, Style},
+    symbols::border,
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{
+        Block, Borders, Clear, Padding, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
+    },
     Frame,
 };
-use ratatui_image::{Resize, StatefulImage};
 
-use crate::app::{App, Focus};
+use unicode_width::UnicodeWidthChar;
+
+use crate::app::{App, UsernameField};
+use crate::bindings::Action as KeyAction;
+use crate::config::{BorderSet, FocusIndicator, InputStyle, ScrollbarStyle};
+use crate::focus::{Context, Field};
+use crate::handoff::Handoff;
+use crate::power::Action;
+use crate::scroll::Scroll;
+use crate::sticky::StickyKeys;
+use crate::submission::{self, Submission};
+use crate::theme::Theme;
+use crate::validate::Hint;
 
 /// Complement of the golden ratio (1 - 1/φ ≈ 0.382)
 const PHI_COMP: f32 = 0.382;
 
+/// Borders for console fonts without box-drawing characters
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// Rows of a big clock glyph
+const BIG_ROWS: usize = 5;
+
+/// Block glyphs for the large text clock: digits 0-9, then `:`
+const BIG_GLYPHS: [[&str; BIG_ROWS]; 11] = [
+    ["███", "█ █", "█ █", "█ █", "███"],
+    [" █ ", "██ ", " █ ", " █ ", "███"],
+    ["███", "  █", "███", "█  ", "███"],
+    ["███", "  █", "███", "  █", "███"],
+    ["█ █", "█ █", "███", "  █", "  █"],
+    ["███", "█  ", "███", "  █", "███"],
+    ["███", "█  ", "███", "█ █", "███"],
+    ["███", "  █", "  █", "  █", "  █"],
+    ["███", "█ █", "███", "█ █", "███"],
+    ["███", "█ █", "███", "  █", "███"],
+    [" ", "█", " ", "█", " "],
+];
+
 /// Render the entire UI
 pub fn render(frame: &mut Frame, app: &mut App) {
     let area = frame.area();
@@ -24,15 +71,100 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     let bg = Block::default().style(Style::default().bg(theme.background));
     frame.render_widget(bg, area);
 
-    // Layout: header at top, form centered
-    let chunks = Layout::vertical([
-        Constraint::Length(3), // Header
-        Constraint::Min(0),    // Main content
-    ])
-    .split(area);
+    let now = Instant::now();
+    let area = app
+        .burn_in
+        .as_ref()
+        .map_or(area, |burn_in| burn_in.shift(area, now));
+
+    // The splash replaces the greeter once the login went through
+    let mut fields = Rect::default();
+    if let Some(logo) = app.splash.as_deref().filter(|_| app.submission == Submission::Granted) {
+        render_splash(frame, app, area, logo);
+    } else {
+        // Layout: header at top, form centered. Large text needs room for
+        // the big clock (5 rows + margins).
+        let header_height = if theme.large_text { 7 } else { 3 };
+        let chunks = Layout::vertical([
+            Constraint::Length(header_height), // Header
+            Constraint::Min(0),                // Main content
+        ])
+        .split(area);
+
+        render_header(frame, app, chunks[0]);
+        fields = render_form(frame, app, chunks[1]);
+        if app.focus.top() == Context::FailedUnits {
+            render_failed_units(frame, app, area);
+        }
+    }
+    if let Some(ref mut screensaver) = app.screensaver {
+        screensaver.draw(frame.buffer_mut(), fields, &app.theme);
+    }
+    if let Some(level) = app.fade.as_mut().and_then(|fade| fade.level(now)) {
+        app.theme.fade(frame.buffer_mut(), level);
+    }
+    app.theme.degrade(frame.buffer_mut());
+
+    if app.burn_in.as_ref().is_some_and(|burn_in| burn_in.dimmed(now)) {
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        let full = frame.area();
+        frame.buffer_mut().set_style(full, dim);
+    }
+}
+
+/// Header buttons: action, the key action that runs it, and icon
+const POWER_BUTTONS: [(Action, KeyAction, &str); 4] = [
+    (Action::Shutdown, KeyAction::Shutdown, "⏻"),
+    (Action::Reboot, KeyAction::Reboot, "󰜉"),
+    (Action::Suspend, KeyAction::Suspend, "󰤄"),
+    (Action::SuspendThenHibernate, KeyAction::SuspendThenHibernate, "󰒲"),
+];
 
-    render_header(frame, app, chunks[0]);
-    render_form(frame, app, chunks[1]);
+/// In front of a locked username
+const LOCK: &str = "";
+
+/// Status indicators after the clock
+fn status_spans(app: &App) -> Vec<Span<'static>> {
+    let theme = &app.theme;
+    let mut spans = Vec::new();
+    if app.status.clock_unsynced {
+        spans.push(Span::styled(
+            "  clock not synced",
+            Style::default().fg(theme.error).add_modifier(Modifier::DIM),
+        ));
+    }
+    if let Some(ref class) = app.status.session_class {
+        spans.push(Span::styled(
+            format!("  session class {class}"),
+            Style::default().fg(theme.error).add_modifier(Modifier::DIM),
+        ));
+    }
+    if app.status.airplane {
+        spans.push(Span::styled(
+            "  airplane mode",
+            Style::default().fg(theme.foreground).add_modifier(Modifier::DIM),
+        ));
+    }
+    if let Some(remote) = app.status.remote_label() {
+        spans.push(Span::styled(
+            format!("  {remote} active"),
+            Style::default().fg(theme.foreground).add_modifier(Modifier::DIM),
+        ));
+    }
+    if let Some(failed) = app.status.failed_label() {
+        let key = app.keymap.label(KeyAction::FailedUnits);
+        spans.push(Span::styled(
+            format!("  {failed}{}", key.map(|key| format!(" [{key}]")).unwrap_or_default()),
+            Style::default().fg(theme.error),
+        ));
+    }
+    for problem in &app.status.health {
+        spans.push(Span::styled(
+            format!("  ⚠ {problem}"),
+            Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+        ));
+    }
+    spans
 }
 
 /// Render the header with clock and power buttons
@@ -40,64 +172,312 @@ fn render_header(frame: &mut Frame, app: &App, area: Rect) {
     let theme = &app.theme;
     let time = Local::now();
 
-    // Split header into left (clock) and right (power buttons)
-    let chunks = Layout::horizontal([
-        Constraint::Ratio(618, 1000), // Clock (φ⁻¹)
-        Constraint::Ratio(382, 1000), // Power buttons (1 - φ⁻¹)
-    ])
-    .split(area);
+    // Split header into left (clock) and right (power buttons), the other
+    // way around right to left
+    let clock_width = Constraint::Ratio(618, 1000); // φ⁻¹
+    let power_width = Constraint::Ratio(382, 1000); // 1 - φ⁻¹
+    let (clock_area, power_area) = if theme.rtl {
+        let chunks = Layout::horizontal([power_width, clock_width]).split(area);
+        (chunks[1], chunks[0])
+    } else {
+        let chunks = Layout::horizontal([clock_width, power_width]).split(area);
+        (chunks[0], chunks[1])
+    };
 
     // Clock
-    let clock_time = time.format("%H:%M").to_string();
-    let clock_date = time.format("%a %d %b").to_string().to_uppercase();
-    let clock = Paragraph::new(vec![
-        Line::from(Span::styled(
+    let clock_time = app.clock.time(&time);
+    let clock_date = app.clock.date(&time);
+    // Secondary clocks go side by side after the time
+    let extra: Vec<Span> = app
+        .clock
+        .extra(&time)
+        .into_iter()
+        .map(|clock| Span::styled(format!("   {clock}"), Style::default().fg(theme.foreground)))
+        .collect();
+    let clock_lines = if theme.large_text {
+        let mut lines: Vec<Line> = big_text(&clock_time)
+            .into_iter()
+            .map(|row| Line::from(Span::styled(row, Style::default().fg(theme.foreground))))
+            .collect();
+        // AM/PM in 12-hour locales follows the digits as normal text
+        if let Some(last) = lines.last_mut() {
+            let period = big_text_rest(&clock_time);
+            if !period.is_empty() {
+                last.spans.push(Span::styled(
+                    format!(" {period}"),
+                    Style::default().fg(theme.foreground).add_modifier(Modifier::BOLD),
+                ));
+            }
+            last.spans.extend(extra);
+            last.spans.extend(status_spans(app));
+        }
+        lines
+    } else {
+        let mut clock_spans = vec![Span::styled(
             clock_time,
             Style::default().fg(theme.foreground).add_modifier(Modifier::BOLD),
-        )),
-        Line::from(Span::styled(
-            clock_date,
-            Style::default().fg(theme.foreground),
-        )),
-    ])
-    .alignment(Alignment::Left)
+        )];
+        clock_spans.extend(extra);
+        clock_spans.extend(status_spans(app));
+        vec![
+            Line::from(clock_spans),
+            Line::from(Span::styled(
+                clock_date,
+                Style::default().fg(theme.foreground),
+            )),
+        ]
+    };
+    let clock = Paragraph::new(clock_lines)
+    .alignment(start(theme))
     .block(Block::default().style(Style::default().bg(theme.background)));
 
-    frame.render_widget(clock, add_margin(chunks[0], 2, 1));
-
-    // Power buttons
-    let power = Paragraph::new(Line::from(vec![
-        Span::styled("[F1] ", Style::default().fg(theme.foreground)),
-        Span::styled("⏻ ", Style::default().fg(theme.accent)),
-        Span::styled("[F2] ", Style::default().fg(theme.foreground)),
-        Span::styled("󰜉 ", Style::default().fg(theme.accent)),
-        Span::styled("[F3] ", Style::default().fg(theme.foreground)),
-        Span::styled("󰤄", Style::default().fg(theme.accent)),
-    ]))
-    .alignment(Alignment::Right)
-    .block(Block::default().style(Style::default().bg(theme.background)));
+    frame.render_widget(clock, add_margin(clock_area, 2, 1));
+
+    // Input modes, latched sticky modifiers, session selector (only when
+    // there is a choice) and power buttons
+    let mut spans = Vec::new();
+    let modes = [
+        app.kana.as_ref().map(|kana| kana.label().to_string()),
+        app.compose.label().map(str::to_string),
+        app.sticky
+            .as_ref()
+            .and_then(StickyKeys::label)
+            .map(|latched| format!("{latched}+")),
+    ];
+    for mode in modes.into_iter().flatten() {
+        spans.push(Span::styled(
+            format!("{mode}  "),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.policy.session_picker && app.sessions.len() > 1 {
+        if let Some(key) = app.keymap.label(KeyAction::NextSession) {
+            spans.push(Span::styled(
+                format!("[{key}] "),
+                Style::default().fg(theme.foreground),
+            ));
+        }
+        let session = app.session();
+        spans.push(Span::styled(
+            format!("{} ", session.name),
+            Style::default().fg(theme.accent),
+        ));
+        spans.push(Span::styled(
+            format!(" {} ", session.kind.badge()),
+            Style::default().fg(theme.foreground).add_modifier(Modifier::REVERSED),
+        ));
+        spans.push(Span::raw("  "));
+    }
+    spans.extend(power_buttons(app));
+    let end = if theme.rtl { Alignment::Left } else { Alignment::Right };
+    let power = Paragraph::new(Line::from(spans))
+        .alignment(end)
+        .block(Block::default().style(Style::default().bg(theme.background)));
+
+    frame.render_widget(power, add_margin(power_area, 2, 1));
+}
+
+/// Where lines of text start: the left, or the right for right-to-left
+const fn start(theme: &Theme) -> Alignment {
+    if theme.rtl { Alignment::Right } else { Alignment::Left }
+}
 
-    frame.render_widget(power, add_margin(chunks[1], 2, 1));
+/// The power buttons the policy allows; reboot names the system it starts
+/// when one was picked with F6
+fn power_buttons(app: &App) -> Vec<Span<'_>> {
+    let theme = &app.theme;
+    let allowed = POWER_BUTTONS
+        .into_iter()
+        .filter(|&(action, _, _)| app.policy.allows_power(action));
+    let mut spans = Vec::new();
+    for (i, (action, key_action, icon)) in allowed.enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" "));
+        }
+        if let Some(key) = app.keymap.label(key_action) {
+            spans.push(Span::styled(
+                format!("[{key}] "),
+                Style::default().fg(theme.foreground),
+            ));
+        }
+        spans.push(Span::styled(icon, Style::default().fg(theme.accent)));
+        if let Some(entry) = app.boot_entry().filter(|_| action == Action::Reboot) {
+            spans.push(Span::styled(
+                format!(" {}", entry.label),
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+            ));
+        }
+    }
+    spans
 }
 
-/// Render the main form area
-fn render_form(frame: &mut Frame, app: &mut App, area: Rect) {
+/// Most failed units listed at once
+const UNIT_ROWS: u16 = 12;
+
+/// Scrollable list of failed units over the middle of the screen
+fn render_failed_units(frame: &mut Frame, app: &App, area: Rect) {
     let theme = &app.theme;
+    let units = &app.status.failed_units;
+    let rows = u16::try_from(units.len()).unwrap_or(UNIT_ROWS).min(UNIT_ROWS);
+    let width = form_width(area.width).max(40).min(area.width);
+    let height = (rows + 2).min(area.height);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
 
-    // Golden ratio form width: area.width * PHI_COMP, clamped [28, 50]
-    #[allow(
-        clippy::cast_possible_truncation,
-        clippy::cast_sign_loss,
-        reason = "area dimensions are small u16 values, product fits u16"
-    )]
-    let form_width = (f32::from(area.width) * PHI_COMP)
-        .round()
-        .clamp(28.0, 50.0) as u16;
+    let lines: Vec<Line> = units
+        .iter()
+        .map(|unit| Line::from(Span::styled(unit.as_str(), Style::default().fg(theme.foreground))))
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border_set(theme.popup_border))
+        .border_style(Style::default().fg(theme.error))
+        .title(Span::styled(
+            app.status.failed_label().unwrap_or_default(),
+            Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+        ))
+        .title_bottom(Span::styled(
+            "↑↓ scroll  esc close",
+            Style::default().fg(theme.foreground).add_modifier(Modifier::DIM),
+        ))
+        .padding(Padding::horizontal(1))
+        .style(Style::default().bg(theme.background));
+    frame.render_widget(Clear, popup);
+    render_scrolled(frame, theme, popup, block, lines, app.units_scroll, theme.error);
+}
+
+/// `lines` from the scroll position in a bordered `block`, marked on its
+/// right border (drawn in `border`) by the theme's scrollbar when they don't
+/// all fit
+fn render_scrolled(
+    frame: &mut Frame,
+    theme: &Theme,
+    area: Rect,
+    block: Block,
+    lines: Vec<Line>,
+    scroll: Scroll,
+    border: Color,
+) {
+    let shown = lines.into_iter().skip(scroll.offset()).collect::<Vec<_>>();
+    frame.render_widget(Paragraph::new(shown).block(block), area);
+
+    // The right border between the corners
+    let track = area.inner(Margin {
+        vertical: 1,
+        horizontal: 0,
+    });
+    let rows = usize::from(track.height);
+    let ascii = theme.popup_border == BorderSet::Ascii;
+    match theme.scrollbar {
+        ScrollbarStyle::Bar if scroll.length() > rows => {
+            let (thumb, line) = if ascii { ("#", "|") } else { ("█", "│") };
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .thumb_symbol(thumb)
+                .thumb_style(Style::default().fg(theme.foreground))
+                .track_symbol(Some(line))
+                .track_style(Style::default().fg(border));
+            let mut state = ScrollbarState::new(scroll.length()).position(scroll.offset());
+            frame.render_stateful_widget(scrollbar, track, &mut state);
+        }
+        ScrollbarStyle::Arrows => {
+            let (above, below) = scroll.more(rows);
+            let (up, down) = if ascii { ("^", "v") } else { ("▲", "▼") };
+            let x = track.right().saturating_sub(1);
+            let style = Style::default().fg(theme.foreground);
+            if above && track.height > 0 {
+                frame.buffer_mut()[(x, track.top())].set_symbol(up).set_style(style);
+            }
+            if below && track.height > 0 {
+                let y = track.bottom() - 1;
+                frame.buffer_mut()[(x, y)].set_symbol(down).set_style(style);
+            }
+        }
+        ScrollbarStyle::Bar | ScrollbarStyle::None => {}
+    }
+}
+
+/// Logo and success message in the middle of the screen
+fn render_splash(frame: &mut Frame, app: &App, area: Rect, logo: &[String]) {
+    let theme = &app.theme;
+    let width = logo.iter().map(|row| cells(row)).max().unwrap_or(0);
+    let logo_size = (
+        u16::try_from(width).unwrap_or(u16::MAX),
+        u16::try_from(logo.len()).unwrap_or(u16::MAX),
+    );
+    // A blank row, the message and the notices
+    let text_rows = u16::try_from(app.notices.len()).map_or(u16::MAX, |rows| rows + 2);
+    let (logo_area, text_area) = splash_layout(area, logo_size, text_rows);
+
+    let rows: Vec<Line> = logo
+        .iter()
+        .map(|row| Line::from(Span::styled(row.as_str(), Style::default().fg(theme.accent))))
+        .collect();
+    frame.render_widget(Paragraph::new(rows), logo_area);
+
+    let mut text = vec![
+        Line::default(),
+        Line::from(Span::styled(
+            app.success.message.as_str(),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        )),
+    ];
+    text.extend(app.shown_notices(&app.notices).into_iter().map(|notice| {
+        Line::from(Span::styled(
+            notice,
+            Style::default().fg(theme.foreground).add_modifier(Modifier::ITALIC),
+        ))
+    }));
+    frame.render_widget(Paragraph::new(text).alignment(Alignment::Center), text_area);
+}
+
+/// Logo of `(width, height)` centered in `area` as one block, so its rows
+/// stay aligned, with `text_rows` below. Both are clamped to `area`; the
+/// logo gives way first.
+fn splash_layout(area: Rect, (width, height): (u16, u16), text_rows: u16) -> (Rect, Rect) {
+    let text_rows = text_rows.min(area.height);
+    let height = height.min(area.height - text_rows);
+    let width = width.min(area.width);
+    let top = area.y + (area.height - height - text_rows) / 2;
+    let logo = Rect::new(area.x + (area.width - width) / 2, top, width, height);
+    let text = Rect::new(area.x, logo.bottom(), area.width, text_rows);
+    (logo, text)
+}
+
+/// Positions of the login form widgets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FormLayout {
+    avatar: Rect,
+    username: Rect,
+    password: Rect,
+    message: Rect,
+}
+
+/// Golden ratio form width: `width * PHI_COMP`, clamped [28, 50]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "area dimensions are small u16 values, product fits u16"
+)]
+fn form_width(width: u16) -> u16 {
+    (f32::from(width) * PHI_COMP).round().clamp(28.0, 50.0) as u16
+}
+
+/// Compute the form layout inside `area` for a message of `message_height` rows.
+/// Widgets are clamped to `area`, so tiny terminals squash the form instead
+/// of drawing outside the buffer.
+fn form_layout(area: Rect, has_avatar: bool, message_height: u16) -> FormLayout {
+    let form_width = form_width(area.width);
 
     // Avatar height adapts: 10 with image, 5 for icon; shrinks to fit terminal
-    // Non-avatar portion: gap(2) + user(3) + gap(1) + pass(3) + gap(1) + msg(1) = 11
-    let base_height: u16 = 11;
-    let desired_avatar: u16 = if app.avatar.is_some() { 10 } else { 5 };
+    // Non-avatar portion: gap(2) + user(3) + gap(1) + pass(3) + gap(1) + msg
+    let base_height: u16 = 10 + message_height;
+    let desired_avatar: u16 = if has_avatar { 10 } else { 5 };
     let avatar_height = desired_avatar.min(area.height.saturating_sub(base_height).max(3));
     let form_height = avatar_height + base_height;
 
@@ -113,33 +493,378 @@ fn render_form(frame: &mut Frame, app: &mut App, area: Rect) {
             .round()
             .clamp(0.0, f32::from(area.height.saturating_sub(form_height))) as u16;
     let x = area.x + area.width.saturating_sub(form_width) / 2;
-    let form_area = Rect::new(x, y, form_width, form_height);
 
-    // Avatar: full form width, adaptive height
-    let avatar_area = Rect::new(form_area.x, form_area.y, form_width, avatar_height);
+    // Offsets derived from avatar height
+    let user_y = y.saturating_add(avatar_height + 2);
+    let pass_y = user_y.saturating_add(4);
+    let msg_y = pass_y.saturating_add(4);
+
+    FormLayout {
+        avatar: Rect::new(x, y, form_width, avatar_height).clamp(area),
+        username: Rect::new(x, user_y, form_width, 3).clamp(area),
+        password: Rect::new(x, pass_y, form_width, 3).clamp(area),
+        message: Rect::new(x, msg_y, form_width, message_height).clamp(area),
+    }
+}
+
+/// Styled text for the message area below the form. Auth stack notices
+/// (Kerberos ticket status) follow the confirmation in italics.
+fn message_segments(app: &App) -> Vec<(Cow<'_, str>, Style)> {
+    let theme = &app.theme;
+    let mut segments: Vec<(Cow<str>, Style)> = Vec::new();
+    // Nothing else can be answered without one
+    if app.status.no_keyboard {
+        segments.push((
+            Cow::Borrowed("no keyboard detected — connect a keyboard"),
+            Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+        ));
+    } else if let Context::Confirm(action) = app.focus.top() {
+        segments.push((
+            Cow::Borrowed(action.question()),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        ));
+        segments.extend(remote_warning(app));
+        segments.push((
+            Cow::Borrowed("enter to confirm, esc to cancel"),
+            Style::default().fg(theme.foreground).add_modifier(Modifier::DIM),
+        ));
+    } else if let Some(ref handoff) = app.handoff {
+        segments.extend(handoff_segments(app, handoff));
+    } else if let Some(remaining) = app.idle_countdown() {
+        segments.push((
+            Cow::Owned(format!("suspending in {}s", remaining.as_secs())),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        ));
+        segments.push((
+            Cow::Borrowed("press any key to stay awake"),
+            Style::default().fg(theme.foreground).add_modifier(Modifier::DIM),
+        ));
+    } else if let Some(ref hold) = app.hold {
+        segments.push((
+            Cow::Borrowed(hold.action.hold_prompt()),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        ));
+        segments.push((
+            Cow::Owned(progress_bar(hold.progress(Instant::now()))),
+            Style::default().fg(theme.accent),
+        ));
+        segments.extend(remote_warning(app));
+    } else if let Some(ref pending) = app.power {
+        segments.push((
+            pending.message(),
+            Style::default().fg(theme.foreground),
+        ));
+    } else if app.submission == Submission::Granted {
+        segments.push((
+            Cow::Borrowed(app.success.message.as_str()),
+            Style::default().fg(theme.accent),
+        ));
+        for notice in app.shown_notices(&app.notices) {
+            segments.push((
+                notice,
+                Style::default().fg(theme.foreground).add_modifier(Modifier::ITALIC),
+            ));
+        }
+    } else if let Some(ref err) = app.error {
+        segments.push((theme.message(err), Style::default().fg(theme.error)));
+        if let Some(attempt) = app.attempt_label() {
+            segments.push((
+                Cow::Owned(attempt),
+                Style::default().fg(theme.foreground).add_modifier(Modifier::DIM),
+            ));
+        }
+    } else if let Some(ref prompt) = app.prompt {
+        // The question itself is the password field's label
+        for notice in app.shown_notices(&prompt.notices) {
+            segments.push((
+                notice,
+                Style::default().fg(theme.foreground),
+            ));
+        }
+    } else if app.submission == Submission::Sent {
+        let spinner = submission::spinner(app.sent_at.elapsed());
+        segments.push((
+            Cow::Owned(format!("{spinner} authenticating")),
+            Style::default().fg(theme.foreground),
+        ));
+    } else if app.status.waiting_greetd {
+        segments.push((
+            Cow::Borrowed("waiting for greetd…"),
+            Style::default().fg(theme.foreground).add_modifier(Modifier::DIM),
+        ));
+    } else {
+        for warning in &app.status.resources {
+            segments.push((
+                Cow::Borrowed(warning.as_str()),
+                Style::default().fg(theme.error).add_modifier(Modifier::BOLD),
+            ));
+        }
+    }
+    segments
+}
+
+/// "Starting Hyprland in 3", or what to press once the countdown stopped
+fn handoff_segments(app: &App, handoff: &Handoff) -> [(Cow<'static, str>, Style); 2] {
+    let theme = &app.theme;
+    let name = &app.session().name;
+    let seconds = handoff.seconds(Instant::now());
+    let title = seconds.map_or_else(
+        || format!("Start {name}?"),
+        |seconds| format!("Starting {name} in {seconds}…"),
+    );
+    let hint = match (seconds.is_some(), app.policy.session_picker) {
+        (true, true) => Cow::Borrowed("esc to choose another session"),
+        (true, false) => Cow::Borrowed("esc to stop"),
+        (false, true) => {
+            // ↓ picks the next session even when its keys are remapped
+            let key = app.keymap.label(KeyAction::NextSession);
+            let key = key.as_deref().unwrap_or("↓");
+            Cow::Owned(format!("enter to start, {key} for another session, esc to log out"))
+        }
+        (false, false) => Cow::Borrowed("enter to start, esc to log out"),
+    };
+    [
+        (
+            Cow::Owned(title),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        ),
+        (
+            hint,
+            Style::default().fg(theme.foreground).add_modifier(Modifier::DIM),
+        ),
+    ]
+}
+
+/// Reminder while confirming a power action that others are logged in
+fn remote_warning(app: &App) -> Option<(Cow<'static, str>, Style)> {
+    let remote = app.status.remote_label()?;
+    Some((
+        Cow::Owned(format!("{remote} active")),
+        Style::default().fg(app.theme.error),
+    ))
+}
+
+/// Cells of the hold progress bar
+const PROGRESS_WIDTH: u8 = 12;
+
+/// `progress` (0 to 1) as a bar of block characters
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "progress is clamped to 0..=1, so the product fits the bar"
+)]
+fn progress_bar(progress: f64) -> String {
+    let width = usize::from(PROGRESS_WIDTH);
+    let filled = (progress.clamp(0.0, 1.0) * f64::from(PROGRESS_WIDTH)).round() as usize;
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Render the main form area; returns the area of the fields, which the
+/// screensaver leaves blank
+fn render_form(frame: &mut Frame, app: &mut App, area: Rect) -> Rect {
+    let theme = &app.theme;
 
+    // The message grows to fit (up to the configured rows) and pushes the form up
+    let segments = message_segments(app);
+    let width = form_width(area.width).min(area.width);
+    let mut message: Vec<Line> = segments
+        .iter()
+        .flat_map(|(text, style)| {
+            wrap(text, width, theme.message_lines)
+                .into_iter()
+                .map(|line| Line::from(Span::styled(line, *style)))
+        })
+        .collect();
+    message.truncate(usize::from(theme.message_lines));
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "message is truncated to message_lines rows"
+    )]
+    let message_height = (message.len() as u16).max(1);
+    let layout = form_layout(area, app.avatar.is_some(), message_height);
+
+    render_avatar(frame, app, layout.avatar);
+    let theme = &app.theme;
+
+    // The greeting leaves a blank row above the avatar, when there's room
+    if let Some(ref greeting) = app.greeting {
+        if layout.avatar.y >= area.y + 2 {
+            let now = Local::now();
+            let text = theme.message(greeting.text(&now, &now));
+            let text = match app.typewriter.as_mut() {
+                Some(typewriter) => Cow::Owned(typewriter.reveal(&text, Instant::now())),
+                None => text,
+            };
+            let style = Style::default().fg(theme.foreground).add_modifier(Modifier::BOLD);
+            let row = Rect::new(area.x, layout.avatar.y - 2, area.width, 1);
+            frame.render_widget(
+                Paragraph::new(Span::styled(text, style)).alignment(Alignment::Center),
+                row,
+            );
+        }
+    }
+
+    if app.username_field == UsernameField::Collapsed {
+        // Password-first: name the remembered user on the field's middle row
+        let mut line = Line::from(Span::styled(
+            app.username.as_str(),
+            Style::default().fg(theme.foreground),
+        ));
+        if let Some(key) = app.keymap.label(KeyAction::EditUsername) {
+            line.push_span(Span::styled(
+                format!("  {key} to change"),
+                Style::default().fg(theme.foreground).add_modifier(Modifier::DIM),
+            ));
+        }
+        let row = add_margin(layout.username, 0, 1);
+        frame.render_widget(Paragraph::new(line).alignment(Alignment::Center), row);
+    } else {
+        render_input(
+            frame,
+            &app.username_display(),
+            app.username_placeholder(),
+            app.focus.is_focused(Field::Username),
+            app.username_field == UsernameField::Locked,
+            theme,
+            layout.username,
+        );
+    }
+
+    let username_focused = app.focus.is_focused(Field::Username);
+    let password_focused = app.focus.is_focused(Field::Password);
+    if let Some(ref label) = app.fields.username.label {
+        render_label(frame, label, layout.username, username_focused, theme);
+    }
+    if let Some(ref prompt) = app.prompt {
+        render_label(frame, &prompt.message, layout.password, password_focused, theme);
+    } else if let Some(ref label) = app.fields.password.label {
+        render_label(frame, label, layout.password, password_focused, theme);
+    }
+    if let Some(ref hint) = app.hint {
+        let field = match hint.field {
+            Field::Username => layout.username,
+            Field::Password => layout.password,
+        };
+        render_hint(frame, hint, field, theme);
+    }
+
+    render_input(
+        frame,
+        &app.masked_password(),
+        &app.password_placeholder(),
+        app.focus.is_focused(Field::Password),
+        false,
+        theme,
+        layout.password,
+    );
+
+    frame.render_widget(
+        Paragraph::new(message).alignment(Alignment::Center),
+        layout.message,
+    );
+    layout.username.union(layout.password)
+}
+
+/// `text` (digits and `:`) in block glyphs, one string per row.
+/// Other characters are skipped; see [`big_text_rest`].
+fn big_text(text: &str) -> Vec<String> {
+    let glyphs: Vec<&[&str; BIG_ROWS]> = text
+        .chars()
+        .filter_map(|c| match c {
+            ':' => Some(&BIG_GLYPHS[10]),
+            _ => c.to_digit(10).and_then(|d| BIG_GLYPHS.get(d as usize)),
+        })
+        .collect();
+    (0..BIG_ROWS)
+        .map(|row| {
+            glyphs
+                .iter()
+                .map(|glyph| glyph[row])
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+/// What `big_text` leaves out of `text`, e.g. the `PM` of `12:34 PM`
+fn big_text_rest(text: &str) -> String {
+    text.split(|c: char| c == ':' || c.is_ascii_digit())
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Greedy word wrap of `text` into at most `max_lines` rows of `width` columns.
+/// Words wider than a row are split; text beyond the last row is cut with `…`.
+fn wrap(text: &str, width: u16, max_lines: u16) -> Vec<String> {
+    let width = usize::from(width.max(1));
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let sep = usize::from(!current.is_empty());
+        if cells(&current) + sep + cells(word) <= width {
+            if sep == 1 {
+                current.push(' ');
+            }
+            current.push_str(word);
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        for c in word.chars() {
+            if !current.is_empty() && cells(&current) + c.width().unwrap_or(0) > width {
+                lines.push(std::mem::take(&mut current));
+            }
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    let max_lines = usize::from(max_lines.max(1));
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            while !last.is_empty() && cells(last) + 1 > width {
+                last.pop();
+            }
+            last.push('…');
+        }
+    }
+    lines
+}
+
+/// Render the avatar (or the placeholder icon) in its frame, with the
+/// caption on the row below
+fn render_avatar(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = &app.theme;
+    if let Some(caption) = app.avatar_caption() {
+        let row = Rect::new(area.x, area.bottom(), area.width, 1).clamp(frame.area());
+        let caption = Span::styled(caption, Style::default().fg(theme.foreground));
+        frame.render_widget(Paragraph::new(caption).alignment(Alignment::Center), row);
+    }
+
+    // The avatar frame flashes in the accent color once access is granted
+    let avatar_border = if app.submission == Submission::Granted {
+        theme.accent
+    } else {
+        theme.foreground
+    };
+    let borders = if theme.avatar_border.is_some() { Borders::ALL } else { Borders::NONE };
     let avatar_block = Block::default()
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(theme.foreground))
+        .borders(borders)
+        .border_set(border_set(theme.avatar_border.unwrap_or_default()))
+        .border_style(Style::default().fg(avatar_border))
+        .padding(Padding::uniform(theme.avatar.padding))
         .style(Style::default().bg(theme.background));
 
     if let Some(ref mut avatar) = app.avatar {
-        let inner = avatar_block.inner(avatar_area);
-        frame.render_widget(avatar_block, avatar_area);
-
-        // Center image: with halfblocks + font(4,8), cells are effectively square.
-        // Compute how many columns the fit image occupies, then offset.
-        let img_cols = (f32::from(inner.height) * avatar.aspect_ratio).round();
-        #[allow(
-            clippy::cast_possible_truncation,
-            clippy::cast_sign_loss,
-            reason = "result is small positive u16"
-        )]
-        let x_offset = (f32::from(inner.width).max(img_cols) - img_cols) as u16 / 2;
-        let centered = Rect::new(inner.x + x_offset, inner.y, inner.width.saturating_sub(x_offset * 2), inner.height);
-
-        let image = StatefulImage::default().resize(Resize::Fit(None));
-        frame.render_stateful_widget(image, centered, &mut avatar.protocol);
+        let inner = avatar_block.inner(area);
+        frame.render_widget(avatar_block, area);
+        avatar.render(frame, inner);
     } else {
         let icon = Paragraph::new(Line::from(Span::styled(
             "󰀄",
@@ -147,106 +872,383 @@ fn render_form(frame: &mut Frame, app: &mut App, area: Rect) {
         )))
         .alignment(Alignment::Center)
         .block(avatar_block);
-        frame.render_widget(icon, avatar_area);
+        frame.render_widget(icon, area);
     }
+}
 
-    // Offsets derived from avatar height
-    let user_y = form_area.y + avatar_height + 2;
-    let pass_y = user_y + 4;
-    let msg_y = pass_y + 4;
-
-    let username_area = Rect::new(form_area.x, user_y, form_width, 3);
-    render_input(
-        frame,
-        &app.username,
-        "username",
-        app.focus == Focus::Username,
-        theme.foreground,
-        theme.accent,
-        theme.background,
-        username_area,
-    );
+/// Display width in cells; control characters take none
+fn cells(text: &str) -> usize {
+    text.chars().map(|c| c.width().unwrap_or(0)).sum()
+}
 
-    let password_area = Rect::new(form_area.x, pass_y, form_width, 3);
-    let masked_password = "*".repeat(app.password.len());
-    render_input(
-        frame,
-        &masked_password,
-        "password",
-        app.focus == Focus::Password,
-        theme.foreground,
-        theme.accent,
-        theme.background,
-        password_area,
-    );
+/// Render a field label on the row above `field`, which the form keeps free
+fn render_label(frame: &mut Frame, label: &str, field: Rect, focused: bool, theme: &Theme) {
+    if field.y == 0 {
+        return;
+    }
+    let row = Rect::new(field.x + 1, field.y - 1, field.width.saturating_sub(2), 1);
+    let style = if focused && theme.focus_indicator == FocusIndicator::Inverted {
+        Style::default().fg(theme.foreground).add_modifier(Modifier::REVERSED)
+    } else {
+        Style::default().fg(theme.foreground).add_modifier(Modifier::DIM)
+    };
+    let label = Paragraph::new(Span::styled(label, style)).alignment(start(theme));
+    frame.render_widget(label, row.clamp(frame.area()));
+}
 
-    let msg_area = Rect::new(form_area.x, msg_y, form_width, 1);
-    if let Some(ref err) = app.error {
-        let error = Paragraph::new(Line::from(Span::styled(
-            err.to_uppercase(),
-            Style::default().fg(theme.error),
-        )))
-        .alignment(Alignment::Center);
-        frame.render_widget(error, msg_area);
-    } else if app.authenticating {
-        let status = Paragraph::new(Line::from(Span::styled(
-            "authenticating...",
-            Style::default().fg(theme.foreground),
-        )))
-        .alignment(Alignment::Center);
-        frame.render_widget(status, msg_area);
+/// Render a hint on the row under `field`. Under the username that's the
+/// password label's row, which the hint takes while shown.
+fn render_hint(frame: &mut Frame, hint: &Hint, field: Rect, theme: &Theme) {
+    if field.bottom() >= frame.area().bottom() {
+        return;
     }
+    let row = Rect::new(field.x + 1, field.bottom(), field.width.saturating_sub(2), 1);
+    let style = if hint.error {
+        Style::default().fg(theme.error)
+    } else {
+        Style::default().fg(theme.foreground).add_modifier(Modifier::DIM)
+    };
+    let text = Span::styled(theme.message(&hint.text), style);
+    let row = row.clamp(frame.area());
+    // The label under it may be longer
+    frame.render_widget(Clear, row);
+    frame.render_widget(Paragraph::new(text).alignment(start(theme)), row);
 }
 
-/// Render a single input field
-#[allow(clippy::too_many_arguments, reason = "render helper takes individual style params")]
+/// Render a single input field in the theme's input style. The text sits
+/// on the middle row in every style, so the cursor position doesn't change.
+/// Right to left the text keeps to the right and the cursor to its left.
+/// A locked field is read-only: dimmed, behind a lock, without a cursor.
+/// Focus shows in the accent color and the theme's focus indicator.
 fn render_input(
     frame: &mut Frame,
     value: &str,
     placeholder: &str,
     focused: bool,
-    fg: ratatui::style::Color,
-    accent: ratatui::style::Color,
-    bg: ratatui::style::Color,
+    locked: bool,
+    theme: &Theme,
     area: Rect,
 ) {
-    let border_color = if focused { accent } else { fg };
+    let highlight = if focused { theme.accent } else { theme.foreground };
+    // Filled fields draw the text in the background color on a solid bar
+    let (text, fill) = if theme.input_style == InputStyle::Filled {
+        (theme.background, highlight)
+    } else {
+        (theme.foreground, theme.background)
+    };
 
-    let display = if value.is_empty() {
+    let display = if locked {
+        Span::styled(
+            format!("{LOCK} {value}"),
+            Style::default().fg(text).add_modifier(Modifier::DIM),
+        )
+    } else if value.is_empty() {
         Span::styled(
             placeholder,
-            Style::default().fg(fg).add_modifier(Modifier::DIM),
+            Style::default().fg(text).add_modifier(Modifier::DIM),
         )
     } else {
-        Span::styled(value, Style::default().fg(fg))
+        Span::styled(value, Style::default().fg(text))
     };
 
-    let input = Paragraph::new(Line::from(display)).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(border_color))
-            .style(Style::default().bg(bg)),
-    );
-
-    frame.render_widget(input, area);
+    // Heavy lines need box-drawing characters
+    let border = match theme.focus_indicator {
+        FocusIndicator::Heavy if focused && theme.input_border != BorderSet::Ascii => {
+            BorderSet::Thick
+        }
+        FocusIndicator::Color
+        | FocusIndicator::Arrow
+        | FocusIndicator::Inverted
+        | FocusIndicator::Heavy => theme.input_border,
+    };
+    let block = Block::default()
+        .border_set(border_set(border))
+        .border_style(Style::default().fg(highlight))
+        .style(Style::default().bg(fill));
+    let block = match theme.input_style {
+        InputStyle::Box => block.borders(Borders::ALL),
+        InputStyle::Underline => block.borders(Borders::BOTTOM).padding(Padding::new(1, 1, 1, 0)),
+        InputStyle::Filled | InputStyle::Minimal => block.padding(Padding::uniform(1)),
+    };
+    let text = Paragraph::new(Line::from(display)).alignment(start(theme));
+    frame.render_widget(text.block(block), area);
+    if focused && theme.focus_indicator == FocusIndicator::Arrow {
+        render_focus_arrow(frame, area, theme);
+    }
 
     // Show cursor if focused
-    if focused {
-        #[allow(clippy::cast_possible_truncation, reason = "input limited to ~30 chars, fits u16")]
-        let cursor_x = area.x + 1 + value.len() as u16;
+    if focused && !locked {
+        // Wide characters (kana, CJK) take two cells
+        let width = u16::try_from(cells(value)).unwrap_or(u16::MAX);
+        let cursor_x = if theme.rtl {
+            (area.x + area.width).checked_sub(width.saturating_add(2))
+        } else {
+            area.x.checked_add(1 + width)
+        };
         let cursor_y = area.y + 1;
-        if cursor_x < area.x + area.width - 1 {
+        let inside = area.x + 1..(area.x + area.width).saturating_sub(1);
+        if let Some(cursor_x) = cursor_x.filter(|x| inside.contains(x) && area.height > 2) {
             frame.set_cursor_position((cursor_x, cursor_y));
         }
     }
 }
 
-/// Add margin to a rect
+/// Arrow pointing at the focused field from the side its text starts on
+fn render_focus_arrow(frame: &mut Frame, field: Rect, theme: &Theme) {
+    let ascii = theme.input_border == BorderSet::Ascii;
+    let (x, arrow) = if theme.rtl {
+        (field.right().checked_add(1), if ascii { "<" } else { "\u{25c0}" })
+    } else {
+        (field.x.checked_sub(2), if ascii { ">" } else { "\u{25b6}" })
+    };
+    let Some(x) = x.filter(|&x| x < frame.area().right()) else {
+        return;
+    };
+    let row = Rect::new(x, field.y + field.height / 2, 1, 1).clamp(frame.area());
+    let style = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+    frame.render_widget(Span::styled(arrow, style), row);
+}
+
+/// Characters of a border set
+const fn border_set(set: BorderSet) -> border::Set {
+    match set {
+        BorderSet::Plain => border::PLAIN,
+        BorderSet::Rounded => border::ROUNDED,
+        BorderSet::Double => border::DOUBLE,
+        BorderSet::Thick => border::THICK,
+        BorderSet::Ascii => ASCII_BORDER,
+    }
+}
+
+/// Add margin to a rect; margins larger than the rect collapse it in place
 const fn add_margin(area: Rect, horizontal: u16, vertical: u16) -> Rect {
+    let horizontal = if horizontal > area.width / 2 { area.width / 2 } else { horizontal };
+    let vertical = if vertical > area.height / 2 { area.height / 2 } else { vertical };
     Rect {
         x: area.x + horizontal,
         y: area.y + vertical,
-        width: area.width.saturating_sub(horizontal * 2),
-        height: area.height.saturating_sub(vertical * 2),
+        width: area.width - horizontal * 2,
+        height: area.height - vertical * 2,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    /// `inner` lies entirely within `outer`
+    const fn contains(outer: Rect, inner: Rect) -> bool {
+        inner.x >= outer.x
+            && inner.y >= outer.y
+            && inner.x + inner.width <= outer.x + outer.width
+            && inner.y + inner.height <= outer.y + outer.height
+    }
+
+    /// Rows of a 12x3 input field drawn with `theme`, and the cursor column
+    fn draw_field(theme: &Theme, value: &str) -> (Vec<String>, u16) {
+        let mut terminal = Terminal::new(TestBackend::new(12, 3)).unwrap();
+        terminal
+            .draw(|frame| render_input(frame, value, "user", true, false, theme, frame.area()))
+            .unwrap();
+        let cursor = terminal.get_cursor_position().unwrap().x;
+        let buffer = terminal.backend().buffer();
+        let rows = (0..3)
+            .map(|y| (0..12).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        (rows, cursor)
+    }
+
+    /// Rows of a 12x3 input field drawn in `style`
+    fn field_rows(style: InputStyle, value: &str) -> Vec<String> {
+        let theme = Theme {
+            input_style: style,
+            ..Theme::default()
+        };
+        draw_field(&theme, value).0
+    }
+
+    #[test]
+    fn test_hint_covers_label() {
+        let theme = Theme::default();
+        let username = Rect::new(0, 0, 20, 3);
+        let password = Rect::new(0, 4, 20, 3);
+        let hint = Hint::error(Field::Username, "Too long");
+        let mut terminal = Terminal::new(TestBackend::new(20, 7)).unwrap();
+        terminal
+            .draw(|frame| {
+                render_label(frame, "a much longer label", password, false, &theme);
+                render_hint(frame, &hint, username, &theme);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let row: String = (1..19).map(|x| buffer[(x, 3)].symbol()).collect();
+        // Nothing of the longer label is left after the hint
+        assert_eq!(row, "TOO LONG          ");
+    }
+
+    #[test]
+    fn test_focus_indicators() {
+        let with = |focus_indicator| Theme {
+            focus_indicator,
+            ..Theme::default()
+        };
+        let heavy = with(FocusIndicator::Heavy);
+        assert_eq!(draw_field(&heavy, "me").0[0], "┏━━━━━━━━━━┓");
+
+        // The arrow sits two columns left of the field
+        let mut terminal = Terminal::new(TestBackend::new(14, 3)).unwrap();
+        let theme = with(FocusIndicator::Arrow);
+        let field = Rect::new(2, 0, 12, 3);
+        terminal
+            .draw(|frame| render_input(frame, "me", "", true, false, &theme, field))
+            .unwrap();
+        let row: String = (0..4).map(|x| terminal.backend().buffer()[(x, 1)].symbol()).collect();
+        assert_eq!(row, "\u{25b6} │m");
+    }
+
+    #[test]
+    fn test_right_to_left_field() {
+        let rtl = Theme::default().with_rtl(true);
+        let (rows, cursor) = draw_field(&rtl, "me");
+        assert_eq!(rows[1], "│        me│");
+        assert_eq!(cursor, 8);
+        assert_eq!(draw_field(&rtl, "").1, 10);
+        assert_eq!(draw_field(&Theme::default(), "me").1, 3);
+    }
+
+    /// Right border of a 6x6 box of ten lines scrolled `down` times
+    fn scrollbar_column(scrollbar: ScrollbarStyle, down: usize) -> String {
+        let theme = Theme {
+            scrollbar,
+            ..Theme::default()
+        };
+        let mut scroll = Scroll::new(10);
+        for _ in 0..down {
+            scroll.down();
+        }
+        let lines = (0..10).map(|line| Line::from(line.to_string())).collect();
+        let mut terminal = Terminal::new(TestBackend::new(6, 6)).unwrap();
+        terminal
+            .draw(|frame| {
+                let block = Block::default().borders(Borders::ALL);
+                render_scrolled(frame, &theme, frame.area(), block, lines, scroll, theme.error);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..6).map(|y| buffer[(5, y)].symbol()).collect()
+    }
+
+    #[test]
+    fn test_scrollbars() {
+        assert_eq!(scrollbar_column(ScrollbarStyle::Bar, 0), "┐█│││┘");
+        assert_eq!(scrollbar_column(ScrollbarStyle::Bar, 9), "┐│││█┘");
+        assert_eq!(scrollbar_column(ScrollbarStyle::Arrows, 0), "┐│││▼┘");
+        assert_eq!(scrollbar_column(ScrollbarStyle::Arrows, 3), "┐▲││▼┘");
+        assert_eq!(scrollbar_column(ScrollbarStyle::None, 3), "┐││││┘");
+    }
+
+    #[test]
+    fn test_input_styles() {
+        assert_eq!(
+            field_rows(InputStyle::Box, "me"),
+            ["┌──────────┐", "│me        │", "└──────────┘"]
+        );
+        assert_eq!(
+            field_rows(InputStyle::Underline, "me"),
+            ["            ", " me         ", "────────────"]
+        );
+        assert_eq!(
+            field_rows(InputStyle::Minimal, ""),
+            ["            ", " user       ", "            "]
+        );
+    }
+
+    fn area() -> impl Strategy<Value = Rect> {
+        (0..200u16, 0..200u16, 0..1000u16, 0..500u16)
+            .prop_map(|(x, y, width, height)| Rect::new(x, y, width, height))
+    }
+
+    #[test]
+    fn test_splash_layout() {
+        let area = Rect::new(0, 0, 80, 24);
+        let (logo, text) = splash_layout(area, (20, 6), 3);
+        assert_eq!(logo, Rect::new(30, 7, 20, 6));
+        assert_eq!(text, Rect::new(0, 13, 80, 3));
+
+        // A logo too big for the screen is cut before the message
+        let (logo, text) = splash_layout(Rect::new(0, 0, 10, 4), (20, 6), 3);
+        assert_eq!(logo, Rect::new(0, 0, 10, 1));
+        assert_eq!(text, Rect::new(0, 1, 10, 3));
+    }
+
+    #[test]
+    fn test_big_text() {
+        let rows = big_text("12:34");
+        assert_eq!(rows.len(), BIG_ROWS);
+        assert!(rows.iter().all(|row| cells(row) == cells(&rows[0])));
+        assert_eq!(rows[0], " █  ███   ███ █ █");
+        assert_eq!(big_text_rest("12:34"), "");
+        assert_eq!(big_text_rest("08:05 PM"), "PM");
+        assert_eq!(big_text_rest("下午3:04"), "下午");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("authentication failed", 30, 3), ["authentication failed"]);
+        assert_eq!(
+            wrap("authentication token manipulation error", 16, 3),
+            ["authentication", "token", "manipulation…"]
+        );
+        assert_eq!(
+            wrap("token manipulation error", 16, 4),
+            ["token", "manipulation", "error"]
+        );
+        assert_eq!(wrap("abcdefgh", 3, 3), ["abc", "def", "gh"]);
+        assert!(wrap("", 10, 3).is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn form_stays_within_area(area in area(), has_avatar in any::<bool>(), message in 1..=3u16) {
+            let layout = form_layout(area, has_avatar, message);
+            for rect in [layout.avatar, layout.username, layout.password, layout.message] {
+                prop_assert!(contains(area, rect), "{rect:?} outside {area:?}");
+            }
+        }
+
+        #[test]
+        fn form_keeps_widget_order(area in area(), has_avatar in any::<bool>(), message in 1..=3u16) {
+            let layout = form_layout(area, has_avatar, message);
+            prop_assert!(layout.avatar.y <= layout.username.y);
+            prop_assert!(layout.username.y <= layout.password.y);
+            prop_assert!(layout.password.y <= layout.message.y);
+        }
+
+        #[test]
+        fn form_fits_unclamped_when_room(width in 28..1000u16, height in 16..500u16, has_avatar in any::<bool>(), message in 1..=3u16) {
+            let area = Rect::new(0, 3, width, height);
+            let layout = form_layout(area, has_avatar, message);
+            prop_assert_eq!(layout.username.height, 3);
+            prop_assert_eq!(layout.password.y, layout.username.y + 4);
+            prop_assert_eq!(layout.message.y, layout.password.y + 4);
+            prop_assert_eq!(layout.message.height, message);
+        }
+
+        #[test]
+        fn wrap_respects_limits(text in ".{0,200}", width in 0..60u16, max_lines in 0..5u16) {
+            let lines = wrap(&text, width, max_lines);
+            prop_assert!(lines.len() <= usize::from(max_lines.max(1)));
+            for line in lines {
+                prop_assert!(cells(&line) <= usize::from(width.max(1)).max(2));
+            }
+        }
+
+        #[test]
+        fn margin_stays_within_area(area in area(), horizontal in 0..10u16, vertical in 0..10u16) {
+            prop_assert!(contains(area, add_margin(area, horizontal, vertical)));
+        }
     }
 }
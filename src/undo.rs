@@ -0,0 +1,108 @@
+//! Undo for the form fields
+//!
+//! Ctrl+Z brings back what a field held before Ctrl+W cleared it or a run
+//! of Backspace ate into it. Only a few steps are kept, and the password's
+//! are dropped as soon as it is sent, so a submitted password can't be
+//! called back onto an unattended screen.
+
+use crate::focus::Field;
+
+/// Steps kept; older ones fall off
+const DEPTH: usize = 8;
+
+#[derive(Debug, Default)]
+pub struct Undo {
+    /// Field contents before each step, oldest first
+    steps: Vec<(Field, String)>,
+    /// Field a run of Backspace is going on in; its start is saved
+    erasing: Option<Field>,
+}
+
+impl Undo {
+    /// `field` holding `content` is about to be cleared
+    pub fn clear(&mut self, field: Field, content: &str) {
+        self.erasing = None;
+        self.save(field, content);
+    }
+
+    /// A character of `field` is about to be erased; a run of Backspace is
+    /// one step
+    pub fn erase(&mut self, field: Field, content: &str) {
+        if self.erasing != Some(field) {
+            self.erasing = Some(field);
+            self.save(field, content);
+        }
+    }
+
+    /// Something was typed, so the next Backspace starts a new step
+    pub const fn typed(&mut self) {
+        self.erasing = None;
+    }
+
+    fn save(&mut self, field: Field, content: &str) {
+        if content.is_empty() {
+            return;
+        }
+        if self.steps.len() == DEPTH {
+            self.steps.remove(0);
+        }
+        self.steps.push((field, content.to_string()));
+    }
+
+    /// The last step, with what its field held before it
+    pub fn pop(&mut self) -> Option<(Field, String)> {
+        self.erasing = None;
+        self.steps.pop()
+    }
+
+    /// Drop every step of `field` (the password once it was sent)
+    pub fn forget(&mut self, field: Field) {
+        self.steps.retain(|&(step_field, _)| step_field != field);
+        if self.erasing == Some(field) {
+            self.erasing = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_steps() {
+        let mut undo = Undo::default();
+        // Three Backspaces in a row, then typing and one more
+        undo.erase(Field::Username, "alice");
+        undo.erase(Field::Username, "alic");
+        undo.erase(Field::Username, "ali");
+        undo.typed();
+        undo.erase(Field::Username, "alx");
+        undo.clear(Field::Password, "hunter2");
+        undo.clear(Field::Password, "");
+
+        assert_eq!(undo.pop(), Some((Field::Password, "hunter2".to_string())));
+        assert_eq!(undo.pop(), Some((Field::Username, "alx".to_string())));
+        assert_eq!(undo.pop(), Some((Field::Username, "alice".to_string())));
+        assert_eq!(undo.pop(), None);
+    }
+
+    #[test]
+    fn test_bounded_and_forgetful() {
+        let mut undo = Undo::default();
+        undo.clear(Field::Password, "hunter2");
+        undo.clear(Field::Username, "alice");
+        undo.forget(Field::Password);
+        assert_eq!(undo.pop(), Some((Field::Username, "alice".to_string())));
+        assert_eq!(undo.pop(), None);
+
+        for i in 0..=DEPTH {
+            undo.clear(Field::Username, &i.to_string());
+        }
+        let mut left = Vec::new();
+        while let Some((_, content)) = undo.pop() {
+            left.push(content);
+        }
+        assert_eq!(left.len(), DEPTH);
+        assert_eq!(left.last().map(String::as_str), Some("1"));
+    }
+}
@@ -0,0 +1,93 @@
+//! Post-auth unlock hook (opt-in, `unlock-hook` feature)
+//!
+//! Per-user encrypted homes outside systemd-homed (fscrypt, LUKS via
+//! systemd-cryptsetup) need the login password once more. After a successful
+//! login the configured helper gets it on stdin — never in argv, where other
+//! users could read it from `/proc`.
+
+#[cfg(feature = "unlock-hook")]
+use std::io::Write;
+#[cfg(feature = "unlock-hook")]
+use std::process::{Command, Stdio};
+
+use crate::config::UnlockConfig;
+#[cfg(feature = "unlock-hook")]
+use crate::status;
+
+/// Run the unlock helper for `username`, writing `password` to its stdin.
+/// Returns the helper's complaint when it fails.
+#[cfg(feature = "unlock-hook")]
+pub fn run(config: &UnlockConfig, username: &str, password: &str) -> Result<(), String> {
+    let args = expand_args(&config.command, username)?;
+    let (program, rest) = args.split_first().ok_or("Unlock command is empty")?;
+
+    let mut child = Command::new(program)
+        .args(rest)
+        .env("GRXXT_USER", username)
+        .envs(status::greeter_session().map(|id| ("GRXXT_SESSION_ID", id)))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Unlock hook failed: {e}"))?;
+
+    // Dropping stdin after the write closes it, so the helper sees EOF
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(password.as_bytes())
+            .map_err(|e| format!("Unlock hook failed: {e}"))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Unlock hook failed: {e}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .map_or_else(
+            || format!("Unlock hook failed: {}", output.status),
+            |line| format!("Unlock hook failed: {}", line.trim()),
+        ))
+}
+
+/// Built without the hook: report that instead of silently skipping the unlock
+#[cfg(not(feature = "unlock-hook"))]
+pub fn run(_config: &UnlockConfig, _username: &str, _password: &str) -> Result<(), String> {
+    Err("Unlock hook not built (enable the `unlock-hook` feature)".into())
+}
+
+/// Split the configured command and substitute `%u` with the username
+#[cfg(feature = "unlock-hook")]
+fn expand_args(command: &str, username: &str) -> Result<Vec<String>, String> {
+    let args = shell_words::split(command).map_err(|e| format!("Invalid unlock command: {e}"))?;
+    Ok(args.iter().map(|arg| arg.replace("%u", username)).collect())
+}
+
+#[cfg(all(test, feature = "unlock-hook"))]
+#[allow(clippy::unwrap_used, reason = "tests can unwrap")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_args() {
+        assert_eq!(
+            expand_args("fscrypt unlock --quiet /home/%u", "me").unwrap(),
+            ["fscrypt", "unlock", "--quiet", "/home/me"]
+        );
+        assert!(expand_args("broken 'quote", "me").is_err());
+    }
+
+    #[test]
+    fn test_run_passes_password_on_stdin() {
+        let config = UnlockConfig {
+            command: r#"sh -c '[ "$(cat)" = hunter2 ] && [ "$GRXXT_USER" = %u ]'"#.into(),
+        };
+        assert_eq!(run(&config, "me", "hunter2"), Ok(()));
+        assert!(run(&config, "me", "wrong").is_err());
+    }
+}
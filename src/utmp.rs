@@ -0,0 +1,107 @@
+//! utmp/wtmp session accounting for the direct PAM backend
+//!
+//! greetd runs sessions itself; with `backend = "pam"` grxxt spawns them, so
+//! it records them too — otherwise `who`, `w` and `last` never see the login.
+
+use std::ffi::{c_char, CStr};
+use std::mem;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::console;
+
+const WTMP: &CStr = c"/var/log/wtmp";
+
+extern "C" {
+    // glibc/musl; not exposed by the libc crate
+    fn updwtmpx(file: *const c_char, ut: *const libc::utmpx);
+}
+
+/// A session's utmp entry, written on login and retired on logout
+pub struct Record {
+    entry: libc::utmpx,
+}
+
+impl Record {
+    /// Record `username`'s session process `pid` on the controlling TTY.
+    /// Accounting is best effort; failures are ignored.
+    pub fn login(username: &str, pid: u32) -> Self {
+        // SAFETY: utmpx is plain old data; all-zero is a valid empty entry
+        let mut entry: libc::utmpx = unsafe { mem::zeroed() };
+        let line = console::tty_line().unwrap_or_default();
+
+        entry.ut_type = libc::USER_PROCESS;
+        entry.ut_pid = i32::try_from(pid).unwrap_or_default();
+        fill(&mut entry.ut_line, &line);
+        fill(&mut entry.ut_id, line_id(&line));
+        fill(&mut entry.ut_user, username);
+
+        let mut record = Self { entry };
+        record.write();
+        record
+    }
+
+    /// Mark the session as ended
+    pub fn logout(mut self) {
+        self.entry.ut_type = libc::DEAD_PROCESS;
+        self.entry.ut_user = [0; libc::__UT_NAMESIZE];
+        self.write();
+    }
+
+    /// Stamp the entry and write it to utmp and wtmp
+    fn write(&mut self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        self.entry.ut_tv.tv_sec = now.as_secs().try_into().unwrap_or_default();
+        self.entry.ut_tv.tv_usec = now.subsec_micros().try_into().unwrap_or_default();
+
+        // SAFETY: the entry is fully initialised; the utmp calls copy it
+        unsafe {
+            libc::setutxent();
+            libc::pututxline(&raw const self.entry);
+            libc::endutxent();
+            updwtmpx(WTMP.as_ptr(), &raw const self.entry);
+        }
+    }
+}
+
+/// utmp id: the line's last four bytes, as agetty does (`tty1` → `tty1`,
+/// `tty12` → `ty12`)
+fn line_id(line: &str) -> &str {
+    let start = line
+        .char_indices()
+        .map(|(i, _)| i)
+        .find(|&i| line.len() - i <= 4)
+        .unwrap_or(line.len());
+    &line[start..]
+}
+
+/// Copy `src` into a fixed-size C field, truncating; unused bytes stay zero
+fn fill(dst: &mut [c_char], src: &str) {
+    for (slot, byte) in dst.iter_mut().zip(src.bytes()) {
+        *slot = c_char::from_ne_bytes([byte]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_id() {
+        assert_eq!(line_id("tty1"), "tty1");
+        assert_eq!(line_id("tty12"), "ty12");
+        assert_eq!(line_id("pts/3"), "ts/3");
+        assert_eq!(line_id(""), "");
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut field = [0; 4];
+        fill(&mut field, "tty12");
+        assert_eq!(field.map(|c| c.to_ne_bytes()[0]), *b"tty1");
+        let mut field = [0; 4];
+        fill(&mut field, "me");
+        assert_eq!(field.map(|c| c.to_ne_bytes()[0]), *b"me\0\0");
+    }
+}
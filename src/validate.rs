@@ -0,0 +1,99 @@
+//! Checks on the login form's fields before anything is sent
+//!
+//! Problems show on the row under the field they concern, as soon as they
+//! are typed, rather than in the message line below the form after a
+//! round-trip through PAM. Usernames are trimmed on submit, with a hint so
+//! the change isn't a surprise.
+
+use std::borrow::Cow;
+
+use crate::focus::Field;
+
+/// Longest username glibc looks up (`LOGIN_NAME_MAX` without the NUL)
+const MAX_USERNAME: usize = 255;
+
+/// A line shown under one of the fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub field: Field,
+    pub text: Cow<'static, str>,
+    /// A problem to fix, rather than a note
+    pub error: bool,
+}
+
+impl Hint {
+    pub fn error(field: Field, text: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            field,
+            text: text.into(),
+            error: true,
+        }
+    }
+
+    pub fn note(field: Field, text: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            field,
+            text: text.into(),
+            error: false,
+        }
+    }
+}
+
+/// What's wrong with a username, if anything. An empty name is fine here:
+/// it's where typing starts.
+pub fn username(name: &str) -> Option<Cow<'static, str>> {
+    if name.len() > MAX_USERNAME {
+        return Some(Cow::Borrowed("Username too long"));
+    }
+    if name.starts_with('-') {
+        return Some(Cow::Borrowed("Username can't start with '-'"));
+    }
+    // `:` separates the passwd fields and `/` would be part of a path
+    let bad = name
+        .chars()
+        .find(|&c| c.is_control() || c == ':' || c == '/')?;
+    Some(if bad.is_control() {
+        Cow::Borrowed("Username can't contain control characters")
+    } else {
+        Cow::Owned(format!("Username can't contain '{bad}'"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_username() {
+        for ok in [
+            "",
+            "alice",
+            "alice.b-c_d",
+            "ALICE@CORP.EXAMPLE",
+            "CORP\\alice",
+            "あきら",
+        ] {
+            assert_eq!(username(ok), None, "{ok}");
+        }
+        assert_eq!(
+            username("-alice").as_deref(),
+            Some("Username can't start with '-'")
+        );
+        assert_eq!(
+            username("a:b").as_deref(),
+            Some("Username can't contain ':'")
+        );
+        assert_eq!(
+            username("../root").as_deref(),
+            Some("Username can't contain '/'")
+        );
+        assert_eq!(
+            username("a\tb").as_deref(),
+            Some("Username can't contain control characters")
+        );
+        assert_eq!(
+            username(&"a".repeat(256)).as_deref(),
+            Some("Username too long")
+        );
+    }
+}